@@ -0,0 +1,370 @@
+/**
+ * Archive Module
+ *
+ * Inspects zip archives without extracting them to disk:
+ * - Listing entries with compressed/uncompressed sizes
+ * - Previewing a single entry as text or base64
+ * - Rebuilding the folder tree from the flat entry names
+ * - Extracting to a folder once the uncompressed total is known to fit
+ */
+
+use crate::file_preview::{current_preview_limits, mime_from_extension};
+use crate::filesystem::folder_tree::FileNode;
+use crate::filesystem::fs_utils::check_free_space;
+use crate::StateSafe;
+use base64::{engine::general_purpose, Engine as _};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use tauri::{command, State};
+use zip::read::ZipArchive;
+
+/// Uncompressed/compressed ratio above which an archive is flagged as a possible zip bomb
+const ZIP_BOMB_RATIO: u64 = 100;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    pub is_dir: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ArchiveListing {
+    pub entries: Vec<ArchiveEntry>,
+    pub total_compressed: u64,
+    pub total_uncompressed: u64,
+    pub suspicious_ratio: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExtractSummary {
+    pub extracted: usize,
+    pub bytes: u64,
+    /// Entries whose names would land outside the destination
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ArchiveEntryPreview {
+    Text { content: String },
+    Binary { content: String, mime: String },
+}
+
+pub(crate) fn open_archive(archive_path: &str) -> Result<ZipArchive<BufReader<File>>, String> {
+    let file = File::open(archive_path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    ZipArchive::new(BufReader::new(file)).map_err(|e| format!("Failed to read archive: {}", e))
+}
+
+/// Lists every entry of a zip from its central directory, with totals for zip-bomb warnings
+#[command(async)]
+pub fn list_archive(archive_path: String) -> Result<ArchiveListing, String> {
+    let mut archive = open_archive(&archive_path)?;
+
+    let mut entries = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index_raw(i)
+            .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+        entries.push(ArchiveEntry {
+            name: file.name().to_string(),
+            compressed_size: file.compressed_size(),
+            uncompressed_size: file.size(),
+            is_dir: file.is_dir(),
+        });
+    }
+
+    let total_compressed: u64 = entries.iter().map(|e| e.compressed_size).sum();
+    let total_uncompressed: u64 = entries.iter().map(|e| e.uncompressed_size).sum();
+
+    Ok(ArchiveListing {
+        suspicious_ratio: total_uncompressed > total_compressed.max(1).saturating_mul(ZIP_BOMB_RATIO),
+        entries,
+        total_compressed,
+        total_uncompressed,
+    })
+}
+
+/// Previews one archive entry as text, or as base64 with a MIME guess, within the preview limits
+#[command(async)]
+pub fn preview_archive_entry(
+    state_mux: State<'_, StateSafe>,
+    archive_path: String,
+    entry_name: String,
+) -> Result<ArchiveEntryPreview, String> {
+    let limits = current_preview_limits(&state_mux);
+    let mut archive = open_archive(&archive_path)?;
+    let file = archive
+        .by_name(&entry_name)
+        .map_err(|e| format!("Entry not found: {}", e))?;
+
+    if file.is_dir() {
+        return Err("Entry is a directory".to_string());
+    }
+
+    let ext = Path::new(&entry_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    let mime = mime_from_extension(ext);
+    let limit = limits.for_mime(mime).max(limits.text);
+    if file.size() > limit {
+        return Err("Entry too large to preview".to_string());
+    }
+
+    // The declared size can lie, so cap the actual read as well
+    let mut data = Vec::with_capacity(file.size() as usize);
+    file.take(limit + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read entry: {}", e))?;
+    if data.len() as u64 > limit {
+        return Err("Entry too large to preview".to_string());
+    }
+
+    if mime == "application/octet-stream" && data.len() as u64 <= limits.text {
+        if let Ok(text) = String::from_utf8(data.clone()) {
+            return Ok(ArchiveEntryPreview::Text { content: text });
+        }
+    }
+
+    Ok(ArchiveEntryPreview::Binary {
+        content: general_purpose::STANDARD.encode(&data),
+        mime: mime.to_string(),
+    })
+}
+
+/// Directory or file of an archive tree while it is being assembled
+#[derive(Default)]
+struct TreeBuilder {
+    is_dir: bool,
+    size: u64,
+    children: BTreeMap<String, TreeBuilder>,
+}
+
+impl TreeBuilder {
+    /// Adds the entry at `components`, creating any parent directories the archive
+    /// doesn't list itself
+    fn insert(&mut self, components: &[&str], is_dir: bool, size: u64) {
+        let Some((first, rest)) = components.split_first() else {
+            return;
+        };
+        let child = self.children.entry(first.to_string()).or_default();
+        if rest.is_empty() {
+            child.is_dir |= is_dir;
+            child.size = size;
+        } else {
+            child.is_dir = true;
+            child.insert(rest, is_dir, size);
+        }
+    }
+
+    /// Directories first, then files, each sorted by name
+    fn into_node(self, name: String, path: String) -> FileNode {
+        let children = self.is_dir.then(|| {
+            let (mut dirs, files): (Vec<_>, Vec<_>) = self
+                .children
+                .into_iter()
+                .map(|(child_name, child)| {
+                    let child_path = if path.is_empty() { child_name.clone() } else { format!("{}/{}", path, child_name) };
+                    child.into_node(child_name, child_path)
+                })
+                .partition(|node| node.is_dir);
+            dirs.extend(files);
+            dirs
+        });
+
+        FileNode {
+            name,
+            size: (!self.is_dir).then_some(self.size),
+            path,
+            is_dir: self.is_dir,
+            children,
+            inaccessible: Vec::new(),
+        }
+    }
+}
+
+/// The archive's entries as a folder tree rooted at the archive itself. Node paths are
+/// entry names inside the archive ("" for the root), usable with `preview_archive_entry`
+/// for files; directories that only appear as a prefix of other entries are added too.
+#[command(async)]
+pub fn archive_tree(archive_path: String) -> Result<FileNode, String> {
+    let mut archive = open_archive(&archive_path)?;
+
+    let mut root = TreeBuilder { is_dir: true, ..Default::default() };
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index_raw(i)
+            .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+        let name = file.name().replace('\\', "/");
+        let components: Vec<&str> = name
+            .split('/')
+            .filter(|c| !c.is_empty() && *c != "." && *c != "..")
+            .collect();
+        root.insert(&components, file.is_dir(), file.size());
+    }
+
+    let name = Path::new(&archive_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| archive_path.clone());
+    Ok(root.into_node(name, String::new()))
+}
+
+/// Extracts every entry into `dest_dir`. The uncompressed total is checked against the
+/// destination's free space, and every target against existing files, before anything
+/// is written; an archive that would overwrite something is refused as a whole.
+#[command(async)]
+pub fn extract_archive(archive_path: String, dest_dir: String) -> Result<ExtractSummary, String> {
+    let mut archive = open_archive(&archive_path)?;
+    let dest = Path::new(&dest_dir);
+
+    let mut required: u64 = 0;
+    let mut collisions = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index_raw(i)
+            .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+        required = required.saturating_add(file.size());
+        if let Some(relative) = file.enclosed_name() {
+            let target = dest.join(relative);
+            if !file.is_dir() && target.symlink_metadata().is_ok() {
+                collisions.push(target.to_string_lossy().to_string());
+            }
+        }
+    }
+    if !collisions.is_empty() {
+        return Err(format!(
+            "{} file(s) already exist in the destination, e.g. {}",
+            collisions.len(),
+            collisions[0]
+        ));
+    }
+    check_free_space(dest, required).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(dest).map_err(|e| format!("Failed to create destination: {}", e))?;
+
+    let mut summary = ExtractSummary { extracted: 0, bytes: 0, skipped: Vec::new() };
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+        let Some(relative) = file.enclosed_name().map(PathBuf::from) else {
+            summary.skipped.push(file.name().to_string());
+            continue;
+        };
+        let target = dest.join(relative);
+
+        if file.is_dir() {
+            std::fs::create_dir_all(&target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        // `create_new` also refuses a file that appeared since the check above
+        let mut out = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&target)
+            .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+
+        // The declared size can lie, so never write more than the free-space check allowed for
+        let declared = file.size();
+        let written = std::io::copy(&mut (&mut file).take(declared), &mut out)
+            .map_err(|e| format!("Failed to extract {}: {}", target.display(), e))?;
+        let mut extra = [0u8; 1];
+        if file.read(&mut extra).map_err(|e| format!("Failed to extract {}: {}", target.display(), e))? > 0 {
+            drop(out);
+            let _ = std::fs::remove_file(&target);
+            return Err(format!("Entry {} is larger than its declared size", file.name()));
+        }
+        summary.bytes += written;
+        summary.extracted += 1;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::{FileOptions, ZipWriter};
+
+    /// Writes a zip holding `entries`; names ending in `/` become directories
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        for (name, data) in entries {
+            if name.ends_with('/') {
+                zip.add_directory(*name, FileOptions::default()).unwrap();
+            } else {
+                zip.start_file(*name, FileOptions::default()).unwrap();
+                zip.write_all(data).unwrap();
+            }
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn listing_reports_entries_and_flags_high_ratios() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("test.zip");
+        write_zip(&archive, &[("docs/", b""), ("docs/zeros.bin", &[0u8; 1 << 20])]);
+
+        let listing = list_archive(archive.to_string_lossy().to_string()).unwrap();
+        assert_eq!(listing.entries.len(), 2);
+        assert!(listing.entries[0].is_dir);
+        assert_eq!(listing.total_uncompressed, 1 << 20);
+        assert!(listing.suspicious_ratio);
+    }
+
+    #[test]
+    fn tree_adds_unlisted_parents_and_puts_directories_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("test.zip");
+        write_zip(&archive, &[("readme.txt", b"hi"), ("src/lib/mod.rs", b"fn main() {}")]);
+
+        let root = archive_tree(archive.to_string_lossy().to_string()).unwrap();
+        assert_eq!(root.name, "test.zip");
+        let children = root.children.unwrap();
+        assert_eq!(children.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["src", "readme.txt"]);
+        assert!(children[0].is_dir);
+        let lib = &children[0].children.as_ref().unwrap()[0];
+        assert_eq!(lib.path, "src/lib");
+        assert_eq!(lib.children.as_ref().unwrap()[0].size, Some(12));
+    }
+
+    #[test]
+    fn extraction_skips_entries_outside_the_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("test.zip");
+        write_zip(&archive, &[("a/b.txt", b"hello"), ("../escape.txt", b"x")]);
+        let dest = dir.path().join("out");
+
+        let summary = extract_archive(archive.to_string_lossy().to_string(), dest.to_string_lossy().to_string()).unwrap();
+        assert_eq!(summary.extracted, 1);
+        assert_eq!(summary.bytes, 5);
+        assert_eq!(summary.skipped, ["../escape.txt"]);
+        assert_eq!(std::fs::read(dest.join("a").join("b.txt")).unwrap(), b"hello");
+        assert!(!dir.path().join("escape.txt").exists());
+    }
+
+    #[test]
+    fn extraction_refuses_to_overwrite_and_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("test.zip");
+        write_zip(&archive, &[("new.txt", b"new"), ("existing.txt", b"new")]);
+        let dest = dir.path().join("out");
+        std::fs::create_dir(&dest).unwrap();
+        std::fs::write(dest.join("existing.txt"), "old").unwrap();
+
+        let err = extract_archive(archive.to_string_lossy().to_string(), dest.to_string_lossy().to_string()).unwrap_err();
+        assert!(err.contains("already exist"));
+        assert!(!dest.join("new.txt").exists());
+        assert_eq!(std::fs::read_to_string(dest.join("existing.txt")).unwrap(), "old");
+    }
+}
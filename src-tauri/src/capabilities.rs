@@ -0,0 +1,81 @@
+/**
+ * Capabilities Module
+ *
+ * Reports what this build of the backend supports so the frontend can hide
+ * features that aren't compiled in:
+ * - Preview kinds and archive formats
+ * - Hash algorithms and thumbnail formats
+ * - Optional metadata extractors (EXIF, PDF, Office)
+ * - Vault format version and size limit
+ */
+
+use crate::duplicate_detector::HASH_ALGORITHMS;
+use crate::file_preview::{AutoPreview, TEXT_EXTRACTED_EXTENSIONS};
+use crate::vault::{MAX_VAULT_SIZE, THUMBNAIL_FORMATS, VAULT_FORMAT_VERSION};
+use serde::Serialize;
+use tauri::command;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Capabilities {
+    pub backend_version: String,
+    /// `AutoPreview` kinds `preview_auto` can return, plus "code" for `preview_code`
+    pub preview_kinds: Vec<String>,
+    pub archive_formats: Vec<String>,
+    pub hash_algorithms: Vec<String>,
+    pub thumbnail_formats: Vec<String>,
+    /// EXIF orientation is applied to vault thumbnails
+    pub exif_orientation: bool,
+    pub pdf_text_extraction: bool,
+    pub office_text_extraction: bool,
+    pub vault_format_version: u32,
+    pub max_vault_size: u64,
+}
+
+/// Office formats, any of which counts as Office text extraction
+const OFFICE_EXTENSIONS: &[&str] = &["docx", "pptx", "xlsx", "odt", "odp", "ods"];
+
+/// Thumbnail formats whose decoders report an EXIF orientation
+const EXIF_FORMATS: &[&str] = &["jpeg", "png", "webp", "tiff"];
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+/// The feature manifest of this build; everything here is fixed at compile time and
+/// read from the lists the preview, hashing and vault code dispatch on
+#[command]
+pub fn get_capabilities() -> Capabilities {
+    let mut preview_kinds = AutoPreview::kinds();
+    preview_kinds.push("code".to_string());
+
+    Capabilities {
+        backend_version: env!("CARGO_PKG_VERSION").to_string(),
+        preview_kinds,
+        archive_formats: strings(&["zip"]),
+        hash_algorithms: strings(HASH_ALGORITHMS),
+        thumbnail_formats: strings(THUMBNAIL_FORMATS),
+        exif_orientation: THUMBNAIL_FORMATS.iter().any(|f| EXIF_FORMATS.contains(f)),
+        pdf_text_extraction: TEXT_EXTRACTED_EXTENSIONS.contains(&"pdf"),
+        office_text_extraction: TEXT_EXTRACTED_EXTENSIONS.iter().any(|e| OFFICE_EXTENSIONS.contains(e)),
+        vault_format_version: VAULT_FORMAT_VERSION,
+        max_vault_size: MAX_VAULT_SIZE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities_follow_the_preview_code() {
+        let capabilities = get_capabilities();
+        for kind in ["text", "code", "image", "pdf", "audio", "video"] {
+            assert!(capabilities.preview_kinds.contains(&kind.to_string()), "missing {}", kind);
+        }
+        assert!(!capabilities.preview_kinds.contains(&"unsupported".to_string()));
+        // docx and pptx text is extracted, PDF text isn't
+        assert!(capabilities.office_text_extraction);
+        assert!(!capabilities.pdf_text_extraction);
+        assert!(capabilities.exif_orientation);
+    }
+}
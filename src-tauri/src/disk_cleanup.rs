@@ -0,0 +1,1024 @@
+/**
+ * Disk Cleanup Module
+ *
+ * Walks directory trees looking for files worth cleaning up:
+ * - Zero-byte placeholders and broken downloads
+ * - Files whose content doesn't match their extension
+ * - Files that can't be read
+ * - Files nobody has touched in a long time
+ * - Names that won't survive syncing to another OS
+ * - A per-category breakdown of what is using the space
+ * - Dangling or looping symlinks
+ * - Paths too long for Windows' MAX_PATH
+ *
+ * Everything here is read-only; deletion goes through the explorer commands.
+ */
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{command, Emitter, State, Window};
+use crate::StateSafe;
+use crate::filesystem::walk::WalkOptions;
+
+/// Files walked between `stale_progress` events
+const STALE_PROGRESS_EVERY: usize = 500;
+
+/// Temp files smaller or newer than these are left out of `find_temp_clutter` by default
+const DEFAULT_CLUTTER_MIN_SIZE: u64 = 10 * 1024 * 1024;
+const DEFAULT_CLUTTER_AGE_DAYS: u64 = 7;
+
+/// Files touched this recently may still be in use and are never reported as clutter
+const CLUTTER_IN_USE_GRACE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Extensions that share a detected type with another extension
+const EQUIVALENT_EXTENSIONS: &[&[&str]] = &[
+    &["jpg", "jpeg", "jpe"],
+    &["tif", "tiff"],
+    &["mp4", "m4v", "m4a", "mov"],
+    &["mkv", "webm"],
+    &["zip", "docx", "xlsx", "pptx", "odt", "ods", "odp", "jar", "apk", "epub"],
+    &["gz", "tgz"],
+    &["exe", "dll", "sys"],
+];
+
+/// Text formats `infer` recognizes only loosely; a miss proves nothing
+const LOOSE_TEXT_EXTENSIONS: &[&str] = &["html", "htm", "xml", "sh"];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ProblemFile {
+    pub path: String,
+    pub size: u64,
+    pub detail: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ProblemFiles {
+    pub zero_byte: Vec<ProblemFile>,
+    pub mismatched_type: Vec<ProblemFile>,
+    pub unreadable: Vec<ProblemFile>,
+}
+
+/// Result of comparing a file's extension to its sniffed magic bytes
+#[derive(Serialize, Clone, Debug)]
+pub struct TypeMismatch {
+    pub claimed: String,
+    pub detected: Option<String>,
+    pub detected_mime: Option<String>,
+    /// The content is a program (PE, ELF, Mach-O, ...) whatever the extension says
+    pub executable: bool,
+}
+
+/// Device names Windows reserves regardless of extension
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Files walked between `categorize_progress` events
+const CATEGORIZE_PROGRESS_EVERY: usize = 1000;
+
+/// Extension lists for each storage category; anything else is sniffed, then "other"
+const CATEGORY_EXTENSIONS: &[(&str, &[&str])] = &[
+    ("image", &["jpg", "jpeg", "png", "gif", "bmp", "webp", "svg", "tif", "tiff", "heic", "ico", "raw"]),
+    ("video", &["mp4", "mkv", "mov", "avi", "webm", "wmv", "flv", "m4v"]),
+    ("audio", &["mp3", "wav", "flac", "ogg", "m4a", "aac", "wma", "opus"]),
+    ("document", &["pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods", "odp", "txt", "md", "rtf", "csv", "epub"]),
+    ("archive", &["zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "zst", "iso", "dmg"]),
+    ("code", &["rs", "py", "js", "ts", "tsx", "jsx", "java", "c", "h", "cpp", "hpp", "cs", "go", "rb", "php", "swift", "kt", "sh", "html", "css", "json", "toml", "yaml", "yml", "xml", "sql"]),
+];
+
+/// Longest file name, in bytes, the common filesystems accept
+const MAX_NAME_BYTES: usize = 255;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct InvalidFilename {
+    pub path: String,
+    pub name: String,
+    pub rules: Vec<String>,
+    pub suggestion: String,
+}
+
+/// A symlink whose target is missing, looping or outside the allowed roots
+#[derive(Serialize, Clone, Debug)]
+pub struct BrokenSymlink {
+    pub path: String,
+    pub target: String,
+    pub reason: String,  // "missing_target", "outside_allowed_roots", "symlink_loop" or "ancestor_cycle"
+}
+
+/// Windows' MAX_PATH, the default threshold for `find_long_paths`
+const WINDOWS_MAX_PATH: usize = 260;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LongPath {
+    pub path: String,
+    pub length: usize,
+}
+
+/// Largest file seen in a category
+#[derive(Serialize, Clone, Debug)]
+pub struct LargestFile {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct CategoryStats {
+    pub count: u64,
+    pub total_bytes: u64,
+    pub largest: Option<LargestFile>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CategorizeProgress {
+    pub scanned: usize,
+    pub total_bytes: u64,
+}
+
+/// A file whose last use is older than the requested threshold
+#[derive(Serialize, Clone, Debug)]
+pub struct StaleFile {
+    pub path: String,
+    pub size: u64,
+    pub last_used: String,
+    pub timestamp: &'static str,  // "accessed" or "modified"
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StaleFiles {
+    pub files: Vec<StaleFile>,
+    pub reclaimable_bytes: u64,
+    pub timestamp_note: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LargeFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LargeFileProgress {
+    pub scanned: usize,
+    pub smallest_kept: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ClutterFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: String,
+    pub location: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TempClutter {
+    pub roots: Vec<String>,
+    /// Files by category (image, video, archive, ...), largest first
+    pub categories: HashMap<String, Vec<ClutterFile>>,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StaleProgress {
+    pub scanned: usize,
+    pub stale: usize,
+}
+
+fn extensions_equivalent(a: &str, b: &str) -> bool {
+    a == b
+        || EQUIVALENT_EXTENSIONS
+            .iter()
+            .any(|group| group.contains(&a) && group.contains(&b))
+}
+
+/// Sniffs the magic bytes of `path` and reports when they contradict its extension.
+/// Returns `None` when the types agree or nothing can be concluded.
+pub(crate) fn detect_type_mismatch(path: &Path) -> std::io::Result<Option<TypeMismatch>> {
+    let claimed = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => ext.to_lowercase(),
+        None => return Ok(None),
+    };
+
+    match infer::get_from_path(path)? {
+        Some(kind) if !extensions_equivalent(&claimed, kind.extension()) => Ok(Some(TypeMismatch {
+            claimed,
+            detected: Some(kind.extension().to_string()),
+            detected_mime: Some(kind.mime_type().to_string()),
+            executable: kind.matcher_type() == infer::MatcherType::App,
+        })),
+        Some(_) => Ok(None),
+        None if infer::is_supported(&claimed) && !LOOSE_TEXT_EXTENSIONS.contains(&claimed.as_str()) => {
+            Ok(Some(TypeMismatch {
+                claimed,
+                detected: None,
+                detected_mime: None,
+                executable: false,
+            }))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Reports zero-byte files, extension/content mismatches and unreadable files under `dir`
+#[command(async)]
+pub fn find_problem_files(dir: String, walk_options: Option<WalkOptions>) -> Result<ProblemFiles, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let options = walk_options.unwrap_or_default();
+
+    let mut problems = ProblemFiles::default();
+
+    for entry in options.walk_dir(&dir).into_iter().filter_entry(|e| options.allows_entry(e)) {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(e) => {
+                if let Some(path) = e.path() {
+                    problems.unreadable.push(ProblemFile {
+                        path: path.to_string_lossy().to_string(),
+                        size: 0,
+                        detail: e.to_string(),
+                    });
+                }
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        let path_str = path.to_string_lossy().to_string();
+        let size = match entry.metadata() {
+            Ok(m) => m.len(),
+            Err(e) => {
+                problems.unreadable.push(ProblemFile { path: path_str, size: 0, detail: e.to_string() });
+                continue;
+            }
+        };
+
+        if size == 0 {
+            problems.zero_byte.push(ProblemFile {
+                path: path_str,
+                size,
+                detail: "empty file".to_string(),
+            });
+            continue;
+        }
+
+        if let Err(e) = fs::File::open(path) {
+            if e.kind() == ErrorKind::PermissionDenied {
+                problems.unreadable.push(ProblemFile { path: path_str, size, detail: e.to_string() });
+            }
+            continue;
+        }
+
+        match detect_type_mismatch(path) {
+            Ok(Some(mismatch)) => problems.mismatched_type.push(ProblemFile {
+                path: path_str,
+                size,
+                detail: match mismatch.detected {
+                    Some(detected) => format!(".{} file contains {} data", mismatch.claimed, detected),
+                    None => format!(".{} file doesn't look like a {} file", mismatch.claimed, mismatch.claimed),
+                },
+            }),
+            Ok(None) => {}
+            Err(e) => problems.unreadable.push(ProblemFile { path: path_str, size, detail: e.to_string() }),
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Picks the timestamp that best reflects when a file was last used.
+///
+/// Windows stops updating last-access times by default, and `noatime` mounts never
+/// update them, which shows up as an atime older than the mtime. Both fall back to
+/// the modification time.
+fn last_used(metadata: &fs::Metadata) -> Option<(SystemTime, &'static str)> {
+    let modified = metadata.modified().ok();
+    if cfg!(windows) {
+        return modified.map(|m| (m, "modified"));
+    }
+    match (metadata.accessed().ok(), modified) {
+        (Some(accessed), Some(modified)) if accessed >= modified => Some((accessed, "accessed")),
+        (_, Some(modified)) => Some((modified, "modified")),
+        (Some(accessed), None) => Some((accessed, "accessed")),
+        (None, None) => None,
+    }
+}
+
+/// Files under `dir` not used for more than `older_than_days`, oldest first,
+/// with the bytes deleting them would free
+#[command(async)]
+pub fn find_stale_files(
+    window: Window,
+    dir: String,
+    older_than_days: u64,
+    walk_options: Option<WalkOptions>,
+) -> Result<StaleFiles, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let options = walk_options.unwrap_or_default();
+
+    let cutoff = SystemTime::now()
+        .checked_sub(Duration::from_secs(older_than_days.saturating_mul(24 * 60 * 60)))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let mut stale: Vec<(SystemTime, StaleFile)> = Vec::new();
+    let mut scanned = 0;
+
+    for entry in options.walk(&dir) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        scanned += 1;
+        if scanned % STALE_PROGRESS_EVERY == 0 {
+            let _ = window.emit("stale_progress", StaleProgress { scanned, stale: stale.len() });
+        }
+
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let Some((used, timestamp)) = last_used(&metadata) else {
+            continue;
+        };
+        if used >= cutoff {
+            continue;
+        }
+
+        stale.push((
+            used,
+            StaleFile {
+                path: entry.path().to_string_lossy().to_string(),
+                size: metadata.len(),
+                last_used: DateTime::<Utc>::from(used).to_rfc3339(),
+                timestamp,
+            },
+        ));
+    }
+
+    let _ = window.emit("stale_progress", StaleProgress { scanned, stale: stale.len() });
+
+    stale.sort_by_key(|(used, _)| *used);
+    let files: Vec<StaleFile> = stale.into_iter().map(|(_, file)| file).collect();
+
+    Ok(StaleFiles {
+        reclaimable_bytes: files.iter().map(|f| f.size).sum(),
+        timestamp_note: if cfg!(windows) {
+            "Windows does not update access times by default, so modification times were used".to_string()
+        } else {
+            "Access times were used where the filesystem records them, modification times elsewhere".to_string()
+        },
+        files,
+    })
+}
+
+/// Adds a file to the min-heap of the `limit` largest seen so far, evicting the smallest
+/// when it is full; files no bigger than the smallest kept one are dropped
+fn keep_if_largest(largest: &mut BinaryHeap<Reverse<(u64, PathBuf)>>, limit: usize, size: u64, path: PathBuf) {
+    if limit == 0 {
+        return;
+    }
+    if largest.len() == limit {
+        match largest.peek() {
+            Some(Reverse((smallest, _))) if size > *smallest => {
+                largest.pop();
+            }
+            _ => return,
+        }
+    }
+    largest.push(Reverse((size, path)));
+}
+
+/// The `limit` largest files under `dir` of at least `min_size` bytes, biggest first.
+/// Only `limit` files are held at a time, so whole drives can be walked.
+#[command(async)]
+pub fn find_large_files(
+    window: Window,
+    dir: String,
+    min_size: u64,
+    limit: usize,
+    walk_options: Option<WalkOptions>,
+) -> Result<Vec<LargeFile>, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let options = walk_options.unwrap_or_default();
+
+    // Min-heap on size: the root is the smallest file currently kept
+    let mut largest: BinaryHeap<Reverse<(u64, PathBuf)>> = BinaryHeap::new();
+    let mut scanned = 0;
+
+    for entry in options.walk(&dir) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        scanned += 1;
+        if scanned % STALE_PROGRESS_EVERY == 0 {
+            let smallest_kept = largest.peek().map(|Reverse((size, _))| *size).unwrap_or(0);
+            let _ = window.emit("large_files_progress", LargeFileProgress { scanned, smallest_kept });
+        }
+
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let size = metadata.len();
+        if size >= min_size {
+            keep_if_largest(&mut largest, limit, size, entry.into_path());
+        }
+    }
+
+    let smallest_kept = largest.peek().map(|Reverse((size, _))| *size).unwrap_or(0);
+    let _ = window.emit("large_files_progress", LargeFileProgress { scanned, smallest_kept });
+
+    // Ascending Reverse order is descending size
+    Ok(largest
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((size, path))| LargeFile {
+            modified: fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(|t| DateTime::<Utc>::from(t).to_rfc3339()),
+            path: path.to_string_lossy().to_string(),
+            size,
+        })
+        .collect())
+}
+
+/// The platform's temp and cache directories that exist on this machine
+fn temp_roots() -> Vec<PathBuf> {
+    let mut roots = vec![std::env::temp_dir()];
+    if let Some(home) = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+        let home = PathBuf::from(home);
+        if cfg!(target_os = "macos") {
+            roots.push(home.join("Library").join("Caches"));
+        } else if cfg!(unix) {
+            roots.push(home.join(".cache"));
+        }
+    }
+    if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+        roots.push(PathBuf::from(local).join("Temp"));
+    }
+
+    let mut existing: Vec<PathBuf> = roots
+        .into_iter()
+        .filter_map(|r| fs::canonicalize(r).ok())
+        .filter(|r| r.is_dir())
+        .collect();
+    existing.sort();
+    existing.dedup();
+    existing
+}
+
+/// Large, old files in the temp and cache directories (or in `roots`, when given),
+/// grouped by category. Anything modified in the last day is skipped as possibly in use.
+#[command(async)]
+pub fn find_temp_clutter(
+    roots: Option<Vec<String>>,
+    min_size: Option<u64>,
+    older_than_days: Option<u64>,
+    walk_options: Option<WalkOptions>,
+) -> Result<TempClutter, String> {
+    let roots: Vec<PathBuf> = match roots {
+        Some(roots) => roots.into_iter().map(PathBuf::from).filter(|r| r.is_dir()).collect(),
+        None => temp_roots(),
+    };
+    let options = walk_options.unwrap_or_default();
+    let min_size = min_size.unwrap_or(DEFAULT_CLUTTER_MIN_SIZE);
+    let age = Duration::from_secs(older_than_days.unwrap_or(DEFAULT_CLUTTER_AGE_DAYS).saturating_mul(24 * 60 * 60));
+    let cutoff = SystemTime::now()
+        .checked_sub(age.max(CLUTTER_IN_USE_GRACE))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let mut categories: HashMap<String, Vec<ClutterFile>> = HashMap::new();
+    let mut reclaimable_bytes = 0;
+
+    for root in &roots {
+        let location = root.to_string_lossy().to_string();
+        for entry in options.walk(root).filter(|e| e.depth() > 0) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let Ok(modified) = metadata.modified() else {
+                continue;
+            };
+            if metadata.len() < min_size || modified > cutoff {
+                continue;
+            }
+
+            reclaimable_bytes += metadata.len();
+            categories
+                .entry(categorize_file(entry.path()).to_string())
+                .or_default()
+                .push(ClutterFile {
+                    path: entry.path().to_string_lossy().to_string(),
+                    size: metadata.len(),
+                    modified: DateTime::<Utc>::from(modified).to_rfc3339(),
+                    location: location.clone(),
+                });
+        }
+    }
+
+    for files in categories.values_mut() {
+        files.sort_by(|a, b| b.size.cmp(&a.size));
+    }
+
+    Ok(TempClutter {
+        roots: roots.iter().map(|r| r.to_string_lossy().to_string()).collect(),
+        categories,
+        reclaimable_bytes,
+    })
+}
+
+fn illegal_chars(target_os: &str) -> &'static [char] {
+    match target_os {
+        "windows" => &['<', '>', ':', '"', '/', '\\', '|', '?', '*'],
+        "macos" => &[':', '/'],
+        _ => &['/'],
+    }
+}
+
+/// The rules `name` breaks on `target_os` ("windows", "macos" or "linux")
+fn filename_violations(name: &str, target_os: &str) -> Vec<String> {
+    let mut rules = Vec::new();
+
+    let illegal = illegal_chars(target_os);
+    if name.chars().any(|c| illegal.contains(&c)) {
+        rules.push("illegal_character".to_string());
+    }
+    if name.chars().any(|c| c == '\0' || (target_os == "windows" && c.is_control())) {
+        rules.push("control_character".to_string());
+    }
+    if target_os == "windows" {
+        if name.ends_with(' ') || name.ends_with('.') {
+            rules.push("trailing_space_or_dot".to_string());
+        }
+        let stem = name.split('.').next().unwrap_or(name).trim_end();
+        if WINDOWS_RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+            rules.push("reserved_name".to_string());
+        }
+    }
+    if name.len() > MAX_NAME_BYTES {
+        rules.push("name_too_long".to_string());
+    }
+
+    rules
+}
+
+/// Suggests a name that is valid on `target_os`: illegal characters become `_`,
+/// trailing spaces and dots are dropped and reserved device names get a `_` prefix.
+#[command(async)]
+pub fn sanitize_filename(name: String, target_os: String) -> String {
+    let illegal = illegal_chars(&target_os);
+    let mut safe: String = name
+        .chars()
+        .map(|c| {
+            if illegal.contains(&c) || c == '\0' || (target_os == "windows" && c.is_control()) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    if target_os == "windows" {
+        safe = safe.trim_end_matches([' ', '.']).to_string();
+        let stem = safe.split('.').next().unwrap_or(&safe).trim_end();
+        if WINDOWS_RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+            safe.insert(0, '_');
+        }
+    }
+
+    // Trim to the byte limit without splitting a character, keeping the extension
+    if safe.len() > MAX_NAME_BYTES {
+        let ext = Path::new(&safe)
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .filter(|e| e.len() < MAX_NAME_BYTES / 2)
+            .unwrap_or_default();
+        let mut stem = safe[..safe.len() - ext.len()].to_string();
+        while stem.len() + ext.len() > MAX_NAME_BYTES {
+            stem.pop();
+        }
+        safe = stem + &ext;
+    }
+
+    if safe.is_empty() {
+        "_".to_string()
+    } else {
+        safe
+    }
+}
+
+/// Paths under `dir` whose names would break on `target_os`, with the rules
+/// they violate and a suggested replacement
+#[command(async)]
+pub fn find_invalid_filenames(
+    dir: String,
+    target_os: String,
+    walk_options: Option<WalkOptions>,
+) -> Result<Vec<InvalidFilename>, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    if !["windows", "macos", "linux"].contains(&target_os.as_str()) {
+        return Err(format!("Unknown target OS: {}", target_os));
+    }
+    let options = walk_options.unwrap_or_default();
+
+    let mut invalid = Vec::new();
+    for entry in options.walk(&dir).filter(|e| e.depth() > 0) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let rules = filename_violations(&name, &target_os);
+        if rules.is_empty() {
+            continue;
+        }
+
+        invalid.push(InvalidFilename {
+            path: entry.path().to_string_lossy().to_string(),
+            suggestion: sanitize_filename(name.clone(), target_os.clone()),
+            name,
+            rules,
+        });
+    }
+
+    Ok(invalid)
+}
+
+/// Buckets a file by extension, falling back to sniffing its magic bytes
+fn categorize_file(path: &Path) -> &'static str {
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        let ext = ext.to_lowercase();
+        if let Some((category, _)) = CATEGORY_EXTENSIONS.iter().find(|(_, exts)| exts.contains(&ext.as_str())) {
+            return category;
+        }
+    }
+
+    match infer::get_from_path(path).ok().flatten().map(|k| k.matcher_type()) {
+        Some(infer::MatcherType::Image) => "image",
+        Some(infer::MatcherType::Video) => "video",
+        Some(infer::MatcherType::Audio) => "audio",
+        Some(infer::MatcherType::Doc) | Some(infer::MatcherType::Book) => "document",
+        Some(infer::MatcherType::Archive) => "archive",
+        _ => "other",
+    }
+}
+
+/// Count, total size and largest file per category (image, video, audio,
+/// document, archive, code, other) for everything under `dir`
+#[command(async)]
+pub fn categorize_directory(
+    window: Window,
+    dir: String,
+    walk_options: Option<WalkOptions>,
+) -> Result<HashMap<String, CategoryStats>, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let options = walk_options.unwrap_or_default();
+
+    let mut categories: HashMap<String, CategoryStats> = HashMap::new();
+    let mut scanned = 0;
+    let mut total_bytes = 0;
+
+    for entry in options.walk(&dir) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let size = metadata.len();
+
+        let stats = categories.entry(categorize_file(entry.path()).to_string()).or_default();
+        stats.count += 1;
+        stats.total_bytes += size;
+        if stats.largest.as_ref().map_or(true, |l| size > l.size) {
+            stats.largest = Some(LargestFile {
+                path: entry.path().to_string_lossy().to_string(),
+                size,
+            });
+        }
+
+        scanned += 1;
+        total_bytes += size;
+        if scanned % CATEGORIZE_PROGRESS_EVERY == 0 {
+            let _ = window.emit("categorize_progress", CategorizeProgress { scanned, total_bytes });
+        }
+    }
+
+    let _ = window.emit("categorize_progress", CategorizeProgress { scanned, total_bytes });
+    Ok(categories)
+}
+
+/// Why the symlink at `link` is a problem, if it is
+fn symlink_problem(link: &Path, allowed_roots: &[std::path::PathBuf], report_cycles: bool) -> Option<&'static str> {
+    let resolved = match fs::canonicalize(link) {
+        Ok(resolved) => resolved,
+        // A chain of links that never reaches a real file fails with ELOOP
+        Err(e) if report_cycles && e.raw_os_error() == Some(LOOP_ERRNO) => return Some("symlink_loop"),
+        Err(_) => return Some("missing_target"),
+    };
+
+    if !allowed_roots.is_empty() && !allowed_roots.iter().any(|root| resolved.starts_with(root)) {
+        return Some("outside_allowed_roots");
+    }
+
+    // A directory link back to one of its own ancestors makes a followed walk endless
+    if report_cycles && resolved.is_dir() {
+        let parent = link.parent().and_then(|p| fs::canonicalize(p).ok())?;
+        if parent.starts_with(&resolved) {
+            return Some("ancestor_cycle");
+        }
+    }
+
+    None
+}
+
+#[cfg(target_os = "linux")]
+const LOOP_ERRNO: i32 = 40;
+#[cfg(all(unix, not(target_os = "linux")))]
+const LOOP_ERRNO: i32 = 62;
+#[cfg(not(unix))]
+const LOOP_ERRNO: i32 = 1921;  // ERROR_CANT_RESOLVE_FILENAME
+
+/// Symlinks under `dir` whose targets don't exist or resolve outside the allowed roots.
+/// With `report_cycles`, link loops and links to their own ancestors are reported too.
+#[command(async)]
+pub fn find_broken_symlinks(
+    state_mux: State<'_, StateSafe>,
+    dir: String,
+    report_cycles: Option<bool>,
+    walk_options: Option<WalkOptions>,
+) -> Result<Vec<BrokenSymlink>, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let options = walk_options.unwrap_or_default();
+
+    let allowed_roots = state_mux
+        .lock()
+        .map_err(|_| "Failed to acquire state lock".to_string())?
+        .allowed_roots
+        .clone();
+    let report_cycles = report_cycles.unwrap_or(false);
+
+    let mut broken = Vec::new();
+    for entry in options.walk(&dir).filter(|e| e.depth() > 0) {
+        let path = entry.path();
+        let is_link = fs::symlink_metadata(path).map(|m| m.file_type().is_symlink()).unwrap_or(false);
+        if !is_link {
+            continue;
+        }
+
+        if let Some(reason) = symlink_problem(path, &allowed_roots, report_cycles) {
+            broken.push(BrokenSymlink {
+                path: path.to_string_lossy().to_string(),
+                target: fs::read_link(path)
+                    .map(|t| t.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                reason: reason.to_string(),
+            });
+        }
+    }
+
+    Ok(broken)
+}
+
+/// Paths under `dir` longer than `threshold` (default 260), longest first.
+/// For the default Windows target the length is in UTF-16 code units, as MAX_PATH counts;
+/// other targets count characters.
+#[command(async)]
+pub fn find_long_paths(
+    dir: String,
+    threshold: Option<usize>,
+    target_os: Option<String>,
+    walk_options: Option<WalkOptions>,
+) -> Result<Vec<LongPath>, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let options = walk_options.unwrap_or_default();
+
+    let threshold = threshold.unwrap_or(WINDOWS_MAX_PATH);
+    let utf16 = target_os.as_deref().unwrap_or("windows") == "windows";
+
+    let mut long_paths: Vec<LongPath> = options
+        .walk(&dir)
+        .filter(|e| e.depth() > 0)
+        .filter_map(|entry| {
+            let path = entry.path().to_string_lossy().to_string();
+            let length = if utf16 { path.encode_utf16().count() } else { path.chars().count() };
+            (length > threshold).then_some(LongPath { path, length })
+        })
+        .collect();
+
+    long_paths.sort_by(|a, b| b.length.cmp(&a.length).then_with(|| a.path.cmp(&b.path)));
+    Ok(long_paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir_string(dir: &tempfile::TempDir) -> String {
+        dir.path().to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn problem_files_report_empty_and_mismatched_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("placeholder.bin"), "").unwrap();
+        fs::write(dir.path().join("photo.jpg"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        fs::write(dir.path().join("broken.png"), b"not an image at all").unwrap();
+        fs::write(dir.path().join("notes.txt"), "fine").unwrap();
+
+        let problems = find_problem_files(dir_string(&dir), None).unwrap();
+        assert_eq!(problems.zero_byte.len(), 1);
+        assert!(problems.zero_byte[0].path.ends_with("placeholder.bin"));
+
+        let mut details: Vec<_> = problems.mismatched_type.iter().map(|p| p.detail.as_str()).collect();
+        details.sort();
+        assert_eq!(details, vec![".jpg file contains png data", ".png file doesn't look like a png file"]);
+        assert!(problems.unreadable.is_empty());
+    }
+
+    #[test]
+    fn equivalent_extensions_are_not_mismatches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("photo.jpeg");
+        fs::write(&path, b"\xff\xd8\xff\xe0\0\x10JFIF\0").unwrap();
+
+        assert!(detect_type_mismatch(&path).unwrap().is_none());
+        assert!(detect_type_mismatch(&dir.path().join("no_extension")).unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn last_used_falls_back_to_mtime_when_atime_lags() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old.txt");
+        fs::write(&path, "data").unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(3600);
+        let file = fs::File::options().write(true).open(&path).unwrap();
+
+        let times = fs::FileTimes::new().set_modified(modified).set_accessed(modified + Duration::from_secs(60));
+        file.set_times(times).unwrap();
+        let (used, timestamp) = last_used(&fs::metadata(&path).unwrap()).unwrap();
+        assert_eq!((used, timestamp), (modified + Duration::from_secs(60), "accessed"));
+
+        // An atime older than the mtime means access times aren't being updated
+        let times = fs::FileTimes::new().set_modified(modified).set_accessed(modified - Duration::from_secs(60));
+        file.set_times(times).unwrap();
+        assert_eq!(last_used(&fs::metadata(&path).unwrap()).unwrap(), (modified, "modified"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn last_used_is_the_mtime_on_windows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old.txt");
+        fs::write(&path, "data").unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+
+        assert_eq!(last_used(&metadata).unwrap(), (metadata.modified().unwrap(), "modified"));
+    }
+
+    #[test]
+    fn filename_violations_depend_on_the_target() {
+        assert_eq!(filename_violations("a:b.txt", "linux"), Vec::<String>::new());
+        assert_eq!(filename_violations("a:b.txt", "macos"), vec!["illegal_character"]);
+        assert_eq!(filename_violations("con.txt", "windows"), vec!["reserved_name"]);
+        assert_eq!(filename_violations("notes. ", "windows"), vec!["trailing_space_or_dot"]);
+        assert_eq!(filename_violations(&"a".repeat(256), "linux"), vec!["name_too_long"]);
+    }
+
+    #[test]
+    fn sanitized_names_are_valid_on_the_target() {
+        assert_eq!(sanitize_filename("what?: \"now\".txt".to_string(), "windows".to_string()), "what__ _now_.txt");
+        assert_eq!(sanitize_filename("CON.txt".to_string(), "windows".to_string()), "_CON.txt");
+        assert_eq!(sanitize_filename("report. .".to_string(), "windows".to_string()), "report");
+        assert_eq!(sanitize_filename("...".to_string(), "windows".to_string()), "_");
+
+        let long = format!("{}.txt", "é".repeat(200));
+        let safe = sanitize_filename(long, "linux".to_string());
+        assert!(safe.len() <= MAX_NAME_BYTES && safe.ends_with(".txt"));
+        assert!(filename_violations(&safe, "linux").is_empty());
+    }
+
+    #[test]
+    fn files_are_categorized_by_extension_then_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, data: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, data).unwrap();
+            path
+        };
+
+        assert_eq!(categorize_file(&write("Song.MP3", b"")), "audio");
+        assert_eq!(categorize_file(&write("main.rs", b"fn main() {}")), "code");
+        assert_eq!(categorize_file(&write("scan", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")), "image");
+        assert_eq!(categorize_file(&write("blob.dat", b"\0\x01\x02")), "other");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_problems_are_classified() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(root.join("real.txt"), "data").unwrap();
+        symlink(root.join("real.txt"), root.join("ok")).unwrap();
+        symlink(root.join("gone.txt"), root.join("dangling")).unwrap();
+        symlink(outside.path(), root.join("escape")).unwrap();
+        symlink(root.join("loop_b"), root.join("loop_a")).unwrap();
+        symlink(root.join("loop_a"), root.join("loop_b")).unwrap();
+        fs::create_dir(root.join("sub")).unwrap();
+        symlink(&root, root.join("sub").join("up")).unwrap();
+        let roots = vec![root.clone()];
+
+        assert_eq!(symlink_problem(&root.join("ok"), &roots, true), None);
+        assert_eq!(symlink_problem(&root.join("dangling"), &roots, true), Some("missing_target"));
+        assert_eq!(symlink_problem(&root.join("escape"), &roots, true), Some("outside_allowed_roots"));
+        assert_eq!(symlink_problem(&root.join("escape"), &[], true), None);
+        assert_eq!(symlink_problem(&root.join("loop_a"), &roots, true), Some("symlink_loop"));
+        assert_eq!(symlink_problem(&root.join("loop_a"), &roots, false), Some("missing_target"));
+        assert_eq!(symlink_problem(&root.join("sub").join("up"), &roots, true), Some("ancestor_cycle"));
+        assert_eq!(symlink_problem(&root.join("sub").join("up"), &roots, false), None);
+    }
+
+    #[test]
+    fn long_paths_are_measured_for_the_target() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("short"), "").unwrap();
+        fs::write(dir.path().join("\u{1F600}\u{1F600}\u{1F600}"), "").unwrap();
+        let base = dir_string(&dir).chars().count() + 1;
+
+        // Each emoji is one character but two UTF-16 code units
+        let utf16 = find_long_paths(dir_string(&dir), Some(base + 5), None, None).unwrap();
+        assert_eq!(utf16.len(), 1);
+        assert_eq!(utf16[0].length, base + 6);
+
+        let chars = find_long_paths(dir_string(&dir), Some(base + 4), Some("linux".to_string()), None).unwrap();
+        assert_eq!(chars.iter().map(|p| p.length).collect::<Vec<_>>(), vec![base + 5]);
+    }
+
+    #[test]
+    fn temp_clutter_keeps_large_old_files_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = SystemTime::now() - Duration::from_secs(10 * 24 * 60 * 60);
+        let write = |name: &str, size: usize, modified: SystemTime| {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![0u8; size]).unwrap();
+            fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        };
+        write("old_big.zip", 2048, old);
+        write("old_small.zip", 10, old);
+        write("fresh_big.mp4", 4096, SystemTime::now());
+
+        // Even with no age limit, files modified in the last day count as in use
+        let clutter = find_temp_clutter(Some(vec![dir_string(&dir)]), Some(1024), Some(0), None).unwrap();
+        assert_eq!(clutter.reclaimable_bytes, 2048);
+        assert_eq!(clutter.categories.len(), 1);
+        assert!(clutter.categories["archive"][0].path.ends_with("old_big.zip"));
+
+        let clutter = find_temp_clutter(Some(vec![dir_string(&dir)]), Some(1024), Some(30), None).unwrap();
+        assert_eq!(clutter.reclaimable_bytes, 0);
+    }
+
+    #[test]
+    fn only_the_largest_files_are_kept() {
+        let mut largest = BinaryHeap::new();
+        for (size, name) in [(5, "a"), (50, "b"), (10, "c"), (20, "d"), (70, "e"), (1, "f")] {
+            keep_if_largest(&mut largest, 3, size, PathBuf::from(name));
+        }
+
+        let kept: Vec<(u64, String)> = largest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, path))| (size, path.to_string_lossy().to_string()))
+            .collect();
+        assert_eq!(kept, vec![(70, "e".to_string()), (50, "b".to_string()), (20, "d".to_string())]);
+
+        let mut none = BinaryHeap::new();
+        keep_if_largest(&mut none, 0, 100, PathBuf::from("x"));
+        assert!(none.is_empty());
+    }
+}
@@ -1,18 +1,163 @@
 use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Read};
-use std::path::PathBuf;
-use walkdir::WalkDir;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tauri::Window;
 use tauri::Emitter;
 use rayon::prelude::*;
-use sha2::{Sha256, Digest};
+use sha2::{Sha256, Sha512, Digest};
+use std::collections::HashSet;
 use tauri::command;
+use unicode_normalization::UnicodeNormalization;
+use tauri::State;
+use crate::content_scanner::{emit_scan_event, scan_pause};
+use crate::StateSafe;
+use crate::filesystem::walk::WalkOptions;
+use crate::filesystem::file_lock::describe_failure;
+
+/// Read buffer for hashing; also the slice size fed to the hasher from a mapping
+const HASH_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Files at least this large are hashed through a memory map where that is safe
+const MMAP_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// `quick_mode` hashes only samples of files at least this large
+const QUICK_HASH_MIN_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Bytes read from each of the start, middle and end of a file in `quick_mode`
+const DEFAULT_QUICK_SAMPLE_KB: u64 = 64;
+
+/// Text files are compared by at most this many leading bytes
+const MAX_SIMILARITY_BYTES: u64 = 1024 * 1024;
+
+/// MinHash signature length; 16 bands of 4 rows pick the candidate pairs
+const MINHASH_SIZE: usize = 64;
+const MINHASH_BAND_ROWS: usize = 4;
+
+/// Words per shingle when comparing text files
+const SHINGLE_WORDS: usize = 3;
+
+/// Default average chunk size of `chunk_fingerprint`; chunks are cut between a quarter
+/// and eight times the average
+const DEFAULT_CDC_AVG_KB: u32 = 8;
+
+/// Pseudo-filesystems that never contain user data worth hashing
+const PSEUDO_FS_ROOTS: &[&str] = &["/proc", "/sys", "/dev", "/run"];
 
 #[derive(Debug, serde::Serialize)]
 pub struct DuplicateGroup {
     pub hash: String,
     pub files: Vec<String>,
+    /// Lowercased extensions in the group, sorted; "" for files without one
+    pub extensions: Vec<String>,
+    /// Identical content under different extensions, often a mislabeled copy
+    pub mixed_extensions: bool,
+    /// Per-file metadata, in the same order as `files`
+    pub details: Vec<DuplicateFileMeta>,
+    /// Copies differ in mode or ownership, so which one is kept matters
+    pub permissions_differ: bool,
+    pub most_recent: Option<String>,
+    /// Copy with the fewest permission bits set (Unix only)
+    pub most_restrictive: Option<String>,
+    /// Some copies are hardlinks to the same storage
+    pub hardlinked: bool,
+    /// Space freed by keeping one copy. Hardlinks are counted once unless the
+    /// caller asked for them to be counted as separate copies.
+    pub reclaimable_bytes: u64,
+    /// Grouped by sampled content only (`quick_mode`); confirm before deleting
+    pub approximate: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DuplicateFileMeta {
+    pub path: String,
+    pub modified: Option<String>,
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+impl DuplicateFileMeta {
+    fn read(path: &str) -> Self {
+        let metadata = fs::metadata(path).ok();
+        let modified = metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339());
+
+        #[cfg(unix)]
+        let (mode, uid, gid) = {
+            use std::os::unix::fs::MetadataExt;
+            match &metadata {
+                Some(m) => (Some(m.mode() & 0o7777), Some(m.uid()), Some(m.gid())),
+                None => (None, None, None),
+            }
+        };
+        #[cfg(not(unix))]
+        let (mode, uid, gid) = (None, None, None);
+
+        Self { path: path.to_string(), modified, mode, uid, gid }
+    }
+}
+
+impl DuplicateGroup {
+    fn new(hash: String, files: Vec<String>, exclude_hardlinks: bool) -> Self {
+        let mut extensions: Vec<String> = files
+            .iter()
+            .map(|f| {
+                Path::new(f)
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_lowercase())
+                    .unwrap_or_default()
+            })
+            .collect();
+        extensions.sort();
+        extensions.dedup();
+
+        let details: Vec<DuplicateFileMeta> = files.iter().map(|f| DuplicateFileMeta::read(f)).collect();
+        let permissions_differ = details
+            .windows(2)
+            .any(|pair| (pair[0].mode, pair[0].uid, pair[0].gid) != (pair[1].mode, pair[1].uid, pair[1].gid));
+        // RFC 3339 timestamps in UTC sort chronologically as strings
+        let most_recent = details
+            .iter()
+            .filter(|d| d.modified.is_some())
+            .max_by(|a, b| a.modified.cmp(&b.modified))
+            .map(|d| d.path.clone());
+        let most_restrictive = details
+            .iter()
+            .filter_map(|d| d.mode.map(|mode| (mode.count_ones(), d)))
+            .min_by_key(|(bits, _)| *bits)
+            .map(|(_, d)| d.path.clone());
+
+        let size = files.first().and_then(|f| fs::metadata(f).ok()).map(|m| m.len()).unwrap_or(0);
+        // Files whose identity can't be read are assumed to be separate copies
+        let mut identities = HashSet::new();
+        let distinct = files
+            .iter()
+            .filter(|f| match file_identity(Path::new(f.as_str())) {
+                Some((id, _)) => identities.insert(id),
+                None => true,
+            })
+            .count();
+        let hardlinked = distinct < files.len();
+        let copies = if exclude_hardlinks { distinct } else { files.len() };
+
+        Self {
+            hash,
+            files,
+            mixed_extensions: extensions.len() > 1,
+            extensions,
+            details,
+            permissions_differ,
+            most_recent,
+            most_restrictive,
+            hardlinked,
+            reclaimable_bytes: size * copies.saturating_sub(1) as u64,
+            approximate: false,
+        }
+    }
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -23,9 +168,55 @@ pub struct DuplicateProgress {
 }
 
 fn file_hash(path: &PathBuf) -> io::Result<String> {
+    file_digest::<Sha256>(path)
+}
+
+/// SHA-256 over the file size and `sample` bytes each from its start, middle and end.
+/// Prefixed with `sample:` so it never collides with a full-content hash.
+fn sample_hash(path: &Path, sample: u64) -> io::Result<String> {
     let mut file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+
     let mut hasher = Sha256::new();
-    let mut buffer = [0u8; 8192]; 
+    hasher.update(len.to_le_bytes());
+    let mut buffer = vec![0u8; sample as usize];
+    for offset in [0, (len / 2).saturating_sub(sample / 2), len.saturating_sub(sample)] {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut read = 0;
+        while read < buffer.len() {
+            match file.read(&mut buffer[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("sample:{}", hex::encode(hasher.finalize())))
+}
+
+/// Lowercase hex digest of a file's contents with any `Digest` algorithm
+fn file_digest<D: Digest>(path: &Path) -> io::Result<String> {
+    file_digest_with::<D>(path, HASH_BUFFER_SIZE, Some(MMAP_THRESHOLD))
+}
+
+/// `file_digest` with an explicit read buffer size. On Windows, files of at least
+/// `mmap_threshold` bytes are memory-mapped instead when they can be locked against
+/// writers (see `mmap_digest`); everything else, and every file elsewhere, is read.
+fn file_digest_with<D: Digest>(path: &Path, buffer_size: usize, mmap_threshold: Option<u64>) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+
+    // Only Windows can lock a mapped file against truncation; see `mmap_digest`
+    #[cfg(windows)]
+    if mmap_threshold.is_some_and(|threshold| file.metadata().is_ok_and(|m| m.len() >= threshold)) {
+        if let Some(hash) = mmap_digest::<D>(path, buffer_size) {
+            return Ok(hash);
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = mmap_threshold;
+
+    let mut hasher = D::new();
+    let mut buffer = vec![0u8; buffer_size.max(1)];
     loop {
         let n = file.read(&mut buffer)?;
         if n == 0 {
@@ -33,15 +224,171 @@ fn file_hash(path: &PathBuf) -> io::Result<String> {
         }
         hasher.update(&buffer[..n]);
     }
-    Ok(format!("{:x}", hasher.finalize()))
+    Ok(hex::encode(hasher.finalize()))
 }
 
-#[command]
-pub fn find_duplicate_files(window: Window, dir: String) -> Result<Vec<DuplicateGroup>, String> {
+/// Hashes a memory-mapped file, or `None` if it can't be opened without write sharing
+/// (another process has it open for writing) or can't be mapped.
+/// Unix has no lock that stops another process truncating a file, and a truncation
+/// while mapped raises SIGBUS mid-hash, so mapping is only used on Windows.
+#[cfg(windows)]
+fn mmap_digest<D: Digest>(path: &Path, chunk_size: usize) -> Option<String> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use windows_sys::Win32::Storage::FileSystem::FILE_SHARE_READ;
+
+    let file = fs::OpenOptions::new().read(true).share_mode(FILE_SHARE_READ).open(path).ok()?;
+    // SAFETY: `file` is opened without FILE_SHARE_WRITE or FILE_SHARE_DELETE, so while it
+    // (and so the mapping) is open no other handle can write, truncate or delete the
+    // file, and the mapped bytes can't change or disappear while they are read
+    let map = unsafe { memmap2::Mmap::map(&file) }.ok()?;
+
+    let mut hasher = D::new();
+    for chunk in map.chunks(chunk_size.max(1)) {
+        hasher.update(chunk);
+    }
+    Some(hex::encode(hasher.finalize()))
+}
+
+/// Checks if the path is one of the pseudo-filesystems or an explicitly excluded path.
+fn is_excluded(path: &Path, exclude: &[PathBuf]) -> bool {
+    PSEUDO_FS_ROOTS.iter().any(|root| path == Path::new(root))
+        || exclude.iter().any(|ex| path.starts_with(ex))
+}
+
+/// Identity of the storage behind `path`, (device, inode) on Unix or (volume serial,
+/// file index) on Windows, together with its link count
+#[cfg(unix)]
+fn file_identity(path: &Path) -> Option<((u64, u64), u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(path).ok()?;
+    Some(((metadata.dev(), metadata.ino()), metadata.nlink()))
+}
+
+#[cfg(windows)]
+fn file_identity(path: &Path) -> Option<((u64, u64), u64)> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION};
+
+    let file = fs::File::open(path).ok()?;
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    // SAFETY: the handle stays open for as long as `file` lives and `info` is a valid
+    // BY_HANDLE_FILE_INFORMATION to write into
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) } == 0 {
+        return None;
+    }
+    let index = ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64;
+    Some(((info.dwVolumeSerialNumber as u64, index), info.nNumberOfLinks as u64))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn file_identity(_path: &Path) -> Option<((u64, u64), u64)> {
+    None
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct HardlinkGroup {
+    pub device: u64,
+    pub inode: u64,
+    pub size: u64,
+    pub link_count: u64,
+    /// Names found under the scanned directory, sorted
+    pub files: Vec<String>,
+    /// Links to the same storage that live outside the scanned directory
+    pub links_elsewhere: u64,
+}
+
+/// Groups the files under `dir` that are hardlinks to the same storage, largest first.
+/// On Windows `device` and `inode` hold the volume serial number and file index.
+#[command(async)]
+pub fn find_hardlink_groups(dir: String, walk_options: Option<WalkOptions>) -> Result<Vec<HardlinkGroup>, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let options = walk_options.unwrap_or_default();
+
+    let mut groups: HashMap<(u64, u64), (u64, u64, Vec<String>)> = HashMap::new();
+    for entry in options.walk(&dir) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Some((id, links)) = file_identity(entry.path()) else {
+            continue;
+        };
+        if links < 2 {
+            continue;
+        }
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        groups
+            .entry(id)
+            .or_insert_with(|| (size, links, Vec::new()))
+            .2
+            .push(entry.path().to_string_lossy().to_string());
+    }
+
+    let mut groups: Vec<HardlinkGroup> = groups
+        .into_iter()
+        .map(|((device, inode), (size, link_count, mut files))| {
+            files.sort();
+            files.dedup();
+            HardlinkGroup {
+                device,
+                inode,
+                size,
+                link_count,
+                links_elsewhere: link_count.saturating_sub(files.len() as u64),
+                files,
+            }
+        })
+        .collect();
+    groups.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.files.cmp(&b.files)));
+    Ok(groups)
+}
+
+/// Every file that shares its size with another, flattened so they are hashed in one
+/// parallel pass and one large size group doesn't leave the pool idle
+fn hash_candidates(size_map: HashMap<u64, Vec<PathBuf>>) -> Vec<(u64, PathBuf)> {
+    size_map
+        .into_iter()
+        .filter(|(_, files)| files.len() >= 2)
+        .flat_map(|(size, files)| files.into_iter().map(move |p| (size, p)))
+        .collect()
+}
+
+/// `same_filesystem` (default true) prunes anything on a different device than `dir`,
+/// `exclude` lists extra paths that are never descended into. With `quick_mode`, files of
+/// at least 16 MiB are compared by `sample_kb` from their start, middle and end plus their
+/// size; groups containing such files are marked `approximate` (see `confirm_duplicates`).
+#[command(async)]
+#[allow(clippy::too_many_arguments)]
+pub fn find_duplicate_files(
+    window: Window,
+    state_mux: State<'_, StateSafe>,
+    dir: String,
+    same_filesystem: Option<bool>,
+    exclude: Option<Vec<String>>,
+    walk_options: Option<WalkOptions>,
+    exclude_hardlinks: Option<bool>,
+    quick_mode: Option<bool>,
+    sample_kb: Option<u64>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let sample = quick_mode
+        .unwrap_or(false)
+        .then(|| sample_kb.unwrap_or(DEFAULT_QUICK_SAMPLE_KB).max(1) * 1024);
     let mut size_map: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    let exclude: Vec<PathBuf> = exclude.unwrap_or_default().into_iter().map(PathBuf::from).collect();
+    let options = walk_options.unwrap_or_default();
+    let exclude_hardlinks = exclude_hardlinks.unwrap_or(true);
 
+    let walker = options
+        .walk_dir(&dir)
+        .same_file_system(same_filesystem.unwrap_or(true))
+        .into_iter()
+        .filter_entry(|e| options.allows_entry(e) && !is_excluded(e.path(), &exclude));
+
+    let pause = scan_pause(&state_mux);
     let mut scanned: usize = 0;
-    for entry in WalkDir::new(&dir).into_iter() {
+    for entry in walker {
+        pause.wait_if_paused(|event| emit_scan_event(&window, event));
         let entry = match entry {
             Ok(e) => e,
             Err(_) => continue,
@@ -66,42 +413,45 @@ pub fn find_duplicate_files(window: Window, dir: String) -> Result<Vec<Duplicate
         }
     }
 
-    let mut hash_map: HashMap<String, Vec<String>> = HashMap::new();
+    let candidates = hash_candidates(size_map);
+    let candidate_count = candidates.len();
+    let hashed = AtomicUsize::new(0);
 
-    for (_size, files) in size_map {
-        if files.len() < 2 {
-            continue;
-        }
-        let results: Vec<(String, Option<String>)> = files
-            .par_iter()
-            .map(|p| {
-                match file_hash(p) {
-                    Ok(h) => (p.to_string_lossy().to_string(), Some(h)),
-                    Err(_) => (p.to_string_lossy().to_string(), None),
-                }
-            })
-            .collect();
+    let hashes: Vec<(String, String)> = candidates
+        .par_iter()
+        .filter_map(|(size, p)| {
+            pause.wait_if_paused(|event| emit_scan_event(&window, event));
+            let hash = match sample {
+                Some(sample) if *size >= QUICK_HASH_MIN_SIZE => sample_hash(p, sample).ok(),
+                _ => file_hash(p).ok(),
+            };
 
-        for (path_str, maybe_hash) in results {
-            if let Some(hash) = maybe_hash {
-                hash_map.entry(hash).or_default().push(path_str);
+            let done = hashed.fetch_add(1, Ordering::Relaxed) + 1;
+            if done % 250 == 0 {
+                let progress = DuplicateProgress {
+                    scanned,
+                    candidates: candidate_count,
+                    duplicates_found: 0,
+                };
+                let _ = window.emit("duplicate_progress", &progress);
             }
-        }
+            hash.map(|h| (h, p.to_string_lossy().to_string()))
+        })
+        .collect();
 
-        let duplicates_count: usize = hash_map.values().filter(|v| v.len() > 1).count();
-        let progress = DuplicateProgress {
-            scanned: scanned as usize,
-            candidates: hash_map.values().map(|v| v.len()).sum(),
-            duplicates_found: duplicates_count,
-        };
-        let _ = window.emit("duplicate_progress", &progress);
+    let mut hash_map: HashMap<String, Vec<String>> = HashMap::new();
+    for (hash, path_str) in hashes {
+        hash_map.entry(hash).or_default().push(path_str);
     }
 
     let duplicates: Vec<DuplicateGroup> = hash_map
         .into_iter()
         .filter_map(|(hash, files)| {
             if files.len() > 1 {
-                Some(DuplicateGroup { hash, files })
+                let approximate = hash.starts_with("sample:");
+                let mut group = DuplicateGroup::new(hash, files, exclude_hardlinks);
+                group.approximate = approximate;
+                Some(group)
             } else {
                 None
             }
@@ -119,12 +469,1393 @@ pub fn find_duplicate_files(window: Window, dir: String) -> Result<Vec<Duplicate
 }
 
 
+/// Re-checks a group, typically an `approximate` one, by full-content hash. Returns the
+/// files that really are identical, split into one group per distinct content; files that
+/// turn out unique are dropped.
+#[command(async)]
+pub fn confirm_duplicates(group: GroupFiles, exclude_hardlinks: Option<bool>) -> Result<Vec<DuplicateGroup>, String> {
+    let hashes: Vec<(String, String)> = group
+        .files
+        .par_iter()
+        .map(|file| {
+            file_hash(&PathBuf::from(file))
+                .map(|hash| (hash, file.clone()))
+                .map_err(|e| format!("Failed to hash {}: {}", file, e))
+        })
+        .collect::<Result<_, String>>()?;
+
+    let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+    for (hash, file) in hashes {
+        by_hash.entry(hash).or_default().push(file);
+    }
+
+    let mut confirmed: Vec<DuplicateGroup> = by_hash
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|(hash, files)| DuplicateGroup::new(hash, files, exclude_hardlinks.unwrap_or(true)))
+        .collect();
+    confirmed.sort_by(|a, b| b.files.len().cmp(&a.files.len()).then_with(|| a.hash.cmp(&b.hash)));
+    Ok(confirmed)
+}
+
 #[command]
 pub fn delete_files(files: Vec<String>) -> Result<(), String> {
     for file in files {
         if let Err(e) = trash::delete(&file) {
-            return Err(format!("Failed to delete {}: {}", file, e));
+            return Err(format!("Failed to delete {}: {}", file, describe_failure(Path::new(&file), e)));
         }
     }
     Ok(())
 }
+
+/// The part of a `DuplicateGroup` sent back by the frontend; other fields are ignored
+#[derive(Debug, serde::Deserialize)]
+pub struct GroupFiles {
+    pub hash: String,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DedupeScriptSummary {
+    pub groups: usize,
+    pub kept: usize,
+    pub deletions: usize,
+}
+
+/// File of `files` that `keep_policy` keeps: "newest" or "oldest" by mtime,
+/// "shortest_path", or "first" as listed
+fn pick_keeper<'a>(files: &'a [String], keep_policy: &str) -> Result<&'a String, String> {
+    let modified = |f: &&String| fs::metadata(f.as_str()).and_then(|m| m.modified()).ok();
+    let keeper = match keep_policy {
+        "first" => files.first(),
+        "newest" => files.iter().max_by_key(modified),
+        // Files without a readable mtime are never picked as the oldest
+        "oldest" => files.iter().min_by_key(|f| modified(f).map_or((1, None), |t| (0, Some(t)))),
+        "shortest_path" => files.iter().min_by_key(|f| (f.chars().count(), f.as_str())),
+        other => return Err(format!("Unknown keep policy: {}", other)),
+    };
+    keeper.ok_or_else(|| "Duplicate group has no files".to_string())
+}
+
+/// Quotes `path` as one literal argument for `shell` ("sh", "ps1" or "bat")
+fn shell_quote(path: &str, shell: &str) -> Result<String, String> {
+    match shell {
+        "sh" if path.chars().any(char::is_control) => {
+            // ANSI-C quoting keeps a name with a newline on one (commentable) line
+            let mut quoted = String::from("$'");
+            for c in path.chars() {
+                match c {
+                    '\\' => quoted.push_str("\\\\"),
+                    '\'' => quoted.push_str("\\'"),
+                    '\n' => quoted.push_str("\\n"),
+                    '\r' => quoted.push_str("\\r"),
+                    '\t' => quoted.push_str("\\t"),
+                    c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+                    c => quoted.push(c),
+                }
+            }
+            quoted.push('\'');
+            Ok(quoted)
+        }
+        "sh" => Ok(format!("'{}'", path.replace('\'', "'\\''"))),
+        _ if path.contains(['\n', '\r']) => Err(format!("Path can't be written to a {} script: {:?}", shell, path)),
+        // PowerShell also ends a single-quoted string at U+2018-U+201B, so those are doubled too
+        "ps1" => {
+            let mut quoted = String::from("'");
+            for c in path.chars() {
+                if matches!(c, '\'' | '\u{2018}'..='\u{201B}') {
+                    quoted.push(c);
+                }
+                quoted.push(c);
+            }
+            quoted.push('\'');
+            Ok(quoted)
+        }
+        // `"` can't appear in Windows names; `%` would still expand inside quotes
+        "bat" => Ok(format!("\"{}\"", path.replace('%', "%%"))),
+        other => Err(format!("Unsupported shell: {}", other)),
+    }
+}
+
+/// Writes a script deleting every file of each group except the one `keep_policy` keeps,
+/// for `shell` "sh" (default), "ps1" or "bat". Each group is annotated with its hash and
+/// keeper, and the delete commands are commented out unless `active` is true.
+#[command]
+pub fn export_dedupe_script(
+    groups: Vec<GroupFiles>,
+    keep_policy: Option<String>,
+    output_path: String,
+    shell: Option<String>,
+    active: Option<bool>,
+) -> Result<DedupeScriptSummary, String> {
+    let shell = shell.unwrap_or_else(|| "sh".to_string()).to_lowercase();
+    let keep_policy = keep_policy.unwrap_or_else(|| "newest".to_string());
+    let (comment, delete, header) = match shell.as_str() {
+        "sh" => ("#", "rm -f --", "#!/bin/sh\n"),
+        "ps1" => ("#", "Remove-Item -Force -LiteralPath", ""),
+        "bat" => ("REM", "del /f /q", "@echo off\n"),
+        other => return Err(format!("Unsupported shell: {}", other)),
+    };
+    let prefix = if active.unwrap_or(false) { String::new() } else { format!("{} ", comment) };
+
+    let mut script = String::from(header);
+    script.push_str(&format!(
+        "{} Duplicate cleanup generated {}, keeping the {} file of each group\n",
+        comment,
+        chrono::Utc::now().to_rfc3339(),
+        keep_policy
+    ));
+
+    let mut summary = DedupeScriptSummary { groups: 0, kept: 0, deletions: 0 };
+    for group in groups.iter().filter(|g| g.files.len() > 1) {
+        let keeper = pick_keeper(&group.files, &keep_policy)?;
+        // Comment lines carry the raw path, so strip anything that would end the line
+        let keeper_label: String = keeper.chars().filter(|c| !c.is_control()).collect();
+        script.push_str(&format!("\n{} hash {}\n{} keep {}\n", comment, group.hash, comment, keeper_label));
+
+        let mut seen = HashSet::from([keeper.as_str()]);
+        for file in &group.files {
+            if !seen.insert(file.as_str()) {
+                continue;
+            }
+            script.push_str(&format!("{}{} {}\n", prefix, delete, shell_quote(file, &shell)?));
+            summary.deletions += 1;
+        }
+        summary.groups += 1;
+        summary.kept += 1;
+    }
+
+    if shell == "bat" {
+        script = script.replace('\n', "\r\n");
+    }
+    fs::write(&output_path, script).map_err(|e| format!("Failed to write script: {}", e))?;
+    Ok(summary)
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DuplicateDirectoryGroup {
+    pub hash: String,
+    pub directories: Vec<String>,
+    pub file_count: usize,
+    pub total_size: u64,
+}
+
+/// Digest, file count and size of one directory subtree
+struct DirDigest {
+    hash: String,
+    file_count: usize,
+    total_size: u64,
+}
+
+/// What digesting a subtree produced
+enum Subtree {
+    /// Holds no files, so it is left out of its parent's digest
+    Empty,
+    Digest(DirDigest),
+    /// Something in it couldn't be read. Its digest would match a tree that really lacks
+    /// the unread part, so neither it nor any directory above it is grouped.
+    Unreadable,
+}
+
+/// Recursively digests `dir` from its children's names and content hashes, leaving out
+/// children `options` hides. Every non-empty, fully readable directory is recorded in `digests`.
+fn digest_directory(
+    on_progress: &dyn Fn(&DuplicateProgress),
+    options: &WalkOptions,
+    dir: &Path,
+    digests: &mut HashMap<String, Vec<(PathBuf, usize, u64)>>,
+    scanned: &mut usize,
+) -> Subtree {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Subtree::Unreadable;
+    };
+
+    let mut children: Vec<(String, String)> = Vec::new();
+    let mut file_count = 0;
+    let mut total_size = 0;
+    // Keep going after an unreadable child so the readable subdirectories are still recorded
+    let mut unreadable = false;
+
+    for entry in entries {
+        let Ok(entry) = entry else {
+            unreadable = true;
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        if !options.allows_path(&path) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            unreadable = true;
+            continue;
+        };
+
+        if file_type.is_dir() {
+            match digest_directory(on_progress, options, &path, digests, scanned) {
+                Subtree::Empty => {}
+                Subtree::Digest(sub) => {
+                    children.push((format!("d:{}", name), sub.hash));
+                    file_count += sub.file_count;
+                    total_size += sub.total_size;
+                }
+                Subtree::Unreadable => unreadable = true,
+            }
+        } else if file_type.is_file() {
+            let Ok(hash) = file_hash(&path) else {
+                unreadable = true;
+                continue;
+            };
+            children.push((format!("f:{}", name), hash));
+            file_count += 1;
+            total_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+
+            *scanned += 1;
+            if *scanned % 250 == 0 {
+                let progress = DuplicateProgress {
+                    scanned: *scanned,
+                    candidates: digests.len(),
+                    duplicates_found: digests.values().filter(|v| v.len() > 1).count(),
+                };
+                on_progress(&progress);
+            }
+        }
+    }
+
+    if unreadable {
+        return Subtree::Unreadable;
+    }
+    if file_count == 0 {
+        return Subtree::Empty;
+    }
+
+    children.sort();
+    let mut hasher = Sha256::new();
+    for (name, hash) in &children {
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        hasher.update(hash.as_bytes());
+        hasher.update([0]);
+    }
+    let hash = format!("{:x}", hasher.finalize());
+
+    digests
+        .entry(hash.clone())
+        .or_default()
+        .push((dir.to_path_buf(), file_count, total_size));
+
+    Subtree::Digest(DirDigest { hash, file_count, total_size })
+}
+
+/// Finds directories under `root` whose whole subtrees are identical (same names, same contents).
+/// Groups nested inside another duplicate group are left out, so only the top-most copies are reported.
+/// Directories containing anything unreadable are never grouped. Entries hidden by
+/// `walk_options` are left out of the comparison; symlinks are never followed.
+#[command(async)]
+pub fn find_duplicate_directories(
+    window: Window,
+    root: String,
+    walk_options: Option<WalkOptions>,
+) -> Result<Vec<DuplicateDirectoryGroup>, String> {
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    Ok(duplicate_directories(root_path, &walk_options.unwrap_or_default(), &|progress| {
+        let _ = window.emit("duplicate_progress", progress);
+    }))
+}
+
+/// `find_duplicate_directories` reporting progress to `on_progress`
+fn duplicate_directories(
+    root: &Path,
+    options: &WalkOptions,
+    on_progress: &dyn Fn(&DuplicateProgress),
+) -> Vec<DuplicateDirectoryGroup> {
+    let mut digests: HashMap<String, Vec<(PathBuf, usize, u64)>> = HashMap::new();
+    let mut scanned = 0;
+    digest_directory(on_progress, options, root, &mut digests, &mut scanned);
+
+    let duplicated: std::collections::HashSet<PathBuf> = digests
+        .values()
+        .filter(|dirs| dirs.len() > 1)
+        .flat_map(|dirs| dirs.iter().map(|(p, _, _)| p.clone()))
+        .collect();
+
+    let mut groups: Vec<DuplicateDirectoryGroup> = digests
+        .into_iter()
+        .filter(|(_, dirs)| dirs.len() > 1)
+        .filter(|(_, dirs)| {
+            !dirs
+                .iter()
+                .all(|(p, _, _)| p.parent().map(|parent| duplicated.contains(parent)).unwrap_or(false))
+        })
+        .map(|(hash, dirs)| DuplicateDirectoryGroup {
+            hash,
+            file_count: dirs[0].1,
+            total_size: dirs[0].2,
+            directories: dirs.into_iter().map(|(p, _, _)| p.to_string_lossy().to_string()).collect(),
+        })
+        .collect();
+
+    groups.sort_by(|a, b| b.total_size.cmp(&a.total_size));
+
+    let final_progress = DuplicateProgress {
+        scanned,
+        candidates: groups.iter().map(|g| g.directories.len()).sum(),
+        duplicates_found: groups.len(),
+    };
+    on_progress(&final_progress);
+
+    groups
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct NormalizationConflict {
+    pub normalized: String,
+    pub paths: Vec<String>,
+}
+
+/// Finds paths under `dir` that are the same name in Unicode NFC form but differ in bytes,
+/// e.g. an NFD name written by macOS next to the NFC name written by Linux.
+#[command(async)]
+pub fn find_normalization_conflicts(
+    dir: String,
+    walk_options: Option<WalkOptions>,
+) -> Result<Vec<NormalizationConflict>, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let options = walk_options.unwrap_or_default();
+
+    let mut by_normalized: HashMap<String, Vec<String>> = HashMap::new();
+    for entry in options.walk(&dir).filter(|e| e.depth() > 0) {
+        let path = entry.path().to_string_lossy().to_string();
+        let normalized: String = path.nfc().collect();
+        by_normalized.entry(normalized).or_default().push(path);
+    }
+
+    let mut conflicts: Vec<NormalizationConflict> = by_normalized
+        .into_iter()
+        .filter_map(|(normalized, mut paths)| {
+            paths.sort();
+            paths.dedup();
+            (paths.len() > 1).then_some(NormalizationConflict { normalized, paths })
+        })
+        .collect();
+    conflicts.sort_by(|a, b| a.normalized.cmp(&b.normalized));
+
+    Ok(conflicts)
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DirectoryHash {
+    pub hash: String,
+    pub file_count: usize,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HashProgress {
+    pub hashed: usize,
+    pub total: usize,
+}
+
+/// Every file under `root` that `options` allows, as a `/`-separated relative path and
+/// its full path, sorted
+fn relative_files(root: &Path, options: &WalkOptions) -> Result<Vec<(String, PathBuf)>, String> {
+    let mut files: Vec<(String, PathBuf)> = Vec::new();
+    for entry in options.walk_dir(root).into_iter().filter_entry(|e| options.allows_entry(e)) {
+        let entry = entry.map_err(|e| format!("Failed to walk directory: {}", e))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(root)
+            .map_err(|e| e.to_string())?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push((relative, entry.path().to_path_buf()));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Digest over the sorted relative paths and content hashes of every file under `path`.
+/// Two trees with the same files and contents give the same digest, whatever order
+/// they are walked in, so a backup can be compared against its source.
+#[command(async)]
+pub fn hash_directory(window: Window, path: String, walk_options: Option<WalkOptions>) -> Result<DirectoryHash, String> {
+    let root = Path::new(&path);
+    if !root.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    directory_digest(root, &walk_options.unwrap_or_default(), &|progress| {
+        let _ = window.emit("hash_progress", progress);
+    })
+}
+
+/// `hash_directory` reporting progress to `on_progress`
+fn directory_digest(root: &Path, options: &WalkOptions, on_progress: &dyn Fn(HashProgress)) -> Result<DirectoryHash, String> {
+    let files = relative_files(root, options)?;
+
+    let total = files.len();
+    let mut hasher = Sha256::new();
+    let mut total_size = 0;
+    for (hashed, (relative, file)) in files.iter().enumerate() {
+        let hash = file_hash(file).map_err(|e| format!("Failed to hash {}: {}", relative, e))?;
+        total_size += fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update(hash.as_bytes());
+        hasher.update([0]);
+
+        if (hashed + 1) % 250 == 0 {
+            on_progress(HashProgress { hashed: hashed + 1, total });
+        }
+    }
+    on_progress(HashProgress { hashed: total, total });
+
+    Ok(DirectoryHash {
+        hash: format!("{:x}", hasher.finalize()),
+        file_count: total,
+        total_size,
+    })
+}
+
+/// Size, content hash and modification time of one file in a hash manifest
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ManifestEntry {
+    pub size: u64,
+    pub hash: String,
+    pub modified: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct HashManifest {
+    pub created_at: String,
+    pub files: std::collections::BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+pub struct ManifestDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+    /// Modified files whose mtime still matches the manifest, which normal edits don't
+    /// produce: a sign of tampering, a rollback or a sync tool restoring timestamps
+    pub content_changed_no_mtime: Vec<String>,
+}
+
+/// Hashes every file under `root`, keyed by relative path, reporting progress to `on_progress`
+fn build_manifest(root: &Path, options: &WalkOptions, on_progress: &dyn Fn(HashProgress)) -> Result<HashManifest, String> {
+    let files = relative_files(root, options)?;
+    let total = files.len();
+
+    let mut entries = std::collections::BTreeMap::new();
+    for (hashed, (relative, file)) in files.into_iter().enumerate() {
+        let metadata = fs::metadata(&file).map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+        let hash = file_hash(&file).map_err(|e| format!("Failed to hash {}: {}", relative, e))?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339());
+        entries.insert(relative, ManifestEntry { size: metadata.len(), hash, modified });
+
+        if (hashed + 1) % 250 == 0 {
+            on_progress(HashProgress { hashed: hashed + 1, total });
+        }
+    }
+    on_progress(HashProgress { hashed: total, total });
+
+    Ok(HashManifest {
+        created_at: chrono::Utc::now().to_rfc3339(),
+        files: entries,
+    })
+}
+
+/// Snapshots the size, hash and mtime of every file under `dir` into a JSON manifest
+#[command(async)]
+pub fn create_manifest(
+    window: Window,
+    dir: String,
+    output_path: String,
+    walk_options: Option<WalkOptions>,
+) -> Result<usize, String> {
+    let root = Path::new(&dir);
+    if !root.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let manifest = build_manifest(root, &walk_options.unwrap_or_default(), &|progress| {
+        let _ = window.emit("hash_progress", progress);
+    })?;
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    fs::write(&output_path, json).map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    Ok(manifest.files.len())
+}
+
+/// Compares `dir` against a manifest from `create_manifest`.
+/// A file counts as modified when its size or content hash differs; mtime alone doesn't count.
+/// Modified files with an unchanged mtime are also listed under `content_changed_no_mtime`.
+/// Pass the same `walk_options` the manifest was created with, or filtered files show up
+/// as removed.
+#[command(async)]
+pub fn verify_manifest(
+    window: Window,
+    dir: String,
+    manifest_path: String,
+    walk_options: Option<WalkOptions>,
+) -> Result<ManifestDiff, String> {
+    let root = Path::new(&dir);
+    if !root.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let stored: HashManifest = serde_json::from_slice(
+        &fs::read(&manifest_path).map_err(|e| format!("Failed to read manifest: {}", e))?,
+    )
+    .map_err(|e| format!("Invalid manifest: {}", e))?;
+    let current = build_manifest(root, &walk_options.unwrap_or_default(), &|progress| {
+        let _ = window.emit("hash_progress", progress);
+    })?;
+
+    Ok(diff_manifests(&stored, &current))
+}
+
+/// What changed from the `stored` manifest to the `current` one
+fn diff_manifests(stored: &HashManifest, current: &HashManifest) -> ManifestDiff {
+    let mut diff = ManifestDiff::default();
+    for (path, entry) in &current.files {
+        match stored.files.get(path) {
+            None => diff.added.push(path.clone()),
+            Some(old) if old.size != entry.size || old.hash != entry.hash => {
+                if old.modified.is_some() && old.modified == entry.modified {
+                    diff.content_changed_no_mtime.push(path.clone());
+                }
+                diff.modified.push(path.clone());
+            }
+            Some(_) => {}
+        }
+    }
+    diff.removed = stored
+        .files
+        .keys()
+        .filter(|path| !current.files.contains_key(*path))
+        .cloned()
+        .collect();
+
+    diff
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct HashMatch {
+    pub path: String,
+    pub hash: String,
+}
+
+/// Digest algorithms accepted by `find_by_hashes`
+pub(crate) const HASH_ALGORITHMS: &[&str] = &["sha256", "sha512"];
+
+/// The file digest function for one of `HASH_ALGORITHMS`
+fn digest_for(algo: &str) -> Result<fn(&Path) -> io::Result<String>, String> {
+    match algo {
+        "sha256" => Ok(file_digest::<Sha256>),
+        "sha512" => Ok(file_digest::<Sha512>),
+        other => Err(format!("Unsupported hash algorithm: {}", other)),
+    }
+}
+
+/// Finds files under `dir` whose `algo` digest ("sha256", the default, or "sha512") is in
+/// `hashes`. When the sizes of the wanted files are known, passing them as `sizes`
+/// skips hashing every file of another size.
+#[command(async)]
+pub fn find_by_hashes(
+    window: Window,
+    state_mux: State<'_, StateSafe>,
+    dir: String,
+    hashes: Vec<String>,
+    algo: Option<String>,
+    sizes: Option<Vec<u64>>,
+    walk_options: Option<WalkOptions>,
+) -> Result<Vec<HashMatch>, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let options = walk_options.unwrap_or_default();
+
+    let digest = digest_for(algo.as_deref().unwrap_or("sha256"))?;
+    let sizes: Option<HashSet<u64>> = sizes.map(|s| s.into_iter().collect());
+
+    let candidates: Vec<PathBuf> = options
+        .walk(&dir)
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| match &sizes {
+            Some(sizes) => e.metadata().map(|m| sizes.contains(&m.len())).unwrap_or(false),
+            None => true,
+        })
+        .map(|e| e.into_path())
+        .collect();
+
+    let total = candidates.len();
+    let pause = scan_pause(&state_mux);
+
+    let matches = match_hashes(&candidates, &hashes, digest, &|done| {
+        if done % 250 == 0 {
+            let _ = window.emit("hash_progress", HashProgress { hashed: done, total });
+        }
+        pause.wait_if_paused(|event| emit_scan_event(&window, event));
+    });
+    let _ = window.emit("hash_progress", HashProgress { hashed: total, total });
+
+    Ok(matches)
+}
+
+/// The `candidates` whose `digest` is one of `hashes`, which may be in any case and padded
+/// with whitespace, sorted by path. `on_hashed` gets the running count after each file.
+fn match_hashes(
+    candidates: &[PathBuf],
+    hashes: &[String],
+    digest: fn(&Path) -> io::Result<String>,
+    on_hashed: &(dyn Fn(usize) + Sync),
+) -> Vec<HashMatch> {
+    let wanted: HashSet<String> = hashes.iter().map(|h| h.trim().to_lowercase()).collect();
+    let hashed = AtomicUsize::new(0);
+
+    let mut matches: Vec<HashMatch> = candidates
+        .par_iter()
+        .filter_map(|path| {
+            let hash = digest(path).ok();
+            on_hashed(hashed.fetch_add(1, Ordering::Relaxed) + 1);
+
+            let hash = hash?;
+            wanted.contains(&hash).then(|| HashMatch {
+                path: path.to_string_lossy().to_string(),
+                hash,
+            })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| a.path.cmp(&b.path));
+    matches
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SimilarPair {
+    pub a: String,
+    pub b: String,
+    /// Estimated share of word shingles the two files have in common, 0-100
+    pub similarity: f64,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SimilarTextGroup {
+    pub files: Vec<String>,
+    /// Every pair in the group that reached the threshold, most similar first
+    pub pairs: Vec<SimilarPair>,
+}
+
+/// Mixes a 64-bit value (splitmix64 finalizer), used to derive the MinHash functions
+fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// MinHash signature over the word shingles of a text file, or `None` if the file doesn't
+/// look like text. Only the first `MAX_SIMILARITY_BYTES` are read.
+fn text_signature(path: &Path) -> Option<[u64; MINHASH_SIZE]> {
+    use std::hash::{Hash, Hasher};
+
+    let mut bytes = Vec::new();
+    fs::File::open(path).ok()?.take(MAX_SIMILARITY_BYTES).read_to_end(&mut bytes).ok()?;
+    if bytes.is_empty() || bytes.iter().take(8192).any(|&b| b == 0) {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&bytes).to_lowercase();
+    let words: Vec<&str> = text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut signature = [u64::MAX; MINHASH_SIZE];
+    for shingle in words.windows(SHINGLE_WORDS.min(words.len())) {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        shingle.hash(&mut hasher);
+        let base = hasher.finish();
+        for (i, slot) in signature.iter_mut().enumerate() {
+            *slot = (*slot).min(mix64(base ^ mix64(i as u64 + 1)));
+        }
+    }
+    Some(signature)
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Groups text files under `dir` whose contents are at least `threshold` similar
+/// (0-1, default 0.8), estimated with MinHash over 3-word shingles. Files are compared
+/// by their first 1 MiB; binary files are skipped. Emits `hash_progress` while reading.
+#[command(async)]
+pub fn find_similar_text_files(
+    window: Window,
+    dir: String,
+    threshold: Option<f64>,
+    walk_options: Option<WalkOptions>,
+) -> Result<Vec<SimilarTextGroup>, String> {
+    if !Path::new(&dir).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let threshold = threshold.unwrap_or(0.8).clamp(0.0, 1.0);
+
+    Ok(similar_text_files(&dir, threshold, &walk_options.unwrap_or_default(), &|progress| {
+        let _ = window.emit("hash_progress", progress);
+    }))
+}
+
+/// `find_similar_text_files` reporting progress to `on_progress`
+fn similar_text_files(
+    dir: &str,
+    threshold: f64,
+    options: &WalkOptions,
+    on_progress: &(dyn Fn(HashProgress) + Sync),
+) -> Vec<SimilarTextGroup> {
+    let files: Vec<PathBuf> = options
+        .walk(dir)
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
+    let total = files.len();
+    let read = AtomicUsize::new(0);
+
+    let signatures: Vec<(String, [u64; MINHASH_SIZE])> = files
+        .par_iter()
+        .filter_map(|path| {
+            let signature = text_signature(path);
+            let done = read.fetch_add(1, Ordering::Relaxed) + 1;
+            if done % 250 == 0 {
+                on_progress(HashProgress { hashed: done, total });
+            }
+            Some((path.to_string_lossy().to_string(), signature?))
+        })
+        .collect();
+    on_progress(HashProgress { hashed: total, total });
+
+    // Files sharing every row of any band are candidates, so most pairs are never compared
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    for band in 0..MINHASH_SIZE / MINHASH_BAND_ROWS {
+        let rows = band * MINHASH_BAND_ROWS..(band + 1) * MINHASH_BAND_ROWS;
+        let mut buckets: HashMap<&[u64], Vec<usize>> = HashMap::new();
+        for (index, (_, signature)) in signatures.iter().enumerate() {
+            buckets.entry(&signature[rows.clone()]).or_default().push(index);
+        }
+        for bucket in buckets.values().filter(|b| b.len() > 1) {
+            for (i, &a) in bucket.iter().enumerate() {
+                for &b in &bucket[i + 1..] {
+                    candidates.insert((a, b));
+                }
+            }
+        }
+    }
+
+    let mut parents: Vec<usize> = (0..signatures.len()).collect();
+    let mut pairs = Vec::new();
+    for (a, b) in candidates {
+        let equal = signatures[a].1.iter().zip(&signatures[b].1).filter(|(x, y)| x == y).count();
+        let similarity = equal as f64 / MINHASH_SIZE as f64;
+        if similarity >= threshold {
+            let (root_a, root_b) = (find_root(&mut parents, a), find_root(&mut parents, b));
+            parents[root_a] = root_b;
+            pairs.push((a, b, similarity));
+        }
+    }
+
+    let mut groups: HashMap<usize, SimilarTextGroup> = HashMap::new();
+    for (a, b, similarity) in pairs {
+        let root = find_root(&mut parents, a);
+        let group = groups.entry(root).or_insert_with(|| SimilarTextGroup { files: Vec::new(), pairs: Vec::new() });
+        group.pairs.push(SimilarPair {
+            a: signatures[a].0.clone(),
+            b: signatures[b].0.clone(),
+            similarity: (similarity * 1000.0).round() / 10.0,
+        });
+        for index in [a, b] {
+            if !group.files.contains(&signatures[index].0) {
+                group.files.push(signatures[index].0.clone());
+            }
+        }
+    }
+
+    let mut groups: Vec<SimilarTextGroup> = groups.into_values().collect();
+    for group in &mut groups {
+        group.files.sort();
+        group.pairs.sort_by(|x, y| y.similarity.total_cmp(&x.similarity).then_with(|| x.a.cmp(&y.a)));
+    }
+    groups.sort_by(|x, y| y.files.len().cmp(&x.files.len()).then_with(|| x.files.cmp(&y.files)));
+    groups
+}
+
+/// One content-defined chunk of a file
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileChunk {
+    pub offset: u64,
+    pub length: u64,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ChunkFingerprint {
+    pub path: String,
+    pub size: u64,
+    pub avg_chunk_size: u64,
+    pub chunks: Vec<FileChunk>,
+}
+
+/// Gear table of the rolling hash, derived from `mix64` so fingerprints are stable
+/// across runs and machines
+fn gear_table() -> &'static [u64; 256] {
+    static GEAR: std::sync::OnceLock<[u64; 256]> = std::sync::OnceLock::new();
+    GEAR.get_or_init(|| std::array::from_fn(|i| mix64(i as u64 + 0x9e3779b97f4a7c15)))
+}
+
+/// Splits a file into content-defined chunks (FastCDC-style gear hash with normalized
+/// chunking) and hashes each with SHA-256. Cut points depend only on nearby bytes, so an
+/// insertion or edit only changes the chunks around it and files sharing large regions
+/// share most chunk hashes. `avg_chunk_kb` is rounded up to a power of two (1 KiB–1 MiB).
+#[command(async)]
+pub fn chunk_fingerprint(path: String, avg_chunk_kb: Option<u32>) -> Result<ChunkFingerprint, String> {
+    let mut file = fs::File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let metadata = file.metadata().map_err(|e| format!("Failed to read metadata: {}", e))?;
+    if !metadata.is_file() {
+        return Err("Path is not a file".to_string());
+    }
+    let size = metadata.len();
+
+    let avg = (avg_chunk_kb.unwrap_or(DEFAULT_CDC_AVG_KB).clamp(1, 1024) as usize * 1024).next_power_of_two();
+    let (min, max) = (avg / 4, avg * 8);
+    // Cut-point bits sit at the top of the hash so each depends on the last 64 bytes.
+    // A stricter mask below the average and a looser one above it narrow the size spread.
+    let bits = avg.trailing_zeros();
+    let top_bits = |n: u32| if n == 0 { 0 } else { u64::MAX << (64 - n) };
+    let (mask_small, mask_large) = (top_bits(bits + 2), top_bits(bits.saturating_sub(2)));
+    let gear = gear_table();
+
+    let mut chunks = Vec::new();
+    let mut offset = 0u64;
+    let mut hasher = Sha256::new();
+    let mut length = 0usize;
+    let mut rolling = 0u64;
+    let mut emit = |hasher: &mut Sha256, length: &mut usize, rolling: &mut u64| {
+        chunks.push(FileChunk {
+            offset,
+            length: *length as u64,
+            hash: hex::encode(std::mem::take(hasher).finalize()),
+        });
+        offset += *length as u64;
+        *length = 0;
+        *rolling = 0;
+    };
+
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        let n = file.read(&mut buffer).map_err(|e| format!("Failed to read file: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        let mut start = 0;
+        for (i, &byte) in buffer[..n].iter().enumerate() {
+            length += 1;
+            if length <= min {
+                continue;
+            }
+            rolling = (rolling << 1).wrapping_add(gear[byte as usize]);
+            let mask = if length < avg { mask_small } else { mask_large };
+            if rolling & mask == 0 || length >= max {
+                hasher.update(&buffer[start..=i]);
+                start = i + 1;
+                emit(&mut hasher, &mut length, &mut rolling);
+            }
+        }
+        hasher.update(&buffer[start..n]);
+    }
+    if length > 0 {
+        emit(&mut hasher, &mut length, &mut rolling);
+    }
+
+    Ok(ChunkFingerprint {
+        path,
+        size,
+        avg_chunk_size: avg as u64,
+        chunks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::walk::hide_dotfiles;
+
+    fn path_string(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn pseudo_filesystems_and_excluded_paths_are_pruned() {
+        let exclude = vec![PathBuf::from("/data/backups")];
+
+        assert!(is_excluded(Path::new("/proc"), &[]));
+        assert!(is_excluded(Path::new("/data/backups/2024"), &exclude));
+        // Pseudo-filesystems match by exact path, exclusions by whole components
+        assert!(!is_excluded(Path::new("/home/proc"), &exclude));
+        assert!(!is_excluded(Path::new("/data/backups-old"), &exclude));
+    }
+
+    #[test]
+    fn identical_directories_are_grouped_at_the_top() {
+        let dir = tempfile::tempdir().unwrap();
+        for copy in ["a", "b"] {
+            let sub = dir.path().join(copy).join("nested");
+            fs::create_dir_all(&sub).unwrap();
+            fs::write(dir.path().join(copy).join("readme.txt"), "hello").unwrap();
+            fs::write(sub.join("data.bin"), "12345").unwrap();
+            fs::create_dir(dir.path().join(copy).join("empty")).unwrap();
+        }
+        fs::create_dir(dir.path().join("c")).unwrap();
+        fs::write(dir.path().join("c").join("readme.txt"), "changed").unwrap();
+
+        let groups = duplicate_directories(dir.path(), &WalkOptions::default(), &|_| {});
+        assert_eq!(groups.len(), 1);
+        let mut dirs = groups[0].directories.clone();
+        dirs.sort();
+        assert_eq!(dirs, vec![path_string(&dir.path().join("a")), path_string(&dir.path().join("b"))]);
+        assert_eq!((groups[0].file_count, groups[0].total_size), (2, 10));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_subtrees_are_never_grouped() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        for copy in ["a", "b"] {
+            fs::create_dir_all(dir.path().join(copy).join("locked")).unwrap();
+            fs::write(dir.path().join(copy).join("readme.txt"), "hello").unwrap();
+        }
+        let locked = dir.path().join("b").join("locked");
+        fs::write(locked.join("secret.txt"), "hidden").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        let readable = fs::read_dir(&locked).is_ok();
+
+        let groups = duplicate_directories(dir.path(), &WalkOptions::default(), &|_| {});
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        // Permissions don't stop root, so there is nothing to test there
+        if readable {
+            return;
+        }
+        // Without the unread file, b would look identical to a
+        assert!(groups.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn nfc_and_nfd_spellings_of_a_name_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("caf\u{e9}.txt"), "").unwrap();
+        fs::write(dir.path().join("cafe\u{301}.txt"), "").unwrap();
+        fs::write(dir.path().join("cafe.txt"), "").unwrap();
+
+        let conflicts = find_normalization_conflicts(path_string(dir.path()), None).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].normalized, path_string(&dir.path().join("caf\u{e9}.txt")));
+        assert_eq!(conflicts[0].paths.len(), 2);
+    }
+
+    #[test]
+    fn directory_digest_depends_on_names_and_contents_only() {
+        let original = tempfile::tempdir().unwrap();
+        let backup = tempfile::tempdir().unwrap();
+        for root in [original.path(), backup.path()] {
+            fs::create_dir(root.join("docs")).unwrap();
+            fs::write(root.join("docs").join("a.txt"), "alpha").unwrap();
+            fs::write(root.join("b.txt"), "beta").unwrap();
+        }
+        let options = WalkOptions::default();
+        let digest = |root: &Path| directory_digest(root, &options, &|_| {}).unwrap();
+
+        let expected = digest(original.path());
+        assert_eq!((expected.file_count, expected.total_size), (2, 9));
+        assert_eq!(digest(backup.path()).hash, expected.hash);
+
+        fs::write(backup.path().join("b.txt"), "BETA").unwrap();
+        assert_ne!(digest(backup.path()).hash, expected.hash);
+        fs::write(backup.path().join("b.txt"), "beta").unwrap();
+        fs::rename(backup.path().join("b.txt"), backup.path().join("c.txt")).unwrap();
+        assert_ne!(digest(backup.path()).hash, expected.hash);
+    }
+
+    #[test]
+    fn manifest_diff_reports_added_removed_and_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("kept.txt"), "same").unwrap();
+        fs::write(dir.path().join("edited.txt"), "before").unwrap();
+        fs::write(dir.path().join("deleted.txt"), "bye").unwrap();
+        let options = WalkOptions::default();
+        let stored = build_manifest(dir.path(), &options, &|_| {}).unwrap();
+
+        fs::write(dir.path().join("edited.txt"), "after, and longer").unwrap();
+        fs::remove_file(dir.path().join("deleted.txt")).unwrap();
+        fs::write(dir.path().join("new.txt"), "hi").unwrap();
+        let current = build_manifest(dir.path(), &options, &|_| {}).unwrap();
+
+        let diff = diff_manifests(&stored, &current);
+        assert_eq!(diff.added, vec!["new.txt"]);
+        assert_eq!(diff.removed, vec!["deleted.txt"]);
+        assert_eq!(diff.modified, vec!["edited.txt"]);
+    }
+
+    #[test]
+    fn touching_a_file_is_not_a_modification() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "same").unwrap();
+        let options = WalkOptions::default();
+        let stored = build_manifest(dir.path(), &options, &|_| {}).unwrap();
+
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+        let current = build_manifest(dir.path(), &options, &|_| {}).unwrap();
+
+        let diff = diff_manifests(&stored, &current);
+        assert!(diff.modified.is_empty() && diff.content_changed_no_mtime.is_empty());
+    }
+
+    #[test]
+    fn only_files_sharing_a_size_are_hashed() {
+        let size_map = HashMap::from([
+            (10, vec![PathBuf::from("a"), PathBuf::from("b")]),
+            (20, vec![PathBuf::from("c")]),
+            (30, vec![PathBuf::from("d"), PathBuf::from("e"), PathBuf::from("f")]),
+        ]);
+
+        let mut candidates = hash_candidates(size_map);
+        candidates.sort();
+        let names: Vec<(u64, &str)> = candidates.iter().map(|(size, p)| (*size, p.to_str().unwrap())).collect();
+        assert_eq!(names, vec![(10, "a"), (10, "b"), (30, "d"), (30, "e"), (30, "f")]);
+    }
+
+    #[test]
+    fn digests_match_known_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc.txt");
+        fs::write(&path, "abc").unwrap();
+
+        assert_eq!(
+            digest_for("sha256").unwrap()(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(digest_for("sha512").unwrap()(&path).unwrap().starts_with("ddaf35a193617aba"));
+        assert!(digest_for("md5").is_err());
+        assert!(HASH_ALGORITHMS.iter().all(|algo| digest_for(algo).is_ok()));
+    }
+
+    #[test]
+    fn groups_flag_copies_with_different_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, "same bytes").unwrap();
+            path_string(&path)
+        };
+        let files = vec![write("report.PDF"), write("copy.pdf"), write("report.bak"), write("README")];
+
+        let groups = confirm_duplicates(GroupFiles { hash: String::new(), files }, None).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].extensions, vec!["", "bak", "pdf"]);
+        assert!(groups[0].mixed_extensions);
+        assert_eq!(groups[0].reclaimable_bytes, 3 * 10);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn groups_flag_copies_with_different_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let open = dir.path().join("open.sh");
+        let private = dir.path().join("private.sh");
+        for (path, mode) in [(&open, 0o755), (&private, 0o600)] {
+            fs::write(path, "echo hi").unwrap();
+            fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+        let files = vec![path_string(&open), path_string(&private)];
+
+        let group = DuplicateGroup::new(String::new(), files, true);
+        assert!(group.permissions_differ);
+        assert_eq!(group.most_restrictive, Some(path_string(&private)));
+        assert_eq!(group.details[0].mode, Some(0o755));
+
+        fs::set_permissions(&open, fs::Permissions::from_mode(0o600)).unwrap();
+        let group = DuplicateGroup::new(String::new(), group.files, true);
+        assert!(!group.permissions_differ);
+    }
+
+    #[test]
+    fn digest_does_not_depend_on_buffer_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        let data: Vec<u8> = (0..20_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        fs::write(&path, &data).unwrap();
+        let expected = hex::encode(Sha256::digest(&data));
+
+        for buffer_size in [0, 1, 4096, HASH_BUFFER_SIZE] {
+            assert_eq!(file_digest_with::<Sha256>(&path, buffer_size, None).unwrap(), expected);
+            // A zero threshold maps every file on Windows; elsewhere the file is still read
+            assert_eq!(file_digest_with::<Sha256>(&path, buffer_size, Some(0)).unwrap(), expected);
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn files_open_for_writing_are_not_mapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        fs::write(&path, b"mapped").unwrap();
+        assert_eq!(mmap_digest::<Sha256>(&path, 4).unwrap(), hex::encode(Sha256::digest(b"mapped")));
+
+        let _writer = fs::OpenOptions::new().write(true).open(&path).unwrap();
+        assert!(mmap_digest::<Sha256>(&path, 4).is_none());
+        assert_eq!(file_digest_with::<Sha256>(&path, 4, Some(0)).unwrap(), hex::encode(Sha256::digest(b"mapped")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hardlinks_are_grouped_and_not_counted_as_waste() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir_in(dir.path().parent().unwrap()).unwrap();
+        let original = dir.path().join("original.bin");
+        fs::write(&original, [7u8; 100]).unwrap();
+        fs::hard_link(&original, dir.path().join("link.bin")).unwrap();
+        fs::hard_link(&original, outside.path().join("elsewhere.bin")).unwrap();
+        fs::write(dir.path().join("copy.bin"), [7u8; 100]).unwrap();
+
+        let groups = find_hardlink_groups(path_string(dir.path()), None).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!((groups[0].size, groups[0].link_count, groups[0].links_elsewhere), (100, 3, 1));
+        assert_eq!(groups[0].files, vec![path_string(&dir.path().join("link.bin")), path_string(&original)]);
+
+        let files: Vec<String> =
+            ["original.bin", "link.bin", "copy.bin"].iter().map(|name| path_string(&dir.path().join(name))).collect();
+        let group = DuplicateGroup::new(String::new(), files.clone(), true);
+        assert!(group.hardlinked);
+        assert_eq!(group.reclaimable_bytes, 100);
+        assert_eq!(DuplicateGroup::new(String::new(), files, false).reclaimable_bytes, 200);
+    }
+
+    #[test]
+    fn content_changes_behind_an_unchanged_mtime_are_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.csv");
+        fs::write(&path, "total,100").unwrap();
+        let options = WalkOptions::default();
+        let stored = build_manifest(dir.path(), &options, &|_| {}).unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+
+        fs::write(&path, "total,999").unwrap();
+        fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        let current = build_manifest(dir.path(), &options, &|_| {}).unwrap();
+
+        let diff = diff_manifests(&stored, &current);
+        assert_eq!(diff.modified, vec!["ledger.csv"]);
+        assert_eq!(diff.content_changed_no_mtime, vec!["ledger.csv"]);
+    }
+
+    #[test]
+    fn paths_are_quoted_for_each_shell() {
+        assert_eq!(shell_quote("it's here", "sh").unwrap(), r"'it'\''s here'");
+        assert_eq!(shell_quote("line\nbreak", "sh").unwrap(), r"$'line\nbreak'");
+        // PowerShell also ends a string at a curly quote, so those are doubled too
+        assert_eq!(shell_quote("it's \u{2019}x", "ps1").unwrap(), "'it''s \u{2019}\u{2019}x'");
+        assert_eq!(shell_quote(r"C:\100%\a.txt", "bat").unwrap(), r#""C:\100%%\a.txt""#);
+        assert!(shell_quote("line\nbreak", "ps1").is_err());
+        assert!(shell_quote("a", "fish").is_err());
+    }
+
+    #[test]
+    fn dedupe_script_keeps_one_file_per_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<String> = ["a.txt", "longer_name.txt"].iter().map(|n| path_string(&dir.path().join(n))).collect();
+        let single = GroupFiles { hash: "lonely".to_string(), files: vec![files[0].clone()] };
+        let group = GroupFiles { hash: "abc123".to_string(), files: files.clone() };
+        let output = dir.path().join("dedupe.sh");
+
+        let summary = export_dedupe_script(
+            vec![group, single],
+            Some("shortest_path".to_string()),
+            path_string(&output),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!((summary.groups, summary.kept, summary.deletions), (1, 1, 1));
+
+        let script = fs::read_to_string(&output).unwrap();
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains(&format!("# keep {}\n", files[0])));
+        assert!(script.contains(&format!("# rm -f -- '{}'\n", files[1])));
+        assert!(!script.contains("lonely"));
+    }
+
+    #[test]
+    fn sampled_hashes_are_confirmed_by_full_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.bin");
+        let b = dir.path().join("b.bin");
+        let c = dir.path().join("c.bin");
+        let data = vec![1u8; 100];
+        let mut edited = data.clone();
+        // Outside the 4-byte samples at 0, 48 and 96
+        edited[20] = 2;
+        fs::write(&a, &data).unwrap();
+        fs::write(&b, &edited).unwrap();
+        fs::write(&c, &data).unwrap();
+
+        let sample = sample_hash(&a, 4).unwrap();
+        assert!(sample.starts_with("sample:"));
+        assert_eq!(sample_hash(&b, 4).unwrap(), sample);
+        assert_ne!(file_hash(&a).unwrap(), file_hash(&b).unwrap());
+
+        let files = vec![path_string(&a), path_string(&b), path_string(&c)];
+        let confirmed = confirm_duplicates(GroupFiles { hash: sample, files }, None).unwrap();
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].files.len(), 2);
+        assert!(!confirmed[0].files.contains(&path_string(&b)) && !confirmed[0].approximate);
+    }
+
+    #[test]
+    fn text_signatures_estimate_similarity() {
+        let dir = tempfile::tempdir().unwrap();
+        let words: Vec<String> = (0..200).map(|i| format!("word{}", i)).collect();
+        let mut edited = words.clone();
+        edited[100] = "changed".to_string();
+        let write = |name: &str, data: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, data).unwrap();
+            text_signature(&path)
+        };
+        let similarity = |a: &[u64; MINHASH_SIZE], b: &[u64; MINHASH_SIZE]| {
+            a.iter().zip(b).filter(|(x, y)| x == y).count() as f64 / MINHASH_SIZE as f64
+        };
+
+        let original = write("original.txt", words.join(" ").as_bytes()).unwrap();
+        let upper = write("upper.txt", words.join("  ").to_uppercase().as_bytes()).unwrap();
+        let near = write("near.txt", edited.join(" ").as_bytes()).unwrap();
+        let other = write("other.txt", b"something else entirely with other words").unwrap();
+
+        // Case and spacing don't matter, one edited word changes only its shingles
+        assert_eq!(similarity(&original, &upper), 1.0);
+        assert!(similarity(&original, &near) > 0.8);
+        assert!(similarity(&original, &other) < 0.2);
+        assert!(write("binary.bin", b"abc\0def").is_none());
+        assert!(write("empty.txt", b"").is_none());
+    }
+
+    #[test]
+    fn chunk_fingerprints_survive_insertions() {
+        let dir = tempfile::tempdir().unwrap();
+        let data: Vec<u8> = (0..256 * 1024u64).map(|i| mix64(i) as u8).collect();
+        let mut inserted = data[..100_000].to_vec();
+        inserted.extend_from_slice(b"a few inserted bytes");
+        inserted.extend_from_slice(&data[100_000..]);
+        let original = dir.path().join("original.bin");
+        let edited = dir.path().join("edited.bin");
+        fs::write(&original, &data).unwrap();
+        fs::write(&edited, &inserted).unwrap();
+
+        let a = chunk_fingerprint(path_string(&original), Some(4)).unwrap();
+        let b = chunk_fingerprint(path_string(&edited), Some(4)).unwrap();
+        assert_eq!(a.avg_chunk_size, 4096);
+        assert_eq!(a.chunks.iter().map(|c| c.length).sum::<u64>(), a.size);
+        assert!(a.chunks.iter().all(|c| c.length <= 8 * 4096));
+        assert!(a.chunks.windows(2).all(|w| w[0].offset + w[0].length == w[1].offset));
+
+        let shared = b.chunks.iter().filter(|c| a.chunks.iter().any(|o| o.hash == c.hash)).count();
+        assert!(shared + 3 >= a.chunks.len(), "{} of {} chunks shared", shared, a.chunks.len());
+    }
+
+    #[test]
+    fn chunk_fingerprint_rejects_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(chunk_fingerprint(path_string(dir.path()), None).is_err());
+    }
+
+    #[test]
+    fn hidden_entries_can_be_left_out_of_directory_comparisons() {
+        let dir = tempfile::tempdir().unwrap();
+        for copy in ["a", "b"] {
+            fs::create_dir(dir.path().join(copy)).unwrap();
+            fs::write(dir.path().join(copy).join("readme.txt"), "same").unwrap();
+        }
+        fs::create_dir(dir.path().join("a").join(".cache")).unwrap();
+        fs::write(dir.path().join("a").join(".cache").join("index"), "only in a").unwrap();
+
+        assert!(duplicate_directories(dir.path(), &WalkOptions::default(), &|_| {}).is_empty());
+
+        let groups = duplicate_directories(dir.path(), &hide_dotfiles(), &|_| {});
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].file_count, 1);
+    }
+
+    #[test]
+    fn only_files_with_a_wanted_hash_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = dir.path().join("known.txt");
+        fs::write(&known, "hello").unwrap();
+        let other = dir.path().join("other.txt");
+        fs::write(&other, "something else").unwrap();
+        let hashes = vec![
+            " 2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824 ".to_string(),
+            "0".repeat(64),
+        ];
+        let hashed = AtomicUsize::new(0);
+
+        let matches = match_hashes(&[known.clone(), other], &hashes, file_digest::<Sha256>, &|done| {
+            hashed.fetch_max(done, Ordering::Relaxed);
+        });
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, path_string(&known));
+        assert_eq!(matches[0].hash, "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824");
+        assert_eq!(hashed.into_inner(), 2);
+    }
+
+    #[test]
+    fn near_identical_text_files_are_grouped_apart_from_unrelated_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let report: Vec<String> = (0..300).map(|i| format!("quarterly{} figures{}", i % 17, i % 23)).collect();
+        let report = report.join(" ");
+        fs::write(dir.path().join("report.txt"), &report).unwrap();
+        fs::write(dir.path().join("report_v2.txt"), format!("{} final revision", report)).unwrap();
+        let notes: Vec<String> = (0..300).map(|i| format!("grocery{} list{}", i % 13, i % 29)).collect();
+        fs::write(dir.path().join("notes.txt"), notes.join(" ")).unwrap();
+
+        let groups = similar_text_files(&dir.path().to_string_lossy(), 0.8, &WalkOptions::default(), &|_| {});
+
+        assert_eq!(groups.len(), 1);
+        let names: Vec<&str> = groups[0].files.iter().map(|f| f.rsplit(['/', '\\']).next().unwrap()).collect();
+        assert_eq!(names, ["report.txt", "report_v2.txt"]);
+        assert!(groups[0].pairs[0].similarity >= 80.0);
+    }
+}
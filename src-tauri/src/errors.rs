@@ -4,6 +4,8 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error("{0}")]
     Custom(String),
+    #[error("Not enough free space: {required} bytes needed, {available} available")]
+    InsufficientSpace { required: u64, available: u64 },
 }
 
 impl serde::Serialize for Error {
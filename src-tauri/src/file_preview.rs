@@ -1,5 +1,5 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::command;
 use base64::{engine::general_purpose, Engine as _};
 use zip::read::ZipArchive;
@@ -8,23 +8,154 @@ use std::io::Cursor;
 use regex::Regex;
 use std::time::SystemTime;
 use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sysinfo::{System, SystemExt};
+use tauri::State;
+use crate::StateSafe;
+use lazy_static::lazy_static;
+use syntect::easy::ScopeRegionIterator;
+use syntect::parsing::{ParseState, ScopeStack, SyntaxSet};
+use syntect::util::LinesWithEndings;
 
 /// Maximum size for text preview (500 KB)
 const MAX_TEXT_PREVIEW: usize = 2_000_000;
 
+/// Per-category preview size caps in bytes, stored in `AppState`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PreviewLimits {
+    pub text: u64,
+    pub image: u64,
+    pub video: u64, // also used for audio
+    pub other: u64,
+}
+
+impl Default for PreviewLimits {
+    fn default() -> Self {
+        Self {
+            text: MAX_TEXT_PREVIEW as u64,
+            image: MAX_TEXT_PREVIEW as u64 * 4,
+            video: MAX_TEXT_PREVIEW as u64 * 4,
+            other: MAX_TEXT_PREVIEW as u64 * 4,
+        }
+    }
+}
+
+impl PreviewLimits {
+    /// Picks the cap that applies to a MIME type
+    pub fn for_mime(&self, mime: &str) -> u64 {
+        if mime.starts_with("image/") {
+            self.image
+        } else if mime.starts_with("video/") || mime.starts_with("audio/") {
+            self.video
+        } else if mime.starts_with("text/") {
+            self.text
+        } else {
+            self.other
+        }
+    }
+}
+
+/// Basic MIME guess for previewable media from a file extension
+pub(crate) fn mime_from_extension(ext: &str) -> &'static str {
+    match ext.to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        _ => "application/octet-stream",
+    }
+}
+
+pub(crate) fn current_preview_limits(state_mux: &StateSafe) -> PreviewLimits {
+    match state_mux.lock() {
+        Ok(state) => state.preview_limits.clone(),
+        Err(poison) => poison.into_inner().preview_limits.clone(),
+    }
+}
+
+lazy_static! {
+    /// Bundled syntax definitions; loading them is slow, so it happens once
+    static ref SYNTAX_SET: SyntaxSet = SyntaxSet::load_defaults_newlines();
+}
+
+/// A run of source text sharing one syntax scope, e.g. `keyword.control.rust`
+#[derive(Serialize, Clone, Debug)]
+pub struct CodeSpan {
+    pub style: String,
+    pub text: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CodePreview {
+    pub language: String,
+    pub lines: Vec<Vec<CodeSpan>>,
+}
+
+/// Default cap on the number of strings returned by `extract_strings`
+const MAX_EXTRACTED_STRINGS: usize = 10_000;
+
+/// A printable run found inside a file
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct ExtractedString {
+    pub offset: u64,
+    pub text: String,
+    pub encoding: String, // "ascii" or "utf16le"
+}
+
+fn is_printable(b: u8) -> bool {
+    b == b'\t' || (0x20..0x7f).contains(&b)
+}
+
 #[command]
-pub fn preview_text_file(path: String) -> Result<String, String> {
+pub fn set_preview_limits(state_mux: State<'_, StateSafe>, limits: PreviewLimits) -> Result<(), String> {
+    let caps = [limits.text, limits.image, limits.video, limits.other];
+    if caps.iter().any(|&c| c == 0) {
+        return Err("Preview limits must be non-zero".to_string());
+    }
+
+    // A preview is read fully into memory (and base64'd for binaries), so keep caps well below free RAM
+    let mut sys = System::new();
+    sys.refresh_memory();
+    let available = sys.available_memory();
+    if caps.iter().any(|&c| c > available / 2) {
+        return Err(format!(
+            "Preview limits must not exceed half of available memory ({} bytes)",
+            available / 2
+        ));
+    }
+
+    let mut state = state_mux.lock().map_err(|_| "Failed to acquire state lock".to_string())?;
+    state.preview_limits = limits;
+    Ok(())
+}
+
+#[command]
+pub fn get_preview_limits(state_mux: State<'_, StateSafe>) -> PreviewLimits {
+    current_preview_limits(&state_mux)
+}
+
+#[command]
+pub fn preview_text_file(state_mux: State<'_, StateSafe>, path: String) -> Result<String, String> {
     let p = PathBuf::from(path);
+    let text_limit = current_preview_limits(&state_mux).text as usize;
 
     let metadata = fs::metadata(&p).map_err(|e| format!("Failed to read metadata: {}", e))?;
-    if metadata.len() as usize > MAX_TEXT_PREVIEW {
+    if metadata.len() as usize > text_limit {
         return Err("File too large to preview".to_string());
     }
 
     // Handle docx / pptx (zip-based Office Open XML)
     if let Some(ext) = p.extension().and_then(|s| s.to_str()) {
         let ext = ext.to_lowercase();
-        if ext == "docx" || ext == "pptx" {
+        if TEXT_EXTRACTED_EXTENSIONS.contains(&ext.as_str()) {
             let bytes = fs::read(&p).map_err(|_| "Failed to read file".to_string())?;
             let reader = Cursor::new(bytes);
             let mut archive = ZipArchive::new(reader).map_err(|_| "Failed to read archive".to_string())?;
@@ -60,7 +191,7 @@ pub fn preview_text_file(path: String) -> Result<String, String> {
                 return Err("No text extracted from docx/pptx".to_string());
             }
             // trim and limit size
-            let out = if collected.len() > MAX_TEXT_PREVIEW { collected[..MAX_TEXT_PREVIEW].to_string() } else { collected };
+            let out = if collected.len() > text_limit { collected[..text_limit].to_string() } else { collected };
             return Ok(out);
         }
     }
@@ -69,37 +200,295 @@ pub fn preview_text_file(path: String) -> Result<String, String> {
 }
 
 #[command]
-pub fn preview_binary_file(path: String) -> Result<(String, String), String> {
+pub fn preview_binary_file(state_mux: State<'_, StateSafe>, path: String) -> Result<(String, String), String> {
     let p = PathBuf::from(path.clone());
 
     let metadata = fs::metadata(&p).map_err(|e| format!("Failed to read metadata: {}", e))?;
-    if metadata.len() as usize > MAX_TEXT_PREVIEW * 4 { // larger threshold for binaries (~2MB)
+
+    // attempt a basic mime guess from extension
+    let mime = mime_from_extension(p.extension().and_then(|s| s.to_str()).unwrap_or(""));
+
+    if metadata.len() > current_preview_limits(&state_mux).for_mime(mime) {
         return Err("File too large to preview".to_string());
     }
 
     let data = fs::read(&p).map_err(|_| "Failed to read file".to_string())?;
 
-    // attempt a basic mime guess from extension
-    let mime = match p.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase().as_str() {
-        "png" => "image/png",
-        "jpg" | "jpeg" => "image/jpeg",
-        "gif" => "image/gif",
-        "svg" => "image/svg+xml",
-        "webp" => "image/webp",
-        "pdf" => "application/pdf",
-        "mp4" => "video/mp4",
-        "webm" => "video/webm",
-        "mov" => "video/quicktime",
-        "mp3" => "audio/mpeg",
-        "wav" => "audio/wav",
-        "ogg" => "audio/ogg",
-        _ => "application/octet-stream",
-    };
-
     let encoded = general_purpose::STANDARD.encode(&data);
     Ok((encoded, mime.to_string()))
 }
 
+/// Bytes sniffed to decide whether an unknown file is text
+const TEXT_SNIFF_LEN: usize = 8 * 1024;
+
+/// Container formats `preview_text_file` extracts text from
+pub(crate) const TEXT_EXTRACTED_EXTENSIONS: &[&str] = &["docx", "pptx"];
+
+/// Result of `preview_auto`, tagged by the kind of preview the frontend should render
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum AutoPreview {
+    Text { content: String },
+    Image { data: String, mime: String },
+    Pdf { data: String, mime: String },
+    Audio { data: String, mime: String },
+    Video { data: String, mime: String },
+    Unsupported { reason: String, mime: String, size: u64 },
+}
+
+impl AutoPreview {
+    /// The `kind` tags of every preview `preview_auto` can render, as they serialize
+    pub(crate) fn kinds() -> Vec<String> {
+        let data = String::new;
+        let previews = [
+            AutoPreview::Text { content: data() },
+            AutoPreview::Image { data: data(), mime: data() },
+            AutoPreview::Pdf { data: data(), mime: data() },
+            AutoPreview::Audio { data: data(), mime: data() },
+            AutoPreview::Video { data: data(), mime: data() },
+        ];
+        previews
+            .iter()
+            .filter_map(|preview| {
+                let value = serde_json::to_value(preview).ok()?;
+                value.get("kind")?.as_str().map(String::from)
+            })
+            .collect()
+    }
+}
+
+/// Checks if the start of a file looks like text: valid UTF-8 (a cut-off final
+/// character is fine) and no NUL bytes
+fn looks_like_text(path: &PathBuf) -> bool {
+    use std::io::Read;
+
+    let mut head = Vec::with_capacity(TEXT_SNIFF_LEN);
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    if file.take(TEXT_SNIFF_LEN as u64).read_to_end(&mut head).is_err() || head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(&head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TextFileAnalysis {
+    pub line_ending: String, // "lf", "crlf", "mixed" or "none"
+    pub has_bom: bool,
+    pub encoding: String,    // "utf-8", "ascii", "utf-16le", "utf-16be" or "unknown"
+    pub trailing_whitespace_lines: usize,
+}
+
+/// Detects the encoding from the BOM or the bytes, and decodes what it can
+fn decode_text(bytes: &[u8]) -> (&'static str, bool, Option<String>) {
+    let utf16 = |data: &[u8], little_endian: bool| -> Option<String> {
+        let units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|c| if little_endian { u16::from_le_bytes([c[0], c[1]]) } else { u16::from_be_bytes([c[0], c[1]]) })
+            .collect();
+        String::from_utf16(&units).ok()
+    };
+
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return ("utf-8", true, std::str::from_utf8(rest).ok().map(String::from));
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return ("utf-16le", true, utf16(rest, true));
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return ("utf-16be", true, utf16(rest, false));
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) if text.is_ascii() => ("ascii", false, Some(text.to_string())),
+        Ok(text) => ("utf-8", false, Some(text.to_string())),
+        Err(_) => ("unknown", false, None),
+    }
+}
+
+/// Line endings, BOM, encoding and trailing whitespace of a text file, for spotting
+/// files that were edited on different operating systems
+#[command]
+pub fn analyze_text_file(state_mux: State<'_, StateSafe>, path: String) -> Result<TextFileAnalysis, String> {
+    let p = PathBuf::from(path);
+    let metadata = fs::metadata(&p).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    if metadata.len() > current_preview_limits(&state_mux).text {
+        return Err("File too large to analyze".to_string());
+    }
+
+    let bytes = fs::read(&p).map_err(|e| format!("Failed to read file: {}", e))?;
+    let (encoding, has_bom, text) = decode_text(&bytes);
+    let text = text.ok_or("File is not valid text in a supported encoding")?;
+    let (line_ending, trailing_whitespace_lines) = line_stats(&text);
+
+    Ok(TextFileAnalysis {
+        line_ending: line_ending.to_string(),
+        has_bom,
+        encoding: encoding.to_string(),
+        trailing_whitespace_lines,
+    })
+}
+
+/// The line ending style of `text` and how many of its lines end in spaces or tabs
+fn line_stats(text: &str) -> (&'static str, usize) {
+    let mut crlf = 0;
+    let mut lf = 0;
+    let mut trailing_whitespace_lines = 0;
+    for line in text.split_inclusive('\n') {
+        let content = match line.strip_suffix('\n') {
+            Some(without_lf) => match without_lf.strip_suffix('\r') {
+                Some(without_crlf) => {
+                    crlf += 1;
+                    without_crlf
+                }
+                None => {
+                    lf += 1;
+                    without_lf
+                }
+            },
+            None => line,
+        };
+        if content.ends_with([' ', '\t']) {
+            trailing_whitespace_lines += 1;
+        }
+    }
+
+    let line_ending = match (lf, crlf) {
+        (0, 0) => "none",
+        (_, 0) => "lf",
+        (0, _) => "crlf",
+        _ => "mixed",
+    };
+    (line_ending, trailing_whitespace_lines)
+}
+
+/// Bytes of a file read by `detect_language`; the start of a document is enough
+const LANGUAGE_SAMPLE_BYTES: u64 = 64 * 1024;
+
+/// Fewer letters than this are too little for a meaningful guess
+const MIN_LANGUAGE_LETTERS: usize = 20;
+
+/// Language guess for a text file. `language` is the ISO 639-3 code (e.g. "eng"); when
+/// it is `None`, `reason` says why: "binary", "too_short" or "undetermined".
+#[derive(Serialize, Clone, Debug)]
+pub struct LanguageDetection {
+    pub language: Option<String>,
+    pub name: Option<String>,
+    pub script: Option<String>,
+    pub confidence: f64,
+    pub reliable: bool,
+    pub encoding: String,
+    pub reason: Option<String>,
+}
+
+impl LanguageDetection {
+    fn unclassified(encoding: &str, reason: &str) -> Self {
+        Self {
+            language: None,
+            name: None,
+            script: None,
+            confidence: 0.0,
+            reliable: false,
+            encoding: encoding.to_string(),
+            reason: Some(reason.to_string()),
+        }
+    }
+}
+
+/// Guesses the language of a text file from its first LANGUAGE_SAMPLE_BYTES
+#[command]
+pub fn detect_language(path: String) -> Result<LanguageDetection, String> {
+    use std::io::Read;
+
+    let file = fs::File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut bytes = Vec::new();
+    file.take(LANGUAGE_SAMPLE_BYTES)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let truncated = bytes.len() as u64 == LANGUAGE_SAMPLE_BYTES;
+
+    // A sample cut mid-character only decodes once the partial character is dropped
+    let max_cut = if truncated { 3 } else { 0 };
+    let decoded = (0..=max_cut)
+        .map(|cut| decode_text(&bytes[..bytes.len().saturating_sub(cut)]))
+        .find(|(_, _, text)| text.is_some());
+    let Some((encoding, _, Some(text))) = decoded else {
+        return Ok(LanguageDetection::unclassified("unknown", "binary"));
+    };
+    if text.contains('\0') {
+        return Ok(LanguageDetection::unclassified(encoding, "binary"));
+    }
+    if text.chars().filter(|c| c.is_alphabetic()).count() < MIN_LANGUAGE_LETTERS {
+        return Ok(LanguageDetection::unclassified(encoding, "too_short"));
+    }
+
+    let Some(info) = whatlang::detect(&text) else {
+        return Ok(LanguageDetection::unclassified(encoding, "undetermined"));
+    };
+    Ok(LanguageDetection {
+        language: Some(info.lang().code().to_string()),
+        name: Some(info.lang().eng_name().to_string()),
+        script: Some(info.script().name().to_string()),
+        confidence: info.confidence(),
+        reliable: info.is_reliable(),
+        encoding: encoding.to_string(),
+        reason: None,
+    })
+}
+
+/// Single preview entry point: sniffs the file and routes it to the text or binary
+/// preview, or explains why it can't be previewed.
+#[command]
+pub fn preview_auto(state_mux: State<'_, StateSafe>, path: String) -> Result<AutoPreview, String> {
+    let p = PathBuf::from(&path);
+    let metadata = fs::metadata(&p).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let size = metadata.len();
+    let ext = p.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+
+    let sniffed = infer::get_from_path(&p).ok().flatten().map(|k| k.mime_type());
+    let mime = sniffed.unwrap_or_else(|| mime_from_extension(&ext)).to_string();
+    let unsupported = |reason: &str| AutoPreview::Unsupported { reason: reason.to_string(), mime: mime.clone(), size };
+
+    if metadata.is_dir() {
+        return Ok(unsupported("is a directory"));
+    }
+
+    let limits = current_preview_limits(&state_mux);
+    let is_office_text = TEXT_EXTRACTED_EXTENSIONS.contains(&ext.as_str());
+    let is_media = ["image/", "audio/", "video/"].iter().any(|m| mime.starts_with(m)) || mime == "application/pdf";
+
+    if is_office_text || (!is_media && looks_like_text(&p)) {
+        if size > limits.text {
+            return Ok(unsupported("too large to preview"));
+        }
+        return Ok(match preview_text_file(state_mux, path) {
+            Ok(content) => AutoPreview::Text { content },
+            Err(e) => unsupported(&e),
+        });
+    }
+
+    if !is_media {
+        return Ok(unsupported("binary file"));
+    }
+    if size > limits.for_mime(&mime) {
+        return Ok(unsupported("too large to preview"));
+    }
+
+    let data = general_purpose::STANDARD.encode(fs::read(&p).map_err(|_| "Failed to read file".to_string())?);
+    Ok(if mime.starts_with("image/") {
+        AutoPreview::Image { data, mime }
+    } else if mime.starts_with("audio/") {
+        AutoPreview::Audio { data, mime }
+    } else if mime.starts_with("video/") {
+        AutoPreview::Video { data, mime }
+    } else {
+        AutoPreview::Pdf { data, mime }
+    })
+}
+
 #[command]
 pub fn metadata_for_path(path: String) -> Result<serde_json::Value, String> {
     use std::fs::metadata;
@@ -133,3 +522,259 @@ pub fn metadata_for_path(path: String) -> Result<serde_json::Value, String> {
 
     Ok(out)
 }
+
+/// Extracts runs of printable ASCII (and optionally UTF-16LE) text from any file,
+/// like the `strings` utility. The file is read in chunks, never loaded whole.
+#[command]
+pub fn extract_strings(
+    path: String,
+    min_length: usize,
+    include_utf16: Option<bool>,
+    max_count: Option<usize>,
+) -> Result<Vec<ExtractedString>, String> {
+    use std::io::Read;
+
+    let min_length = min_length.max(1);
+    let include_utf16 = include_utf16.unwrap_or(false);
+    let max_count = max_count.unwrap_or(MAX_EXTRACTED_STRINGS);
+
+    let mut file = fs::File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut buffer = [0u8; 64 * 1024];
+    let mut results = Vec::new();
+
+    let mut ascii_run: Vec<u8> = Vec::new();
+    let mut ascii_start: u64 = 0;
+    let mut utf16_run: Vec<u8> = Vec::new();
+    let mut utf16_start: u64 = 0;
+    let mut pending_low: Option<u8> = None;
+    let mut offset: u64 = 0;
+
+    fn flush(run: &mut Vec<u8>, start: u64, min_length: usize, encoding: &str, out: &mut Vec<ExtractedString>) {
+        if run.len() >= min_length {
+            out.push(ExtractedString {
+                offset: start,
+                text: String::from_utf8_lossy(run).to_string(),
+                encoding: encoding.to_string(),
+            });
+        }
+        run.clear();
+    }
+
+    'read: loop {
+        let n = file.read(&mut buffer).map_err(|e| format!("Failed to read file: {}", e))?;
+        if n == 0 {
+            break;
+        }
+
+        for &b in &buffer[..n] {
+            if is_printable(b) {
+                if ascii_run.is_empty() {
+                    ascii_start = offset;
+                }
+                ascii_run.push(b);
+            } else {
+                flush(&mut ascii_run, ascii_start, min_length, "ascii", &mut results);
+            }
+
+            // UTF-16LE: printable low byte followed by a zero high byte, on even offsets
+            if include_utf16 {
+                if offset % 2 == 0 {
+                    pending_low = Some(b);
+                } else if let Some(low) = pending_low.take() {
+                    if b == 0 && is_printable(low) {
+                        if utf16_run.is_empty() {
+                            utf16_start = offset - 1;
+                        }
+                        utf16_run.push(low);
+                    } else {
+                        flush(&mut utf16_run, utf16_start, min_length, "utf16le", &mut results);
+                    }
+                }
+            }
+
+            offset += 1;
+            if results.len() >= max_count {
+                break 'read;
+            }
+        }
+    }
+
+    flush(&mut ascii_run, ascii_start, min_length, "ascii", &mut results);
+    flush(&mut utf16_run, utf16_start, min_length, "utf16le", &mut results);
+    results.truncate(max_count);
+
+    Ok(results)
+}
+
+/// Tokenizes a source file into scope-classified spans per line. The language comes from
+/// `language_hint` (a name or extension) or else from the file extension and first line.
+#[command(async)]
+pub fn preview_code(
+    state_mux: State<'_, StateSafe>,
+    path: String,
+    language_hint: Option<String>,
+) -> Result<CodePreview, String> {
+    let p = PathBuf::from(&path);
+
+    let metadata = fs::metadata(&p).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    if metadata.len() > current_preview_limits(&state_mux).text {
+        return Err("File too large to preview".to_string());
+    }
+    let source = fs::read_to_string(&p).map_err(|_| "Failed to read file as text".to_string())?;
+    tokenize_code(&p, &source, language_hint.as_deref())
+}
+
+/// The tokenizer behind `preview_code`; `p` is only used to pick the language
+fn tokenize_code(p: &Path, source: &str, language_hint: Option<&str>) -> Result<CodePreview, String> {
+    let ss = &*SYNTAX_SET;
+    let syntax = language_hint
+        .and_then(|hint| ss.find_syntax_by_token(hint))
+        .or_else(|| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .and_then(|ext| ss.find_syntax_by_extension(ext))
+        })
+        .or_else(|| ss.find_syntax_by_first_line(source))
+        .unwrap_or_else(|| ss.find_syntax_plain_text());
+
+    let mut parse_state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut lines = Vec::new();
+
+    for line in LinesWithEndings::from(source) {
+        let ops = parse_state
+            .parse_line(line, ss)
+            .map_err(|e| format!("Failed to tokenize: {}", e))?;
+
+        let mut spans: Vec<CodeSpan> = Vec::new();
+        for (text, op) in ScopeRegionIterator::new(&ops, line) {
+            stack
+                .apply(op)
+                .map_err(|e| format!("Failed to tokenize: {}", e))?;
+            let text = text.trim_end_matches(['\n', '\r']);
+            if text.is_empty() {
+                continue;
+            }
+
+            let style = stack
+                .as_slice()
+                .last()
+                .map(|scope| scope.build_string())
+                .unwrap_or_default();
+            match spans.last_mut() {
+                Some(last) if last.style == style => last.text.push_str(text),
+                _ => spans.push(CodeSpan { style, text: text.to_string() }),
+            }
+        }
+        lines.push(spans);
+    }
+
+    Ok(CodePreview {
+        language: syntax.name.clone(),
+        lines,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_ascii_and_utf16_runs_with_offsets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blob.bin");
+        let mut data = vec![0u8, 1, 2];
+        data.extend_from_slice(b"hello world");
+        data.extend_from_slice(&[0, 0xff]);
+        data.extend("wide".encode_utf16().flat_map(u16::to_le_bytes));
+        data.extend_from_slice(&[0xff, 0xff]);
+        fs::write(&path, &data).unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let found = extract_strings(path.clone(), 4, Some(true), None).unwrap();
+        let found: Vec<_> = found.iter().map(|s| (s.offset, s.text.as_str(), s.encoding.as_str())).collect();
+        assert_eq!(found, [(3, "hello world", "ascii"), (16, "wide", "utf16le")]);
+
+        assert_eq!(extract_strings(path, 4, None, Some(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn limits_are_picked_by_mime_category() {
+        let limits = PreviewLimits { text: 1, image: 2, video: 3, other: 4 };
+
+        assert_eq!(limits.for_mime("text/plain"), 1);
+        assert_eq!(limits.for_mime(mime_from_extension("PNG")), 2);
+        assert_eq!(limits.for_mime(mime_from_extension("mp3")), 3);
+        assert_eq!(limits.for_mime(mime_from_extension("mp4")), 3);
+        assert_eq!(limits.for_mime(mime_from_extension("pdf")), 4);
+    }
+
+    #[test]
+    fn code_is_split_into_scoped_spans_per_line() {
+        let preview = tokenize_code(Path::new("main.rs"), "fn main() {\n    return;\n}\n", None).unwrap();
+
+        assert_eq!(preview.language, "Rust");
+        assert_eq!(preview.lines.len(), 3);
+        let first_line: String = preview.lines[0].iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(first_line, "fn main() {");
+        assert!(preview.lines[1].iter().any(|s| s.text == "return" && s.style.starts_with("keyword")));
+
+        let hinted = tokenize_code(Path::new("script"), "def f(): pass\n", Some("py")).unwrap();
+        assert_eq!(hinted.language, "Python");
+    }
+
+    #[test]
+    fn text_sniffing_rejects_nul_bytes_and_invalid_utf8() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, data: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, data).unwrap();
+            path
+        };
+
+        assert!(looks_like_text(&write("plain.txt", "héllo".as_bytes())));
+        assert!(!looks_like_text(&write("nul.bin", b"abc\0def")));
+        assert!(!looks_like_text(&write("latin1.txt", b"caf\xe9 au lait")));
+        // A multi-byte character cut at the end of the sniffed range still counts as text
+        let mut cut = vec![b'a'; TEXT_SNIFF_LEN - 1];
+        cut.extend_from_slice("é".as_bytes());
+        assert!(looks_like_text(&write("cut.txt", &cut)));
+    }
+
+    #[test]
+    fn encoding_is_detected_from_the_bom_or_bytes() {
+        assert_eq!(decode_text(b"plain"), ("ascii", false, Some("plain".to_string())));
+        assert_eq!(decode_text("\u{feff}héllo".as_bytes()), ("utf-8", true, Some("héllo".to_string())));
+        assert_eq!(decode_text(&[0xFF, 0xFE, b'h', 0, b'i', 0]), ("utf-16le", true, Some("hi".to_string())));
+        assert_eq!(decode_text(&[0xFE, 0xFF, 0, b'h', 0, b'i']), ("utf-16be", true, Some("hi".to_string())));
+        assert_eq!(decode_text(b"caf\xe9"), ("unknown", false, None));
+    }
+
+    #[test]
+    fn line_endings_and_trailing_whitespace_are_counted() {
+        assert_eq!(line_stats("a\nb\n"), ("lf", 0));
+        assert_eq!(line_stats("a \r\nb\r\n"), ("crlf", 1));
+        assert_eq!(line_stats("a\r\nb\t\nc "), ("mixed", 2));
+        assert_eq!(line_stats("single line"), ("none", 0));
+    }
+
+    #[test]
+    fn language_is_detected_or_the_reason_given() {
+        let dir = tempfile::tempdir().unwrap();
+        let detect = |name: &str, data: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, data).unwrap();
+            detect_language(path.to_string_lossy().to_string()).unwrap()
+        };
+
+        let english = detect(
+            "english.txt",
+            b"The quick brown fox jumps over the lazy dog while the children watch from the garden.",
+        );
+        assert_eq!(english.language.as_deref(), Some("eng"));
+        assert_eq!(english.reason, None);
+
+        assert_eq!(detect("short.txt", b"hi there").reason.as_deref(), Some("too_short"));
+        assert_eq!(detect("blob.bin", b"\xff\xfe\x00\xd8\x00\xd8").reason.as_deref(), Some("binary"));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::errors::Error;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Changes kept per watched root; the oldest are dropped first
+const MAX_RECENT_CHANGES: usize = 500;
+
+/// Repeated changes of the same kind to one path within this window become one entry
+const COALESCE_WINDOW_MS: i64 = 2000;
+
+/// Entries returned when the caller gives no limit
+const DEFAULT_RECENT_LIMIT: usize = 100;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RecentChange {
+    pub path: String,
+    pub kind: String,
+    pub timestamp: String,
+    #[serde(skip)]
+    at: DateTime<Utc>,
+}
+
+type ChangeFeed = Arc<Mutex<VecDeque<RecentChange>>>;
+
+lazy_static! {
+    static ref ACTIVITY_FEEDS: Mutex<HashMap<String, (RecommendedWatcher, ChangeFeed)>> =
+        Mutex::new(HashMap::new());
+}
+
+fn change_kind(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("created"),
+        EventKind::Modify(notify::event::ModifyKind::Name(_)) => Some("renamed"),
+        EventKind::Modify(_) => Some("modified"),
+        EventKind::Remove(_) => Some("removed"),
+        _ => None,
+    }
+}
+
+/// Adds a change, folding it into a recent entry for the same path and kind
+fn record_change(feed: &mut VecDeque<RecentChange>, change: RecentChange) {
+    let coalesce_from = change.at - chrono::Duration::milliseconds(COALESCE_WINDOW_MS);
+    if let Some(pos) = feed
+        .iter()
+        .rposition(|c| c.path == change.path && c.kind == change.kind && c.at >= coalesce_from)
+    {
+        feed.remove(pos);
+    }
+
+    feed.push_back(change);
+    while feed.len() > MAX_RECENT_CHANGES {
+        feed.pop_front();
+    }
+}
+
+/// Starts recording changes under `root` for `get_recent_changes`
+#[tauri::command]
+pub fn watch_directory(root: String) -> Result<(), Error> {
+    let feed: ChangeFeed = Arc::new(Mutex::new(VecDeque::new()));
+    let event_feed = feed.clone();
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        let Some(kind) = change_kind(&event.kind) else {
+            return;
+        };
+        let Ok(mut feed) = event_feed.lock() else {
+            return;
+        };
+        let at = Utc::now();
+        for path in &event.paths {
+            record_change(
+                &mut feed,
+                RecentChange {
+                    path: path.to_string_lossy().to_string(),
+                    kind: kind.to_string(),
+                    timestamp: at.to_rfc3339(),
+                    at,
+                },
+            );
+        }
+    })
+    .map_err(|e| Error::Custom(format!("Failed to create watcher: {}", e)))?;
+
+    watcher
+        .watch(&PathBuf::from(&root), RecursiveMode::Recursive)
+        .map_err(|e| Error::Custom(format!("Failed to watch directory: {}", e)))?;
+
+    let mut feeds = ACTIVITY_FEEDS
+        .lock()
+        .map_err(|_| Error::Custom("Failed to acquire activity lock".to_string()))?;
+    feeds.insert(root, (watcher, feed));
+
+    Ok(())
+}
+
+/// Stops recording changes under `root` and drops its feed
+#[tauri::command]
+pub fn unwatch_directory(root: String) -> Result<(), Error> {
+    let mut feeds = ACTIVITY_FEEDS
+        .lock()
+        .map_err(|_| Error::Custom("Failed to acquire activity lock".to_string()))?;
+    feeds.remove(&root);
+    Ok(())
+}
+
+/// Most recent changes under a watched `root`, newest first
+#[tauri::command]
+pub fn get_recent_changes(root: String, limit: Option<usize>) -> Result<Vec<RecentChange>, Error> {
+    let feeds = ACTIVITY_FEEDS
+        .lock()
+        .map_err(|_| Error::Custom("Failed to acquire activity lock".to_string()))?;
+    let (_, feed) = feeds
+        .get(&root)
+        .ok_or_else(|| Error::Custom("Directory is not being watched".to_string()))?;
+    let feed = feed
+        .lock()
+        .map_err(|_| Error::Custom("Failed to acquire activity lock".to_string()))?;
+
+    Ok(feed
+        .iter()
+        .rev()
+        .take(limit.unwrap_or(DEFAULT_RECENT_LIMIT))
+        .cloned()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(path: &str, kind: &str, at: DateTime<Utc>) -> RecentChange {
+        RecentChange { path: path.to_string(), kind: kind.to_string(), timestamp: at.to_rfc3339(), at }
+    }
+
+    #[test]
+    fn repeated_changes_within_the_window_are_coalesced() {
+        let start = Utc::now();
+        let mut feed = VecDeque::new();
+        record_change(&mut feed, change("/a", "modified", start));
+        record_change(&mut feed, change("/b", "modified", start));
+        record_change(&mut feed, change("/a", "modified", start + chrono::Duration::milliseconds(500)));
+        record_change(&mut feed, change("/a", "removed", start + chrono::Duration::milliseconds(600)));
+
+        let entries: Vec<_> = feed.iter().map(|c| (c.path.as_str(), c.kind.as_str())).collect();
+        assert_eq!(entries, [("/b", "modified"), ("/a", "modified"), ("/a", "removed")]);
+
+        let later = start + chrono::Duration::milliseconds(COALESCE_WINDOW_MS + 1000);
+        record_change(&mut feed, change("/b", "modified", later));
+        assert_eq!(feed.len(), 4);
+    }
+
+    #[test]
+    fn feed_keeps_only_the_newest_changes() {
+        let start = Utc::now();
+        let mut feed = VecDeque::new();
+        for i in 0..MAX_RECENT_CHANGES + 10 {
+            record_change(&mut feed, change(&format!("/file{}", i), "created", start));
+        }
+
+        assert_eq!(feed.len(), MAX_RECENT_CHANGES);
+        assert_eq!(feed.front().unwrap().path, "/file10");
+    }
+}
@@ -1,7 +1,9 @@
 use crate::errors::Error;
 use crate::filesystem::cache::FsEventHandler;
-use crate::filesystem::fs_utils::get_mount_point;
+use crate::filesystem::file_lock::describe_failure;
+use crate::filesystem::fs_utils::{check_free_space, get_mount_point, resolve_path, resolve_path_in_state};
 use crate::filesystem::volume::{DirectoryChild, FileMeta};
+use crate::filesystem::walk::WalkOptions;
 use crate::StateSafe;
 
 use notify::event::CreateKind;
@@ -9,14 +11,17 @@ use std::fs;
 use std::fs::read_dir;
 use std::ops::Deref;
 use std::path::Path;
-use tauri::State;
+use serde::Serialize;
+use tauri::{Emitter, State, Window};
 
 use chrono::{DateTime, Local};
 use std::time::SystemTime;
 
-/// Opens a file at the given path. Returns a string if there was an error.
-#[tauri::command]
-pub async fn open_file(path: String) -> Result<(), Error> {
+/// Files launched at the same time by `open_files`
+const MAX_CONCURRENT_OPENS: usize = 8;
+
+/// Launches `path` with its default application
+fn launch(path: &str) -> Result<(), Error> {
     let output_res = open::commands(path)[0].output();
     let output = match output_res {
         Ok(output) => output,
@@ -35,6 +40,57 @@ pub async fn open_file(path: String) -> Result<(), Error> {
     Err(Error::Custom(err_msg))
 }
 
+/// Opens a file at the given path. Returns a string if there was an error.
+#[tauri::command]
+pub async fn open_file(path: String) -> Result<(), Error> {
+    launch(&path)
+}
+
+#[derive(Serialize, Clone)]
+pub struct OpenResult {
+    pub path: String,
+    pub error: Option<String>,
+}
+
+/// Opens every path with its default application, a few at a time. Failures are reported
+/// per path instead of stopping the rest; repeated paths are launched once unless `dedupe` is false.
+#[tauri::command]
+pub async fn open_files(paths: Vec<String>, dedupe: Option<bool>) -> Result<Vec<OpenResult>, Error> {
+    let mut paths = paths;
+    if dedupe.unwrap_or(true) {
+        let mut seen = std::collections::HashSet::new();
+        paths.retain(|p| seen.insert(p.clone()));
+    }
+
+    let mut results = Vec::with_capacity(paths.len());
+    for batch in paths.chunks(MAX_CONCURRENT_OPENS) {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .iter()
+                .map(|path| {
+                    scope.spawn(move || {
+                        if !Path::new(path).exists() {
+                            return Err(Error::Custom("File not found".to_string()));
+                        }
+                        launch(path)
+                    })
+                })
+                .collect();
+
+            for (path, handle) in batch.iter().zip(handles) {
+                let error = match handle.join() {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(e.to_string()),
+                    Err(_) => Some("Launcher panicked".to_string()),
+                };
+                results.push(OpenResult { path: path.clone(), error });
+            }
+        });
+    }
+
+    Ok(results)
+}
+
 fn system_time_to_string(st: Option<SystemTime>) -> Option<String> {
     st.map(|time| {
         let datetime: DateTime<Local> = time.into();
@@ -42,18 +98,40 @@ fn system_time_to_string(st: Option<SystemTime>) -> Option<String> {
     })
 }
 
+#[derive(Serialize, Clone)]
+pub struct DirectoryListing {
+    pub children: Vec<DirectoryChild>,
+    /// Entries that exist but whose metadata couldn't be read, e.g. permission denied
+    pub inaccessible: Vec<String>,
+}
+
 /// Searches and returns the files in a given directory. This is not recursive.
 #[tauri::command]
-pub async fn open_directory(path: String) -> Result<Vec<DirectoryChild>, ()> {
-    let Ok(directory) = read_dir(path) else {
-        return Ok(Vec::new());
+pub async fn open_directory(path: String, walk_options: Option<WalkOptions>) -> Result<DirectoryListing, ()> {
+    let options = walk_options.unwrap_or_default();
+    let mut listing = DirectoryListing { children: Vec::new(), inaccessible: Vec::new() };
+    let Ok(directory) = read_dir(&path) else {
+        listing.inaccessible.push(path);
+        return Ok(listing);
     };
 
-    Ok(directory
-        .filter_map(|entry| entry.ok())
-        .map(|entry| {
-            let metadata = entry.metadata().unwrap();
-            let file_meta = FileMeta {
+    for entry in directory {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                listing.inaccessible.push(format!("{}: {}", path, err));
+                continue;
+            }
+        };
+        if !options.allows_path(&entry.path()) {
+            continue;
+        }
+
+        let Ok(metadata) = entry.metadata() else {
+            listing.inaccessible.push(entry.path().to_string_lossy().to_string());
+            continue;
+        };
+        let file_meta = FileMeta {
     name: entry.file_name().to_string_lossy().to_string(),
     path: entry.path().to_string_lossy().to_string(),
     is_dir: metadata.is_dir(),
@@ -62,17 +140,19 @@ pub async fn open_directory(path: String) -> Result<Vec<DirectoryChild>, ()> {
     modified: system_time_to_string(metadata.modified().ok()),
 };
 
-            if file_meta.is_dir {
-                DirectoryChild::Directory(file_meta)
-            } else {
-                DirectoryChild::File(file_meta)
-            }
-        })
-        .collect())
+        listing.children.push(if file_meta.is_dir {
+            DirectoryChild::Directory(file_meta)
+        } else {
+            DirectoryChild::File(file_meta)
+        });
+    }
+
+    Ok(listing)
 }
 
 #[tauri::command]
 pub async fn create_file(state_mux: State<'_, StateSafe>, path: String) -> Result<(), Error> {
+    let path = resolve_path_in_state(&path, &state_mux)?;
     let mount_point_str = get_mount_point(path.clone()).unwrap_or_default();
 
     let fs_event_manager = FsEventHandler::new(state_mux.deref().clone(), mount_point_str.into());
@@ -87,6 +167,7 @@ pub async fn create_file(state_mux: State<'_, StateSafe>, path: String) -> Resul
 
 #[tauri::command]
 pub async fn create_directory(state_mux: State<'_, StateSafe>, path: String) -> Result<(), Error> {
+    let path = resolve_path_in_state(&path, &state_mux)?;
     let mount_point_str = get_mount_point(path.clone()).unwrap_or_default();
 
     let fs_event_manager = FsEventHandler::new(state_mux.deref().clone(), mount_point_str.into());
@@ -105,6 +186,8 @@ pub async fn rename_file(
     old_path: String,
     new_path: String,
 ) -> Result<(), Error> {
+    let old_path = resolve_path_in_state(&old_path, &state_mux)?;
+    let new_path = resolve_path_in_state(&new_path, &state_mux)?;
     let mount_point_str = get_mount_point(old_path.clone()).unwrap_or_default();
 
     let mut fs_event_manager =
@@ -112,15 +195,194 @@ pub async fn rename_file(
     fs_event_manager.handle_rename_from(Path::new(&old_path));
     fs_event_manager.handle_rename_to(Path::new(&new_path));
 
-    let res = fs::rename(old_path, new_path);
+    let res = if is_case_only_rename(&old_path, &new_path) {
+        rename_via_temp(&old_path, &new_path)
+    } else {
+        fs::rename(&old_path, new_path)
+    };
     match res {
         Ok(_) => Ok(()),
-        Err(err) => Err(Error::Custom(err.to_string())),
+        Err(err) => Err(Error::Custom(describe_failure(Path::new(&old_path), err))),
+    }
+}
+
+/// Checks if the rename only changes letter case and the filesystem treats both names as the same file.
+/// Case-sensitive filesystems return false so the direct rename is used.
+fn is_case_only_rename(old_path: &str, new_path: &str) -> bool {
+    if old_path == new_path || old_path.to_lowercase() != new_path.to_lowercase() {
+        return false;
+    }
+
+    let (Ok(old_meta), Ok(new_meta)) = (fs::metadata(old_path), fs::metadata(new_path)) else {
+        return false;
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        old_meta.dev() == new_meta.dev() && old_meta.ino() == new_meta.ino()
+    }
+    #[cfg(not(unix))]
+    {
+        old_meta.is_dir() == new_meta.is_dir()
+    }
+}
+
+/// Renames through a temporary sibling name, so case-insensitive filesystems apply the new case.
+fn rename_via_temp(old_path: &str, new_path: &str) -> std::io::Result<()> {
+    let temp_path = format!("{}.{}.renaming", new_path, uuid::Uuid::new_v4());
+    fs::rename(old_path, &temp_path)?;
+    if let Err(err) = fs::rename(&temp_path, new_path) {
+        // Put the original name back rather than leaving the temp name behind
+        let _ = fs::rename(&temp_path, old_path);
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Clone)]
+pub struct PlannedRename {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct BulkRenameResult {
+    pub planned: Vec<PlannedRename>,
+    pub applied: Vec<PlannedRename>,
+    /// New names that clash with each other or with files already in the directory
+    pub collisions: Vec<String>,
+    pub failed: Vec<EmptyFailure>,
+}
+
+/// Expands `{name}`, `{ext}`, `{date}`, `{index}` and the zero-padded `{index:N}` for one file
+fn expand_rename_pattern(pattern: &str, path: &Path, index: usize) -> Result<String, Error> {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| Error::Custom(format!("Unclosed token in pattern: {}", pattern)))?
+            + open;
+        let token = &rest[open + 1..close];
+        match token.split_once(':') {
+            None if token == "name" => out.push_str(&stem),
+            None if token == "ext" => out.push_str(&ext),
+            None if token == "index" => out.push_str(&index.to_string()),
+            None if token == "date" => {
+                let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+                let date: Option<DateTime<Local>> = modified.map(Into::into);
+                out.push_str(&date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default());
+            }
+            Some(("index", width)) => {
+                let width: usize = width
+                    .parse()
+                    .map_err(|_| Error::Custom(format!("Invalid counter width: {}", width)))?;
+                out.push_str(&format!("{:0width$}", index, width = width));
+            }
+            _ => return Err(Error::Custom(format!("Unknown token {{{}}}", token))),
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+
+    if out.is_empty() || out == "." || out == ".." || out.contains('/') || out.contains('\\') {
+        return Err(Error::Custom(format!("Pattern gives an invalid name for {}: {:?}", path.display(), out)));
+    }
+    Ok(out)
+}
+
+/// Renames `paths` in order using `pattern` (see `expand_rename_pattern`), counting from
+/// `start_index` (default 1). The whole plan is checked for collisions first; if there are
+/// any, or `dry_run` is set, nothing is renamed and only the plan is returned.
+#[tauri::command]
+pub async fn bulk_rename(
+    state_mux: State<'_, StateSafe>,
+    paths: Vec<String>,
+    pattern: String,
+    start_index: Option<usize>,
+    dry_run: Option<bool>,
+) -> Result<BulkRenameResult, Error> {
+    let start_index = start_index.unwrap_or(1);
+    let mut planned = Vec::with_capacity(paths.len());
+    for (i, path) in paths.iter().enumerate() {
+        let from = resolve_path_in_state(path, &state_mux)?;
+        let name = expand_rename_pattern(&pattern, Path::new(&from), start_index + i)?;
+        let to = Path::new(&from).with_file_name(name).to_string_lossy().to_string();
+        planned.push(PlannedRename { from, to });
+    }
+
+    let collisions = rename_collisions(&planned);
+    let mut result = BulkRenameResult { planned, applied: Vec::new(), collisions, failed: Vec::new() };
+    if dry_run.unwrap_or(false) || !result.collisions.is_empty() {
+        return Ok(result);
+    }
+
+    let (applied, failed) = apply_renames(&result.planned);
+    for rename in &applied {
+        let mount_point_str = get_mount_point(rename.from.clone()).unwrap_or_default();
+        let mut fs_event_manager = FsEventHandler::new(state_mux.deref().clone(), mount_point_str.into());
+        fs_event_manager.handle_rename_from(Path::new(&rename.from));
+        fs_event_manager.handle_rename_to(Path::new(&rename.to));
+    }
+    result.applied = applied;
+    result.failed = failed;
+
+    Ok(result)
+}
+
+/// Targets of `planned` that clash with each other (ignoring case) or with an existing
+/// file that isn't itself being renamed
+fn rename_collisions(planned: &[PlannedRename]) -> Vec<String> {
+    let sources: std::collections::HashSet<&str> = planned.iter().map(|r| r.from.as_str()).collect();
+    let mut targets = std::collections::HashSet::new();
+    let mut collisions = Vec::new();
+    for rename in planned {
+        let clashes_in_plan = !targets.insert(rename.to.to_lowercase());
+        let clashes_on_disk = !sources.contains(rename.to.as_str())
+            && fs::symlink_metadata(&rename.to).is_ok()
+            && !is_case_only_rename(&rename.from, &rename.to);
+        if clashes_in_plan || clashes_on_disk {
+            collisions.push(rename.to.clone());
+        }
+    }
+    collisions
+}
+
+/// Carries out a collision-free plan, returning the renames done and the ones that failed.
+/// Everything moves to a temporary name first so swaps and chains (a -> b, b -> c) work.
+fn apply_renames(planned: &[PlannedRename]) -> (Vec<PlannedRename>, Vec<EmptyFailure>) {
+    let mut applied = Vec::new();
+    let mut failed = Vec::new();
+
+    let mut staged = Vec::new();
+    for rename in planned.iter().filter(|r| r.from != r.to) {
+        let temp = format!("{}.{}.renaming", rename.from, uuid::Uuid::new_v4());
+        match fs::rename(&rename.from, &temp) {
+            Ok(()) => staged.push((rename, temp)),
+            Err(err) => failed.push(EmptyFailure { path: rename.from.clone(), error: err.to_string() }),
+        }
+    }
+
+    for (rename, temp) in staged {
+        if let Err(err) = fs::rename(&temp, &rename.to) {
+            let _ = fs::rename(&temp, &rename.from);
+            failed.push(EmptyFailure { path: rename.from.clone(), error: err.to_string() });
+            continue;
+        }
+        applied.push(rename.clone());
     }
+
+    (applied, failed)
 }
 
 #[tauri::command]
 pub async fn delete_file(state_mux: State<'_, StateSafe>, path: String) -> Result<(), Error> {
+    let path = resolve_path_in_state(&path, &state_mux)?;
     let mount_point_str = get_mount_point(path.clone()).unwrap_or_default();
 
     let fs_event_manager = FsEventHandler::new(state_mux.deref().clone(), mount_point_str.into());
@@ -130,7 +392,159 @@ pub async fn delete_file(state_mux: State<'_, StateSafe>, path: String) -> Resul
     let res = trash::delete(&path);
     match res {
         Ok(_) => Ok(()),
-        Err(err) => Err(Error::Custom(format!("Failed to move to Recycle Bin: {}", err))),
+        Err(err) => Err(Error::Custom(format!(
+            "Failed to move to Recycle Bin: {}",
+            describe_failure(Path::new(&path), err)
+        ))),
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct EmptyProgress {
+    pub done: usize,
+    pub total: usize,
+    pub path: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct EmptyFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct EmptyDirectoryResult {
+    pub removed: usize,
+    pub failed: Vec<EmptyFailure>,
+}
+
+/// Checks if `path` is a filesystem root or the top of a mounted volume
+fn is_mount_point(path: &Path) -> bool {
+    let Some(parent) = path.parent() else {
+        return true;
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(path), fs::metadata(parent)) {
+            (Ok(meta), Ok(parent_meta)) => meta.dev() != parent_meta.dev(),
+            _ => true,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = parent;
+        false
+    }
+}
+
+/// Refuses to empty anything but a directory below a mount point, named by `confirm_token`
+fn check_can_empty(dir: &Path, confirm_token: &str) -> Result<(), Error> {
+    if !dir.is_dir() {
+        return Err(Error::Custom("Path is not a directory".to_string()));
+    }
+    if is_mount_point(dir) {
+        return Err(Error::Custom("Refusing to empty a root or mount point".to_string()));
+    }
+    let name = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if confirm_token != name {
+        return Err(Error::Custom("Confirmation does not match the directory name".to_string()));
+    }
+    Ok(())
+}
+
+/// Deletes one child of an emptied directory. A symlink is removed itself, never
+/// what it points to.
+fn remove_child(child: &Path, to_trash: bool) -> Result<(), String> {
+    if to_trash {
+        trash::delete(child).map_err(|e| e.to_string())
+    } else if child.is_dir() && !child.is_symlink() {
+        fs::remove_dir_all(child).map_err(|e| e.to_string())
+    } else {
+        fs::remove_file(child).map_err(|e| e.to_string())
+    }
+}
+
+/// Removes every child of `path`, to the trash unless `to_trash` is false.
+/// `confirm_token` must equal the directory's own name. Failures are collected
+/// and reported instead of stopping the operation.
+#[tauri::command]
+pub async fn empty_directory(
+    window: Window,
+    state_mux: State<'_, StateSafe>,
+    path: String,
+    to_trash: Option<bool>,
+    confirm_token: String,
+) -> Result<EmptyDirectoryResult, Error> {
+    let path = resolve_path_in_state(&path, &state_mux)?;
+    let dir = Path::new(&path);
+    check_can_empty(dir, &confirm_token)?;
+
+    let children: Vec<_> = read_dir(dir)?.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    let total = children.len();
+    let to_trash = to_trash.unwrap_or(true);
+
+    let mount_point_str = get_mount_point(path.clone()).unwrap_or_default();
+    let fs_event_manager = FsEventHandler::new(state_mux.deref().clone(), mount_point_str.into());
+
+    let mut result = EmptyDirectoryResult { removed: 0, failed: Vec::new() };
+    for (done, child) in children.iter().enumerate() {
+        let removed = remove_child(child, to_trash);
+        let child_str = child.to_string_lossy().to_string();
+        match removed {
+            Ok(()) => {
+                fs_event_manager.handle_delete(child);
+                result.removed += 1;
+            }
+            Err(error) => result.failed.push(EmptyFailure { path: child_str.clone(), error }),
+        }
+
+        let _ = window.emit("empty_progress", EmptyProgress { done: done + 1, total, path: child_str });
+    }
+
+    Ok(result)
+}
+
+/// Canonicalizes a path and checks it against the allowed roots, returning the resolved path
+#[tauri::command]
+pub async fn validate_path(state_mux: State<'_, StateSafe>, path: String) -> Result<String, Error> {
+    resolve_path_in_state(&path, &state_mux)
+}
+
+/// Restricts filesystem commands to paths under `roots`; an empty list allows everything
+#[tauri::command]
+pub async fn set_allowed_roots(state_mux: State<'_, StateSafe>, roots: Vec<String>) -> Result<(), Error> {
+    let roots = roots
+        .iter()
+        .map(|root| resolve_path(root, &[]))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut state = state_mux
+        .lock()
+        .map_err(|_| Error::Custom("Failed to acquire state lock".to_string()))?;
+    state.allowed_roots = roots;
+    Ok(())
+}
+
+/// Sets Unix permission bits, e.g. `0o600` to make a key file private.
+/// Symlinks are refused, since chmod would change whatever they point to.
+#[tauri::command]
+pub async fn set_permissions(state_mux: State<'_, StateSafe>, path: String, mode: u32) -> Result<(), Error> {
+    let path = resolve_path_in_state(&path, &state_mux)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if fs::symlink_metadata(&path)?.file_type().is_symlink() {
+            return Err(Error::Custom("Refusing to change permissions through a symlink".to_string()));
+        }
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o7777))?;
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode);
+        Err(Error::Custom("Setting permission bits is only supported on Unix".to_string()))
     }
 }
 
@@ -147,12 +561,19 @@ pub async fn get_clipboard_path() -> Result<String, Error> {
     clipboard::get_clipboard_path()
 }
 
+/// Copies the clipboard path into `destination`. Directory symlinks are followed unless
+/// `walk_options` says otherwise, as pastes always have.
 #[tauri::command]
-pub async fn paste_file(state_mux: State<'_, StateSafe>, destination: String) -> Result<String, Error> {
+pub async fn paste_file(
+    state_mux: State<'_, StateSafe>,
+    destination: String,
+    walk_options: Option<WalkOptions>,
+) -> Result<String, Error> {
     use crate::filesystem::clipboard;
 
     // Get the source path from the backend clipboard
-    let source_path = clipboard::get_clipboard_path()?;
+    let source_path = resolve_path_in_state(&clipboard::get_clipboard_path()?, &state_mux)?;
+    let destination = resolve_path_in_state(&destination, &state_mux)?;
     let source_meta = fs::metadata(&source_path)
         .map_err(|e| Error::Custom(format!("Source file not found: {}", e)))?;
 
@@ -167,8 +588,21 @@ pub async fn paste_file(state_mux: State<'_, StateSafe>, destination: String) ->
     let dest_full_path = Path::new(&destination).join(&source_file_name);
     let destination_path = dest_full_path.to_string_lossy().to_string();
 
+    let options = walk_options.unwrap_or(WalkOptions { follow_symlinks: true, ..WalkOptions::default() });
+    let required = if source_meta.is_dir() {
+        options
+            .walk(&source_path)
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| e.metadata().ok())
+            .map(|m| m.len())
+            .sum()
+    } else {
+        source_meta.len()
+    };
+    check_free_space(Path::new(&destination), required)?;
+
     if source_meta.is_dir() {
-        copy_dir_recursive(&source_path, &destination_path)?;
+        copy_dir_recursive(Path::new(&source_path), &dest_full_path, &options)?;
     } else {
         fs::copy(&source_path, &destination_path)
             .map_err(|e| Error::Custom(format!("Failed to copy file: {}", e)))?;
@@ -185,30 +619,395 @@ pub async fn paste_file(state_mux: State<'_, StateSafe>, destination: String) ->
     Ok(source_file_name)
 }
 
-fn copy_dir_recursive(src: &str, dst: &str) -> Result<(), Error> {
-    fs::create_dir_all(dst)
-        .map_err(|e| Error::Custom(format!("Failed to create destination directory: {}", e)))?;
+/// mtimes closer than this count as equal, since FAT stores them in 2-second steps
+const MTIME_TOLERANCE_SECS: u64 = 2;
 
-    for entry in fs::read_dir(src)
-        .map_err(|e| Error::Custom(format!("Failed to read source directory: {}", e)))?
-    {
+#[derive(Serialize, Clone)]
+pub struct CopyProgress {
+    pub done: usize,
+    pub total: usize,
+    pub path: String,
+    /// The destination already held this file, so it wasn't copied again
+    pub skipped: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct CopyDirectoryResult {
+    pub copied: usize,
+    pub skipped: usize,
+    /// Destination files that existed but differed in size or mtime, copied again
+    pub recopied: usize,
+    pub bytes: u64,
+    pub failed: Vec<EmptyFailure>,
+}
+
+/// Whether `dest` is a finished copy of a source file with this size and mtime
+fn already_copied(dest: &Path, size: u64, modified: Option<SystemTime>) -> bool {
+    let Ok(meta) = fs::metadata(dest) else {
+        return false;
+    };
+    let same_mtime = match (meta.modified().ok(), modified) {
+        (Some(a), Some(b)) => {
+            let gap = a.duration_since(b).or_else(|_| b.duration_since(a)).unwrap_or_default();
+            gap.as_secs() <= MTIME_TOLERANCE_SECS
+        }
+        _ => false,
+    };
+    meta.is_file() && meta.len() == size && same_mtime
+}
+
+/// Copies `src` to `dest` and gives the copy the source's mtime. The mtime is only set
+/// once the data is complete, so an interrupted copy never looks finished to a resume.
+/// Permissions are applied last, through the handle the data went in by, so a read-only
+/// source still gets its mtime.
+fn copy_with_mtime(src: &Path, dest: &Path, modified: Option<SystemTime>) -> std::io::Result<u64> {
+    let permissions = fs::metadata(src)?.permissions();
+    let mut input = fs::File::open(src)?;
+    let mut output = fs::File::create(dest)?;
+    let bytes = std::io::copy(&mut input, &mut output)?;
+    if let Some(modified) = modified {
+        output.set_modified(modified)?;
+    }
+    drop(output);
+    fs::set_permissions(dest, permissions)?;
+    Ok(bytes)
+}
+
+/// Copies the directory tree `src` to `dst`, emitting `copy_progress` for every file.
+/// With `resume`, files already in `dst` with the source's size and mtime are skipped,
+/// and ones that differ (e.g. cut off by an interrupted run) are copied again.
+/// Per-file failures are collected instead of stopping the copy.
+#[tauri::command]
+pub async fn copy_directory(
+    window: Window,
+    state_mux: State<'_, StateSafe>,
+    src: String,
+    dst: String,
+    resume: Option<bool>,
+    walk_options: Option<WalkOptions>,
+) -> Result<CopyDirectoryResult, Error> {
+    let src = resolve_path_in_state(&src, &state_mux)?;
+    let dst = resolve_path_in_state(&dst, &state_mux)?;
+    let (src_dir, dst_dir) = (Path::new(&src), Path::new(&dst));
+
+    if !src_dir.is_dir() {
+        return Err(Error::Custom("Source is not a directory".to_string()));
+    }
+    if dst_dir.starts_with(src_dir) {
+        return Err(Error::Custom("Cannot copy a directory into itself".to_string()));
+    }
+    let resume = resume.unwrap_or(false);
+    let options = walk_options.unwrap_or_default();
+    if dst_dir.exists() && !resume {
+        return Err(Error::Custom("Destination already exists; pass resume to continue a copy".to_string()));
+    }
+
+    // (source, destination, size, mtime, already copied by an earlier run)
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+    let mut failed = Vec::new();
+    for entry in options.walk_dir(src_dir).into_iter().filter_entry(|e| options.allows_entry(e)) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let path = e.path().unwrap_or(src_dir).to_string_lossy().to_string();
+                failed.push(EmptyFailure { path, error: e.to_string() });
+                continue;
+            }
+        };
+        let Ok(relative) = entry.path().strip_prefix(src_dir) else {
+            continue;
+        };
+        let target = dst_dir.join(relative);
+        if entry.file_type().is_dir() {
+            dirs.push(target);
+        } else if entry.file_type().is_file() {
+            let meta = match entry.metadata() {
+                Ok(meta) => meta,
+                Err(e) => {
+                    failed.push(EmptyFailure { path: entry.path().to_string_lossy().to_string(), error: e.to_string() });
+                    continue;
+                }
+            };
+            let modified = meta.modified().ok();
+            let done = resume && already_copied(&target, meta.len(), modified);
+            files.push((entry.into_path(), target, meta.len(), modified, done));
+        }
+    }
+
+    let required = files.iter().filter(|f| !f.4).map(|f| f.2).sum();
+    check_free_space(dst_dir, required)?;
+    for dir in &dirs {
+        fs::create_dir_all(dir)
+            .map_err(|e| Error::Custom(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+
+    let total = files.len();
+    let mut result = CopyDirectoryResult { copied: 0, skipped: 0, recopied: 0, bytes: 0, failed };
+    for (done, (source, target, _, modified, finished)) in files.into_iter().enumerate() {
+        if finished {
+            result.skipped += 1;
+        } else {
+            let existed = target.exists();
+            match copy_with_mtime(&source, &target, modified) {
+                Ok(bytes) => {
+                    result.bytes += bytes;
+                    result.copied += 1;
+                    if existed {
+                        result.recopied += 1;
+                    }
+                }
+                Err(e) => result.failed.push(EmptyFailure { path: source.to_string_lossy().to_string(), error: e.to_string() }),
+            }
+        }
+
+        let progress = CopyProgress { done: done + 1, total, path: target.to_string_lossy().to_string(), skipped: finished };
+        let _ = window.emit("copy_progress", progress);
+    }
+
+    let mount_point_str = get_mount_point(dst.clone()).unwrap_or_default();
+    let fs_event_manager = FsEventHandler::new(state_mux.deref().clone(), mount_point_str.into());
+    fs_event_manager.handle_create(CreateKind::Folder, dst_dir);
+
+    Ok(result)
+}
+
+/// Copies every entry under `src` that `options` allows into `dst`, stopping at the first failure
+fn copy_dir_recursive(src: &Path, dst: &Path, options: &WalkOptions) -> Result<(), Error> {
+    for entry in options.walk_dir(src).into_iter().filter_entry(|e| options.allows_entry(e)) {
         let entry = entry
             .map_err(|e| Error::Custom(format!("Failed to read directory entry: {}", e)))?;
-        let path = entry.path();
-        let file_name = path.file_name()
-            .ok_or_else(|| Error::Custom("Invalid filename".to_string()))?;
-        let dest_path = Path::new(dst).join(file_name);
-
-        if path.is_dir() {
-            copy_dir_recursive(
-                path.to_str().unwrap_or(""),
-                dest_path.to_str().unwrap_or(""),
-            )?;
+        let relative = entry
+            .path()
+            .strip_prefix(src)
+            .map_err(|_| Error::Custom("Invalid filename".to_string()))?;
+        let dest_path = dst.join(relative);
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&dest_path)
+                .map_err(|e| Error::Custom(format!("Failed to create destination directory: {}", e)))?;
         } else {
-            fs::copy(&path, &dest_path)
+            fs::copy(entry.path(), &dest_path)
                 .map_err(|e| Error::Custom(format!("Failed to copy file: {}", e)))?;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_dir_recursive_copies_what_the_options_allow() {
+        let src = tempfile::tempdir().unwrap();
+        fs::create_dir(src.path().join("sub")).unwrap();
+        fs::write(src.path().join("sub").join("a.txt"), "a").unwrap();
+        fs::write(src.path().join(".hidden"), "h").unwrap();
+        let dst = tempfile::tempdir().unwrap();
+
+        let all = dst.path().join("all");
+        copy_dir_recursive(src.path(), &all, &WalkOptions::default()).unwrap();
+        assert_eq!(fs::read_to_string(all.join("sub").join("a.txt")).unwrap(), "a");
+        assert!(all.join(".hidden").exists());
+
+        let visible = dst.path().join("visible");
+        let options = WalkOptions { show_hidden: false, ..WalkOptions::default() };
+        copy_dir_recursive(src.path(), &visible, &options).unwrap();
+        assert!(visible.join("sub").join("a.txt").exists());
+        assert!(!visible.join(".hidden").exists());
+    }
+
+    fn path_string(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn case_only_renames_need_the_same_file_behind_both_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("Readme.md");
+        fs::write(&old, "docs").unwrap();
+        let new = dir.path().join("README.md");
+
+        assert!(!is_case_only_rename(&path_string(&old), &path_string(&old)));
+        assert!(!is_case_only_rename(&path_string(&old), &path_string(&dir.path().join("Notes.md"))));
+        // Case-sensitive filesystems have no README.md yet, so the plain rename is used
+        if !new.exists() {
+            assert!(!is_case_only_rename(&path_string(&old), &path_string(&new)));
+            // A second link stands in for the case-insensitive lookup of the same file
+            fs::hard_link(&old, &new).unwrap();
+        }
+        assert!(is_case_only_rename(&path_string(&old), &path_string(&new)));
+    }
+
+    #[test]
+    fn renaming_through_a_temp_name_applies_the_new_case() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("Readme.md");
+        fs::write(&old, "docs").unwrap();
+        let new = dir.path().join("README.md");
+
+        rename_via_temp(&path_string(&old), &path_string(&new)).unwrap();
+        let names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["README.md"]);
+        assert_eq!(fs::read_to_string(&new).unwrap(), "docs");
+    }
+
+    #[test]
+    fn emptying_requires_the_directory_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("Downloads");
+        fs::create_dir(&target).unwrap();
+        fs::write(dir.path().join("file.txt"), "").unwrap();
+
+        assert!(check_can_empty(&target, "Downloads").is_ok());
+        assert!(check_can_empty(&target, "downloads").is_err());
+        assert!(check_can_empty(&dir.path().join("file.txt"), "file.txt").is_err());
+        assert!(check_can_empty(Path::new("/"), "").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn removing_a_linked_directory_keeps_its_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("keep.txt"), "keep").unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("a.txt"), "a").unwrap();
+
+        for child in ["link", "sub"] {
+            remove_child(&dir.path().join(child), false).unwrap();
+        }
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+        assert!(outside.path().join("keep.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn open_files_reports_each_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing: Vec<String> = ["a.txt", "b.txt", "a.txt"].iter().map(|n| path_string(&dir.path().join(n))).collect();
+
+        let results = open_files(missing.clone(), None).await.unwrap();
+        assert_eq!(results.iter().map(|r| r.path.as_str()).collect::<Vec<_>>(), vec![&missing[0], &missing[1]]);
+        assert!(results.iter().all(|r| r.error.as_deref() == Some("File not found")));
+
+        assert_eq!(open_files(missing, Some(false)).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn open_directory_reports_what_it_cannot_read() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+
+        let listing = open_directory(path_string(dir.path()), None).await.unwrap();
+        assert_eq!(listing.children.len(), 2);
+        assert!(listing.inaccessible.is_empty());
+
+        let missing = path_string(&dir.path().join("gone"));
+        let listing = open_directory(missing.clone(), None).await.unwrap();
+        assert!(listing.children.is_empty());
+        assert_eq!(listing.inaccessible, vec![missing]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn entries_without_metadata_are_listed_as_inaccessible() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("a.txt"), "a").unwrap();
+        // Listing needs read, metadata needs search permission
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o444)).unwrap();
+        let searchable = fs::metadata(locked.join("a.txt")).is_ok();
+
+        let listing = open_directory(path_string(&locked), None).await.unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        // Permissions don't stop root, so there is nothing to test there
+        if searchable {
+            return;
+        }
+        assert!(listing.children.is_empty());
+        assert_eq!(listing.inaccessible, vec![path_string(&locked.join("a.txt"))]);
+    }
+
+    #[test]
+    fn rename_patterns_expand_tokens() {
+        let path = Path::new("/photos/IMG_0001.JPG");
+
+        assert_eq!(expand_rename_pattern("{name}_{index}.{ext}", path, 7).unwrap(), "IMG_0001_7.JPG");
+        assert_eq!(expand_rename_pattern("trip-{index:3}.{ext}", path, 7).unwrap(), "trip-007.JPG");
+        assert!(expand_rename_pattern("{nope}", path, 1).is_err());
+        assert!(expand_rename_pattern("{name", path, 1).is_err());
+        assert!(expand_rename_pattern("{index:x}", path, 1).is_err());
+        assert!(expand_rename_pattern("../{name}", path, 1).is_err());
+    }
+
+    #[test]
+    fn bulk_renames_detect_collisions_and_allow_swaps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| path_string(&dir.path().join(name));
+        for (name, data) in [("a.txt", "a"), ("b.txt", "b"), ("other.txt", "o")] {
+            fs::write(dir.path().join(name), data).unwrap();
+        }
+        let plan = |renames: &[(&str, &str)]| -> Vec<PlannedRename> {
+            renames.iter().map(|(from, to)| PlannedRename { from: path(from), to: path(to) }).collect()
+        };
+
+        assert_eq!(rename_collisions(&plan(&[("a.txt", "other.txt")])), vec![path("other.txt")]);
+        assert_eq!(rename_collisions(&plan(&[("a.txt", "x.txt"), ("b.txt", "X.txt")])), vec![path("X.txt")]);
+
+        let swap = plan(&[("a.txt", "b.txt"), ("b.txt", "a.txt")]);
+        assert!(rename_collisions(&swap).is_empty());
+        let (applied, failed) = apply_renames(&swap);
+        assert_eq!((applied.len(), failed.len()), (2, 0));
+        assert_eq!(fs::read_to_string(path("a.txt")).unwrap(), "b");
+        assert_eq!(fs::read_to_string(path("b.txt")).unwrap(), "a");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn resumed_copies_skip_only_finished_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.bin");
+        let dest = dir.path().join("dest.bin");
+        fs::write(&src, b"complete data").unwrap();
+        let modified = SystemTime::now() - std::time::Duration::from_secs(3600);
+        fs::File::options().write(true).open(&src).unwrap().set_modified(modified).unwrap();
+
+        assert!(!already_copied(&dest, 13, Some(modified)));
+        // An interrupted copy has the wrong size and a fresh mtime
+        fs::write(&dest, b"compl").unwrap();
+        assert!(!already_copied(&dest, 13, Some(modified)));
+
+        assert_eq!(copy_with_mtime(&src, &dest, Some(modified)).unwrap(), 13);
+        assert!(already_copied(&dest, 13, Some(modified)));
+        assert!(already_copied(&dest, 13, Some(modified + std::time::Duration::from_secs(1))));
+        assert!(!already_copied(&dest, 13, Some(modified + std::time::Duration::from_secs(10))));
+    }
+
+    #[test]
+    fn read_only_sources_keep_their_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let dest = dir.path().join("dest.txt");
+        fs::write(&src, "locked").unwrap();
+        let modified = SystemTime::now() - std::time::Duration::from_secs(3600);
+        fs::File::options().write(true).open(&src).unwrap().set_modified(modified).unwrap();
+        let mut permissions = fs::metadata(&src).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&src, permissions).unwrap();
+
+        copy_with_mtime(&src, &dest, Some(modified)).unwrap();
+        let meta = fs::metadata(&dest).unwrap();
+        assert!(meta.permissions().readonly());
+        assert_eq!(meta.modified().unwrap(), modified);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::errors::Error;
+use serde::Serialize;
+use std::fmt::Display;
+use std::path::Path;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FileInUse {
+    pub path: String,
+    pub in_use: bool,
+    /// Names of the processes holding the file open, where the platform reports them
+    pub processes: Vec<String>,
+}
+
+/// Whether another process has `path` open. On Windows this tries an open that shares
+/// nothing and asks the Restart Manager who holds the file; on Linux it scans the open
+/// descriptors in /proc (only processes this user may inspect); elsewhere it asks `lsof`.
+pub fn check_in_use(path: &Path) -> FileInUse {
+    let processes = holding_processes(path);
+
+    #[cfg(windows)]
+    let in_use = !processes.is_empty() || locked_by_share_mode(path);
+    #[cfg(not(windows))]
+    let in_use = !processes.is_empty();
+
+    FileInUse {
+        path: path.display().to_string(),
+        in_use,
+        processes,
+    }
+}
+
+/// Message for a failed delete or move, naming the processes holding `path` when it is in use
+pub fn describe_failure(path: &Path, err: impl Display) -> String {
+    let status = check_in_use(path);
+    if !status.in_use {
+        return err.to_string();
+    }
+    if status.processes.is_empty() {
+        format!("{} is in use by another process ({})", path.display(), err)
+    } else {
+        format!("{} is in use by {} ({})", path.display(), status.processes.join(", "), err)
+    }
+}
+
+#[tauri::command]
+pub async fn is_file_in_use(path: String) -> Result<FileInUse, Error> {
+    let path = Path::new(&path);
+    if !path.exists() {
+        return Err(Error::Custom(format!("Path not found: {}", path.display())));
+    }
+    Ok(check_in_use(path))
+}
+
+/// Opening with no sharing fails with a sharing or lock violation while any other handle is open
+#[cfg(windows)]
+fn locked_by_share_mode(path: &Path) -> bool {
+    use std::os::windows::fs::OpenOptionsExt;
+    use windows_sys::Win32::Foundation::{ERROR_LOCK_VIOLATION, ERROR_SHARING_VIOLATION};
+
+    if path.is_dir() {
+        return false;
+    }
+    match std::fs::OpenOptions::new().read(true).share_mode(0).open(path) {
+        Ok(_) => false,
+        Err(err) => matches!(
+            err.raw_os_error(),
+            Some(code) if code == ERROR_SHARING_VIOLATION as i32 || code == ERROR_LOCK_VIOLATION as i32
+        ),
+    }
+}
+
+#[cfg(windows)]
+fn holding_processes(path: &Path) -> Vec<String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::{ERROR_MORE_DATA, ERROR_SUCCESS};
+    use windows_sys::Win32::System::RestartManager::{
+        RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY, RM_PROCESS_INFO,
+    };
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut session = 0u32;
+    let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+    let mut names = Vec::new();
+
+    // SAFETY: every pointer passed refers to a live, correctly sized local buffer, and the
+    // session is ended before returning
+    unsafe {
+        if RmStartSession(&mut session, 0, key.as_mut_ptr()) != ERROR_SUCCESS {
+            return names;
+        }
+
+        let resources = [wide.as_ptr()];
+        if RmRegisterResources(session, 1, resources.as_ptr(), 0, std::ptr::null(), 0, std::ptr::null())
+            == ERROR_SUCCESS
+        {
+            let mut needed = 0u32;
+            let mut count = 0u32;
+            let mut reasons = 0u32;
+            let mut infos: Vec<RM_PROCESS_INFO> = Vec::new();
+            // The process list can grow between calls, so retry until it fits
+            loop {
+                let status = RmGetList(session, &mut needed, &mut count, infos.as_mut_ptr(), &mut reasons);
+                if status == ERROR_MORE_DATA {
+                    infos.resize_with(needed as usize, || std::mem::zeroed());
+                    count = needed;
+                    continue;
+                }
+                if status == ERROR_SUCCESS {
+                    for info in infos.iter().take(count as usize) {
+                        let len = info.strAppName.iter().position(|&c| c == 0).unwrap_or(info.strAppName.len());
+                        names.push(String::from_utf16_lossy(&info.strAppName[..len]));
+                    }
+                }
+                break;
+            }
+        }
+        RmEndSession(session);
+    }
+
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(target_os = "linux")]
+fn holding_processes(path: &Path) -> Vec<String> {
+    let Ok(target) = std::fs::canonicalize(path) else {
+        return Vec::new();
+    };
+    let own_pid = std::process::id().to_string();
+    let Ok(procs) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    let mut names = Vec::new();
+    for proc_entry in procs.filter_map(Result::ok) {
+        let pid = proc_entry.file_name().to_string_lossy().to_string();
+        if pid == own_pid || !pid.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        // Descriptors of other users' processes are unreadable and skipped
+        let Ok(fds) = std::fs::read_dir(proc_entry.path().join("fd")) else {
+            continue;
+        };
+        let holds = fds
+            .filter_map(Result::ok)
+            .any(|fd| std::fs::read_link(fd.path()).map(|link| link == target).unwrap_or(false));
+        if holds {
+            let name = std::fs::read_to_string(proc_entry.path().join("comm"))
+                .map(|comm| comm.trim().to_string())
+                .unwrap_or_default();
+            names.push(if name.is_empty() { format!("pid {}", pid) } else { format!("{} (pid {})", name, pid) });
+        }
+    }
+    names.sort();
+    names
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn holding_processes(path: &Path) -> Vec<String> {
+    // `lsof -F pc` prints one `p<pid>` line followed by a `c<command>` line per process
+    let Ok(output) = std::process::Command::new("lsof").arg("-F").arg("pc").arg(path).output() else {
+        return Vec::new();
+    };
+    let own_pid = std::process::id().to_string();
+
+    let mut names = Vec::new();
+    let mut pid = String::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(p) = line.strip_prefix('p') {
+            pid = p.to_string();
+        } else if let Some(command) = line.strip_prefix('c') {
+            if pid != own_pid {
+                names.push(format!("{} (pid {})", command, pid));
+            }
+        }
+    }
+    names.sort();
+    names
+}
+
+#[cfg(not(any(unix, windows)))]
+fn holding_processes(_path: &Path) -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unheld_file_reports_the_plain_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("free.txt");
+        std::fs::write(&path, "x").unwrap();
+
+        assert!(!check_in_use(&path).in_use);
+        assert_eq!(describe_failure(&path, "Permission denied"), "Permission denied");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn names_the_process_holding_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("held.txt");
+        std::fs::write(&path, "x").unwrap();
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .stdin(std::fs::File::open(&path).unwrap())
+            .spawn()
+            .unwrap();
+
+        let status = check_in_use(&path);
+        let message = describe_failure(&path, "Permission denied");
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert!(status.in_use);
+        let pid = format!("pid {}", child.id());
+        assert!(status.processes.iter().any(|p| p.contains(&pid)), "{:?}", status.processes);
+        assert!(message.contains("is in use by") && message.contains(&pid));
+    }
+}
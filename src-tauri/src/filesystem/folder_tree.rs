@@ -2,6 +2,7 @@ use std::fs;
 use std::path::Path;
 use serde::Serialize;
 use tauri::command;
+use crate::filesystem::walk::WalkOptions;
 
 #[derive(Serialize)]
 pub struct FileNode {
@@ -9,33 +10,57 @@ pub struct FileNode {
     pub path: String,
     pub is_dir: bool,
     pub children: Option<Vec<FileNode>>,
+    /// Uncompressed size of files in an archive tree; not read for directory trees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Only set on the root: paths that couldn't be read anywhere in the tree
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub inaccessible: Vec<String>,
 }
 
 #[command]
-pub fn read_dir_recursive(path: String) -> Result<FileNode, String> {
+pub fn read_dir_recursive(path: String, walk_options: Option<WalkOptions>) -> Result<FileNode, String> {
     let path_obj = Path::new(&path);
     if !path_obj.exists() {
         return Err("Path not found".to_string());
     }
+    let options = walk_options.unwrap_or_default();
 
-    fn build_tree(path: &Path) -> FileNode {
+    fn build_tree(path: &Path, options: &WalkOptions, depth: usize, inaccessible: &mut Vec<String>) -> FileNode {
         let name = path.file_name()
             .map(|s| s.to_string_lossy().to_string())
             .unwrap_or_else(|| path.display().to_string());
 
-        let is_dir = path.is_dir();
+        // The root is always expanded, even when it is itself a symlink
+        let is_dir = if options.follow_symlinks || depth == 0 {
+            path.is_dir()
+        } else {
+            fs::symlink_metadata(path).map(|m| m.is_dir()).unwrap_or(false)
+        };
+        let descend = !matches!(options.max_depth, Some(max) if depth >= max);
 
-        let children = if is_dir {
+        let children = if is_dir && descend {
             match fs::read_dir(path) {
                 Ok(entries) => {
                     let mut nodes = Vec::new();
-                    for entry in entries.flatten() {
-                        let entry_path = entry.path();
-                        nodes.push(build_tree(&entry_path));
+                    for entry in entries {
+                        let entry_path = match entry {
+                            Ok(entry) => entry.path(),
+                            Err(_) => {
+                                inaccessible.push(path.display().to_string());
+                                continue;
+                            }
+                        };
+                        if options.allows_path(&entry_path) {
+                            nodes.push(build_tree(&entry_path, options, depth + 1, inaccessible));
+                        }
                     }
                     Some(nodes)
                 }
-                Err(_) => None,
+                Err(_) => {
+                    inaccessible.push(path.display().to_string());
+                    None
+                }
             }
         } else {
             None
@@ -46,8 +71,36 @@ pub fn read_dir_recursive(path: String) -> Result<FileNode, String> {
             path: path.display().to_string(),
             is_dir,
             children,
+            size: None,
+            inaccessible: Vec::new(),
         }
     }
 
-    Ok(build_tree(path_obj))
+    let mut inaccessible = Vec::new();
+    let mut root = build_tree(path_obj, &options, 0, &mut inaccessible);
+    inaccessible.dedup();
+    root.inaccessible = inaccessible;
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_entries_are_left_out_of_the_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".hidden"), "").unwrap();
+        fs::write(dir.path().join("visible"), "").unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        let names = |tree: FileNode| {
+            let mut names: Vec<String> = tree.children.unwrap().into_iter().map(|c| c.name).collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(read_dir_recursive(root.clone(), None).unwrap()), vec![".hidden", "visible"]);
+        let options = WalkOptions { show_hidden: false, ..WalkOptions::default() };
+        assert_eq!(names(read_dir_recursive(root, Some(options)).unwrap()), vec!["visible"]);
+    }
 }
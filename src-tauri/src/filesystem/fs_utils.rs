@@ -1,4 +1,8 @@
+use crate::errors::Error;
+use crate::StateSafe;
+use std::fs;
 use std::path::{Path, PathBuf};
+use sysinfo::{DiskExt, System, SystemExt};
 
 pub fn get_mount_point(path: String) -> Option<String> {
     let path = Path::new(&path);
@@ -10,3 +14,101 @@ pub fn get_mount_point(path: String) -> Option<String> {
     mount_point_path.push("\\");
     Some(mount_point_path.to_string_lossy().into_owned())
 }
+
+/// Canonicalizes `path` and checks it stays inside one of `allowed_roots` (any root if empty).
+/// Only the parent is resolved, so the final name is kept as given: the target may not
+/// exist yet, and a symlink is acted on itself rather than on what it points to.
+pub fn resolve_path(path: &str, allowed_roots: &[PathBuf]) -> Result<PathBuf, Error> {
+    let raw = Path::new(path);
+    if !raw.is_absolute() {
+        return Err(Error::Custom(format!("Path must be absolute: {}", path)));
+    }
+
+    let resolved = match (raw.parent(), raw.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent)
+            .map_err(|e| Error::Custom(format!("Invalid path {}: {}", path, e)))?
+            .join(name),
+        (None, _) => fs::canonicalize(raw)?,
+        (Some(_), None) => {
+            return Err(Error::Custom(format!("Path must name a file or directory: {}", path)));
+        }
+    };
+
+    if !allowed_roots.is_empty() && !allowed_roots.iter().any(|root| resolved.starts_with(root)) {
+        return Err(Error::Custom(format!("Path is outside the allowed directories: {}", path)));
+    }
+
+    Ok(resolved)
+}
+
+/// `resolve_path` against the roots configured in the app state, returned as a string
+pub fn resolve_path_in_state(path: &str, state_mux: &StateSafe) -> Result<String, Error> {
+    let roots = state_mux
+        .lock()
+        .map_err(|_| Error::Custom("Failed to acquire state lock".to_string()))?
+        .allowed_roots
+        .clone();
+    Ok(resolve_path(path, &roots)?.to_string_lossy().to_string())
+}
+
+/// Bytes available on the disk holding `path`, which may not exist yet; its nearest
+/// existing ancestor is looked up instead. `None` if no mounted disk contains it.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let resolved = fs::canonicalize(existing).ok()?;
+    // Verbatim `\\?\C:\` paths don't start with the `C:\` mount point
+    #[cfg(windows)]
+    let resolved = PathBuf::from(resolved.to_string_lossy().trim_start_matches(r"\\?\"));
+
+    let mut system = System::new();
+    system.refresh_disks_list();
+    system
+        .disks()
+        .iter()
+        .filter(|disk| resolved.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+/// Fails with `InsufficientSpace` when writing `required_bytes` under `dest` would not fit.
+/// Called before exports, extractions and copies so they don't stop halfway with a
+/// partial file. Passes when the free space can't be determined.
+pub fn check_free_space(dest: &Path, required_bytes: u64) -> Result<(), Error> {
+    match available_space(dest) {
+        Some(available) if required_bytes > available => Err(Error::InsufficientSpace {
+            required: required_bytes,
+            available,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traversal_out_of_the_allowed_root_is_rejected() {
+        let root = tempfile::tempdir().unwrap();
+        let roots = vec![fs::canonicalize(root.path()).unwrap()];
+        let escape = format!("{}/../../etc/passwd", root.path().display());
+
+        let err = resolve_path(&escape, &roots).unwrap_err();
+        assert!(err.to_string().contains("outside the allowed directories"));
+    }
+
+    #[test]
+    fn paths_inside_the_root_resolve_even_if_missing() {
+        let root = tempfile::tempdir().unwrap();
+        let roots = vec![fs::canonicalize(root.path()).unwrap()];
+        fs::create_dir(root.path().join("sub")).unwrap();
+        let inside = format!("{}/sub/../new.txt", root.path().display());
+
+        assert_eq!(resolve_path(&inside, &roots).unwrap(), roots[0].join("new.txt"));
+    }
+
+    #[test]
+    fn relative_paths_are_rejected() {
+        assert!(resolve_path("../etc/passwd", &[]).is_err());
+    }
+}
@@ -1,9 +1,13 @@
 pub mod cache;
 pub mod explorer;
-mod fs_utils;
+pub mod fs_utils;
 pub mod volume;
 pub mod folder_tree;
 pub mod clipboard;
+pub mod tail;
+pub mod activity;
+pub mod walk;
+pub mod file_lock;
 
 pub const DIRECTORY: &str = "directory";
 pub const FILE: &str = "file";
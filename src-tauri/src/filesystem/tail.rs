@@ -0,0 +1,281 @@
+use crate::errors::Error;
+use lazy_static::lazy_static;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{Emitter, Window};
+
+/// Lines emitted immediately when tailing starts, unless the caller asks for more
+const DEFAULT_TAIL_LINES: usize = 100;
+
+/// `last_lines` reads at most this much from the end of the file, so a file without
+/// newlines (or a huge `lines` request) can't make it load everything
+const MAX_TAIL_SCAN_BYTES: u64 = 4 * 1024 * 1024;
+
+lazy_static! {
+    static ref ACTIVE_TAILS: Mutex<HashMap<String, RecommendedWatcher>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Serialize, Clone)]
+pub struct TailLine {
+    pub path: String,
+    pub line: String,
+}
+
+/// Read offset and the unterminated last line of a tailed file
+struct TailCursor {
+    path: PathBuf,
+    /// Identity of the file the offset belongs to
+    id: Option<(u64, u64)>,
+    offset: u64,
+    partial: String,
+}
+
+impl TailCursor {
+    /// Reads everything appended since the last call and returns the completed lines.
+    /// A file that was replaced (rotated) or shrank (truncated) is read again from the top.
+    fn read_new_lines(&mut self) -> std::io::Result<Vec<String>> {
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+        let id = file_id(&file);
+        if id != self.id || len < self.offset {
+            self.id = id;
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if len == self.offset {
+            return Ok(Vec::new());
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::with_capacity((len - self.offset) as usize);
+        file.read_to_end(&mut buf)?;
+        self.offset += buf.len() as u64;
+
+        self.partial.push_str(&String::from_utf8_lossy(&buf));
+        let mut lines: Vec<String> = self.partial.split('\n').map(String::from).collect();
+        self.partial = lines.pop().unwrap_or_default();
+
+        Ok(lines
+            .into_iter()
+            .map(|l| l.trim_end_matches('\r').to_string())
+            .collect())
+    }
+}
+
+/// Identity of an open file, (device, inode) on Unix or (volume serial, file index) on Windows
+#[cfg(unix)]
+fn file_id(file: &File) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = file.metadata().ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(windows)]
+fn file_id(file: &File) -> Option<(u64, u64)> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION};
+
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    // SAFETY: the handle stays open for as long as `file` is borrowed and `info` is a
+    // valid BY_HANDLE_FILE_INFORMATION to write into
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) } == 0 {
+        return None;
+    }
+    let index = ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64;
+    Some((info.dwVolumeSerialNumber as u64, index))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn file_id(_file: &File) -> Option<(u64, u64)> {
+    None
+}
+
+/// Returns the last `count` lines of the file and the offset just past them, looking
+/// no further back than `max_bytes`
+fn last_lines(file: &mut File, count: usize, max_bytes: u64) -> std::io::Result<(Vec<String>, u64)> {
+    const CHUNK: u64 = 64 * 1024;
+
+    let len = file.metadata()?.len();
+    let floor = len.saturating_sub(max_bytes);
+    let mut start = len;
+    let mut newlines = 0;
+    let mut buf: Vec<u8> = Vec::new();
+
+    // Walk backwards until enough newlines have been seen
+    while start > floor && newlines <= count {
+        let read_from = start.saturating_sub(CHUNK).max(floor);
+        let mut chunk = vec![0u8; (start - read_from) as usize];
+        file.seek(SeekFrom::Start(read_from))?;
+        file.read_exact(&mut chunk)?;
+        newlines += chunk.iter().filter(|&&b| b == b'\n').count();
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+        start = read_from;
+    }
+
+    // Unless the scan reached the top, the first line is cut off
+    if start > 0 {
+        let cut = buf.iter().position(|&b| b == b'\n').map_or(buf.len(), |i| i + 1);
+        buf.drain(..cut);
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<String> = text
+        .lines()
+        .map(|l| l.trim_end_matches('\r').to_string())
+        .collect();
+    if lines.len() > count {
+        lines.drain(..lines.len() - count);
+    }
+
+    Ok((lines, len))
+}
+
+/// Emits the last lines of `path` as `tail_line` events, then streams every appended line
+/// until `stop_tail` is called.
+#[tauri::command]
+pub fn tail_file(window: Window, path: String, lines: Option<usize>) -> Result<(), Error> {
+    let file_path = PathBuf::from(&path);
+    let parent = file_path
+        .parent()
+        .ok_or_else(|| Error::Custom("File has no parent directory".to_string()))?
+        .to_path_buf();
+
+    let mut file = File::open(&file_path)?;
+    let id = file_id(&file);
+    let (initial, offset) = last_lines(&mut file, lines.unwrap_or(DEFAULT_TAIL_LINES), MAX_TAIL_SCAN_BYTES)?;
+    for line in initial {
+        let _ = window.emit("tail_line", TailLine { path: path.clone(), line });
+    }
+
+    let mut cursor = TailCursor {
+        path: file_path.clone(),
+        id,
+        offset,
+        partial: String::new(),
+    };
+    let event_path = path.clone();
+
+    // Watch the parent so a rotated (replaced) file is picked up too
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if !event.paths.iter().any(|p| p == &cursor.path) {
+            return;
+        }
+        if let Ok(new_lines) = cursor.read_new_lines() {
+            for line in new_lines {
+                let _ = window.emit("tail_line", TailLine { path: event_path.clone(), line });
+            }
+        }
+    })
+    .map_err(|e| Error::Custom(format!("Failed to create watcher: {}", e)))?;
+
+    watcher
+        .watch(&parent, RecursiveMode::NonRecursive)
+        .map_err(|e| Error::Custom(format!("Failed to watch file: {}", e)))?;
+
+    let mut tails = ACTIVE_TAILS
+        .lock()
+        .map_err(|_| Error::Custom("Failed to acquire tail lock".to_string()))?;
+    tails.insert(path, watcher);
+
+    Ok(())
+}
+
+/// Stops tailing `path`; dropping the watcher ends the stream
+#[tauri::command]
+pub fn stop_tail(path: String) -> Result<(), Error> {
+    let mut tails = ACTIVE_TAILS
+        .lock()
+        .map_err(|_| Error::Custom("Failed to acquire tail lock".to_string()))?;
+    tails.remove(&path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+
+    fn cursor_at_end(path: &std::path::Path) -> TailCursor {
+        let file = File::open(path).unwrap();
+        TailCursor {
+            path: path.to_path_buf(),
+            id: file_id(&file),
+            offset: file.metadata().unwrap().len(),
+            partial: String::new(),
+        }
+    }
+
+    #[test]
+    fn reads_appended_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "old\n").unwrap();
+        let mut cursor = cursor_at_end(&path);
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"one\ntw").unwrap();
+        assert_eq!(cursor.read_new_lines().unwrap(), vec!["one"]);
+        file.write_all(b"o\r\n").unwrap();
+        assert_eq!(cursor.read_new_lines().unwrap(), vec!["two"]);
+    }
+
+    #[test]
+    fn restarts_when_the_file_is_replaced_by_a_larger_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "old\n").unwrap();
+        let mut cursor = cursor_at_end(&path);
+
+        // Rotation: the new file is already longer than the old offset
+        let rotated = dir.path().join("app.log.new");
+        fs::write(&rotated, "first\nsecond\n").unwrap();
+        fs::rename(&rotated, &path).unwrap();
+
+        assert_eq!(cursor.read_new_lines().unwrap(), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn restarts_when_the_file_is_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "a long first line\n").unwrap();
+        let mut cursor = cursor_at_end(&path);
+
+        fs::write(&path, "new\n").unwrap();
+        assert_eq!(cursor.read_new_lines().unwrap(), vec!["new"]);
+    }
+
+    #[test]
+    fn last_lines_returns_the_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "1\n2\n3\n4\n").unwrap();
+
+        let (lines, offset) = last_lines(&mut File::open(&path).unwrap(), 2, MAX_TAIL_SCAN_BYTES).unwrap();
+        assert_eq!(lines, vec!["3", "4"]);
+        assert_eq!(offset, 8);
+    }
+
+    #[test]
+    fn last_lines_stops_at_the_byte_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let long_line = "x".repeat(1000);
+        fs::write(&path, format!("{}\nshort\n", long_line)).unwrap();
+
+        // The cap cuts into the long line, which is dropped rather than returned in part
+        let (lines, offset) = last_lines(&mut File::open(&path).unwrap(), 10, 100).unwrap();
+        assert_eq!(lines, vec!["short"]);
+        assert_eq!(offset, 1007);
+    }
+}
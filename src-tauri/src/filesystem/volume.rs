@@ -1,6 +1,7 @@
 use crate::filesystem::cache::{
     load_system_cache, run_cache_interval, save_system_cache, FsEventHandler, CACHE_FILE_PATH,
 };
+use crate::filesystem::walk::WalkOptions;
 use crate::filesystem::{bytes_to_gb, DIRECTORY, FILE};
 use crate::{CachedPath, StateSafe};
 use notify::{RecursiveMode, Watcher};
@@ -14,7 +15,6 @@ use std::{fs, thread};
 use sysinfo::{Disk, DiskExt, System, SystemExt};
 use tauri::State;
 use tokio::task::block_in_place;
-use walkdir::WalkDir;
 use std::time::SystemTime;
 
 #[derive(Serialize)]
@@ -80,10 +80,9 @@ impl Volume {
 
         let system_cache = Arc::new(Mutex::new(volume));
 
-        WalkDir::new(self.mountpoint.clone())
-            .into_iter()
+        WalkOptions::default()
+            .walk(self.mountpoint.clone())
             .par_bridge()
-            .filter_map(Result::ok)
             .for_each(|entry| {
                 let file_name = entry.file_name().to_string_lossy().to_string();
                 let file_path = entry.path().to_string_lossy().to_string();
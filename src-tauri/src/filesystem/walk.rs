@@ -0,0 +1,179 @@
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+use walkdir::{DirEntry, WalkDir};
+
+/// Names treated as OS bookkeeping on every platform, whatever their attributes
+const SYSTEM_NAMES: &[&str] = &[
+    ".DS_Store",
+    ".Spotlight-V100",
+    ".Trashes",
+    ".fseventsd",
+    ".TemporaryItems",
+    "Thumbs.db",
+    "desktop.ini",
+    "$RECYCLE.BIN",
+    "System Volume Information",
+    "lost+found",
+];
+
+#[cfg(windows)]
+const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+#[cfg(windows)]
+const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+
+/// How directory-walking commands treat hidden and system entries, symlinks and depth.
+/// The defaults keep every entry, so commands behave as before unless the caller opts in.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct WalkOptions {
+    pub show_hidden: bool,
+    pub skip_system: bool,
+    pub follow_symlinks: bool,
+    pub max_depth: Option<usize>,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            show_hidden: true,
+            skip_system: false,
+            follow_symlinks: false,
+            max_depth: None,
+        }
+    }
+}
+
+impl WalkOptions {
+    /// Whether an entry named `name` is filtered out; `metadata` is only read when needed
+    fn excludes(&self, name: &OsStr, metadata: impl FnOnce() -> Option<fs::Metadata>) -> bool {
+        if self.show_hidden && !self.skip_system {
+            return false;
+        }
+
+        let name = name.to_string_lossy();
+        let dotfile = name.starts_with('.');
+        let system_name = SYSTEM_NAMES.contains(&name.as_ref());
+
+        #[cfg(windows)]
+        let (hidden, system) = {
+            use std::os::windows::fs::MetadataExt;
+            let attributes = metadata().map(|m| m.file_attributes()).unwrap_or(0);
+            (
+                dotfile || attributes & FILE_ATTRIBUTE_HIDDEN != 0,
+                system_name || attributes & FILE_ATTRIBUTE_SYSTEM != 0,
+            )
+        };
+        #[cfg(not(windows))]
+        let (hidden, system) = {
+            let _ = metadata;
+            (dotfile, system_name)
+        };
+
+        (!self.show_hidden && hidden) || (self.skip_system && system)
+    }
+
+    /// Whether `path` should be listed
+    pub fn allows_path(&self, path: &Path) -> bool {
+        match path.file_name() {
+            Some(name) => !self.excludes(name, || fs::symlink_metadata(path).ok()),
+            None => true,
+        }
+    }
+
+    /// Whether a walked entry should be listed (and descended into). The root always is,
+    /// so walking a hidden directory the user opened explicitly still works.
+    pub fn allows_entry(&self, entry: &DirEntry) -> bool {
+        entry.depth() == 0 || !self.excludes(entry.file_name(), || entry.metadata().ok())
+    }
+
+    /// A `WalkDir` over `root` with the depth and symlink settings applied.
+    /// Callers add `filter_entry(|e| options.allows_entry(e))`, plus any filters of their own.
+    pub fn walk_dir(&self, root: impl AsRef<Path>) -> WalkDir {
+        let walker = WalkDir::new(root).follow_links(self.follow_symlinks);
+        match self.max_depth {
+            Some(depth) => walker.max_depth(depth),
+            None => walker,
+        }
+    }
+
+    /// Readable entries under `root` that pass these options
+    pub fn walk(&self, root: impl AsRef<Path>) -> impl Iterator<Item = DirEntry> + '_ {
+        self.walk_dir(root)
+            .into_iter()
+            .filter_entry(move |e| self.allows_entry(e))
+            .filter_map(Result::ok)
+    }
+}
+
+/// Options that leave out dotfiles, for tests of the commands that take `WalkOptions`
+#[cfg(test)]
+pub(crate) fn hide_dotfiles() -> WalkOptions {
+    WalkOptions { show_hidden: false, ..WalkOptions::default() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn walked(options: &WalkOptions, root: &Path) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = options
+            .walk(root)
+            .filter(|e| e.depth() > 0)
+            .map(|e| e.path().strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn defaults_keep_every_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".hidden"), "").unwrap();
+        fs::write(dir.path().join("visible"), "").unwrap();
+
+        assert_eq!(walked(&WalkOptions::default(), dir.path()), vec![PathBuf::from(".hidden"), PathBuf::from("visible")]);
+    }
+
+    #[test]
+    fn hidden_directories_are_not_descended_into() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".git").join("config"), "").unwrap();
+        fs::write(dir.path().join("visible"), "").unwrap();
+
+        assert_eq!(walked(&hide_dotfiles(), dir.path()), vec![PathBuf::from("visible")]);
+    }
+
+    #[test]
+    fn a_hidden_root_is_still_walked() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(".config");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("settings"), "").unwrap();
+
+        assert_eq!(walked(&hide_dotfiles(), &root), vec![PathBuf::from("settings")]);
+    }
+
+    #[test]
+    fn skip_system_drops_os_bookkeeping() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Thumbs.db"), "").unwrap();
+        fs::write(dir.path().join("photo.jpg"), "").unwrap();
+        let options = WalkOptions { skip_system: true, ..WalkOptions::default() };
+
+        assert_eq!(walked(&options, dir.path()), vec![PathBuf::from("photo.jpg")]);
+        assert!(!options.allows_path(&dir.path().join("Thumbs.db")));
+    }
+
+    #[test]
+    fn max_depth_limits_the_walk() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a").join("b")).unwrap();
+        let options = WalkOptions { max_depth: Some(1), ..WalkOptions::default() };
+
+        assert_eq!(walked(&options, dir.path()), vec![PathBuf::from("a")]);
+    }
+}
@@ -8,20 +8,46 @@ mod duplicate_detector;
 mod file_preview;
 mod vault;
 mod content_scanner;
+mod disk_cleanup;
+mod archive;
+mod capabilities;
 
 use filesystem::explorer::{
     create_directory, create_file, delete_file, open_directory, open_file, rename_file,
-    copy_file, paste_file, get_clipboard_path,
+    copy_file, paste_file, get_clipboard_path, set_permissions, validate_path, set_allowed_roots,
+    empty_directory, open_files, bulk_rename, copy_directory,
 };
 use filesystem::volume::get_volumes;
-use search::search_directory;
-use vault::{vault_check_exists, vault_create, vault_open, vault_lock, vault_list_entries, vault_import_file, vault_export_file, vault_delete_entry, vault_generate_recovery_codes};
+use search::{search_directory, search_live, directory_extension_stats, cancel_search, search_export};
+use vault::{
+    vault_check_exists, vault_create, vault_open, vault_lock, vault_list_entries, vault_import_file,
+    vault_export_file, vault_delete_entry, vault_generate_recovery_codes, vault_merkle_root,
+    vault_enable_autobackup, vault_disable_autobackup, vault_copy_to_clipboard,
+    vault_export_metadata, vault_ingest, vault_list_backups, vault_restore_backup,
+    vault_set_capacity_warning, vault_check_duplicate, generate_passphrase,
+    vault_export_recovery, vault_import_recovery, calibrate_argon2, vault_set_pinned,
+    vault_entries_by_category, vault_diff, vault_diagnostics,
+    vault_verify_export, vault_audit_page, vault_split, vault_ingest_and_wipe,
+    estimate_password_strength,
+    vault_repair_log,
+    vault_thumbnail,
+    vault_test_recovery,
+    discover_vaults,
+    vault_upgrade_kdf,
+    vault_rotate_recovery_codes,
+    vault_truncate_log,
+    vault_read_log_archive,
+    vault_open_with_recovery_code,
+};
 use content_scanner::scan_directory_for_sensitive_files;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex, atomic::AtomicU64};
 use filesystem::folder_tree::read_dir_recursive;
 use vault::VaultSession;
+use content_scanner::{ScanCache, ScanPause};
+use file_preview::PreviewLimits;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct CachedPath {
@@ -37,7 +63,12 @@ pub type VolumeCache = HashMap<String, Vec<CachedPath>>;
 pub struct AppState {
     pub system_cache: HashMap<String, VolumeCache>,
     pub active_search_id: AtomicU64,
+    pub active_scan_id: AtomicU64,
+    pub scan_pause: Arc<ScanPause>,
+    pub scan_cache: ScanCache,
     pub vault_sessions: HashMap<String, VaultSession>,
+    pub preview_limits: PreviewLimits,
+    pub allowed_roots: Vec<PathBuf>,
 }
 
 impl AppState {
@@ -45,7 +76,12 @@ impl AppState {
         Self {
             system_cache: HashMap::new(),
             active_search_id: AtomicU64::new(0),
+            active_scan_id: AtomicU64::new(0),
+            scan_pause: Arc::new(ScanPause::default()),
+            scan_cache: ScanCache::default(),
             vault_sessions: HashMap::new(),
+            preview_limits: PreviewLimits::default(),
+            allowed_roots: Vec::new(),
         }
     }
 }
@@ -61,28 +97,72 @@ async fn main() {
 
         // register all backend commands
         .invoke_handler(tauri::generate_handler![
+            // backend
+            capabilities::get_capabilities,
+
             // filesystem
             get_volumes,
             open_directory,
             search_directory,
+            search_live,
+            directory_extension_stats,
+            cancel_search,
+            search_export,
             open_file,
+            open_files,
             create_file,
             create_directory,
             rename_file,
+            bulk_rename,
             delete_file,
             copy_file,
             paste_file,
+            copy_directory,
             get_clipboard_path,
+            set_permissions,
+            validate_path,
+            set_allowed_roots,
+            empty_directory,
             read_dir_recursive,
+            filesystem::tail::tail_file,
+            filesystem::tail::stop_tail,
+            filesystem::activity::watch_directory,
+            filesystem::activity::unwatch_directory,
+            filesystem::activity::get_recent_changes,
+            filesystem::file_lock::is_file_in_use,
             // duplicate detector
             duplicate_detector::find_duplicate_files,
             duplicate_detector::delete_files,
+            duplicate_detector::find_duplicate_directories,
+            duplicate_detector::find_normalization_conflicts,
+            duplicate_detector::hash_directory,
+            duplicate_detector::create_manifest,
+            duplicate_detector::verify_manifest,
+            duplicate_detector::find_by_hashes,
+            duplicate_detector::find_hardlink_groups,
+            duplicate_detector::export_dedupe_script,
+            duplicate_detector::confirm_duplicates,
+            duplicate_detector::find_similar_text_files,
+            duplicate_detector::chunk_fingerprint,
 
             file_preview::preview_text_file
             ,
             file_preview::preview_binary_file
             ,
             file_preview::metadata_for_path,
+            file_preview::extract_strings,
+            file_preview::set_preview_limits,
+            file_preview::get_preview_limits,
+            file_preview::preview_code,
+            file_preview::preview_auto,
+            file_preview::analyze_text_file,
+            file_preview::detect_language,
+
+            // archives
+            archive::list_archive,
+            archive::preview_archive_entry,
+            archive::archive_tree,
+            archive::extract_archive,
             
             // vault
             vault_check_exists,
@@ -94,9 +174,63 @@ async fn main() {
             vault_export_file,
             vault_delete_entry,
             vault_generate_recovery_codes,
+            vault_merkle_root,
+            vault_enable_autobackup,
+            vault_disable_autobackup,
+            vault_copy_to_clipboard,
+            vault_export_metadata,
+            vault_ingest,
+            vault_list_backups,
+            vault_restore_backup,
+            vault_set_capacity_warning,
+            vault_check_duplicate,
+            generate_passphrase,
+            vault_export_recovery,
+            vault_import_recovery,
+            calibrate_argon2,
+            vault_set_pinned,
+            vault_entries_by_category,
+            vault_diff,
+            vault_diagnostics,
+            vault_verify_export,
+            vault_audit_page,
+            vault_split,
+            vault_ingest_and_wipe,
+            estimate_password_strength,
+            vault_repair_log,
+            vault_thumbnail,
+            vault_test_recovery,
+            discover_vaults,
+            vault_upgrade_kdf,
+            vault_rotate_recovery_codes,
+            vault_truncate_log,
+            vault_read_log_archive,
+            vault_open_with_recovery_code,
             
             // content scanner
             scan_directory_for_sensitive_files,
+            content_scanner::cancel_scan,
+            content_scanner::pause_scan,
+            content_scanner::resume_scan,
+            content_scanner::clear_scan_cache,
+            content_scanner::scan_files,
+            content_scanner::verify_file_types,
+            content_scanner::estimate_scan,
+            content_scanner::export_redacted_report,
+            content_scanner::detect_deceptive_names,
+            content_scanner::watch_and_scan,
+            content_scanner::stop_watch_scan,
+
+            // disk cleanup
+            disk_cleanup::find_problem_files,
+            disk_cleanup::find_stale_files,
+            disk_cleanup::find_invalid_filenames,
+            disk_cleanup::sanitize_filename,
+            disk_cleanup::categorize_directory,
+            disk_cleanup::find_broken_symlinks,
+            disk_cleanup::find_long_paths,
+            disk_cleanup::find_large_files,
+            disk_cleanup::find_temp_clutter,
         ])
 
         // shared application state
@@ -1,25 +1,62 @@
     use crate::filesystem::volume::{DirectoryChild, FileMeta};
+    use crate::filesystem::{DIRECTORY, FILE};
     use crate::StateSafe;
     use tauri::Emitter;
     use fuzzy_matcher::skim::SkimMatcherV2;
     use fuzzy_matcher::FuzzyMatcher;
-    use std::path::Path;
-    use std::time::{Instant, SystemTime};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant, SystemTime};
     use std::collections::HashMap;
     use tauri::{State, Window};
     use serde::Serialize;
+    use crate::filesystem::walk::WalkOptions;
+    use crate::filesystem::fs_utils::resolve_path_in_state;
+    use std::fs::File;
+    use std::io::{BufWriter, Write};
+    use std::sync::mpsc::{sync_channel, SyncSender};
+    use std::thread::JoinHandle;
 
     const MINIMUM_SCORE: i16 = 100;
 
+    /// Progress is emitted at most this often unless the caller picks another interval
+    const DEFAULT_PROGRESS_INTERVAL_MS: u64 = 100;
+
+    /// Queries shorter than this skip fuzzy matching, since they fuzzy-match nearly everything
+    const DEFAULT_SHORT_QUERY_LEN: usize = 3;
+
+    /// Scores for short queries, which only match as a prefix or substring
+    const SHORT_QUERY_PREFIX_SCORE: i16 = 500;
+    const SHORT_QUERY_SUBSTRING_SCORE: i16 = 200;
+
+    /// Every search update goes out under this one event name
+    const SEARCH_EVENT: &str = "search_event";
+
+    /// Events queued for the emitter thread before the search loop waits
+    const DEFAULT_MAX_PENDING_EVENTS: usize = 256;
+
+    /// Result events sent to the frontend per second, at most
+    const DEFAULT_MAX_RESULTS_PER_SEC: u32 = 2000;
+
     /// Wrapper for sending child + fuzzy score to frontend
     #[derive(Serialize, Clone)]
     pub struct ScoredChild {
+        pub search_id: u64,
         pub child: DirectoryChild,
         pub score: i16,
     }
 
+    /// A directory holding matching files, sent instead of the files with `group_by_directory`
+    #[derive(Serialize, Clone)]
+    pub struct DirectoryMatch {
+        pub search_id: u64,
+        pub path: String,
+        pub match_count: u64,
+        pub best_score: i16,
+    }
+
     #[derive(Serialize, Clone)]
     pub struct SearchProgress {
+        pub search_id: u64,
         pub scanned: u64,
         pub matched: u64,
         pub counts_by_type: HashMap<String, u64>,
@@ -28,6 +65,7 @@
 
     #[derive(Serialize, Clone)]
     pub struct SearchFinished {
+        pub search_id: u64,
         pub elapsed_ms: u64,
         pub scanned: u64,
         pub matched: u64,
@@ -35,6 +73,153 @@
         pub counts_by_extension: HashMap<String, u64>,
     }
 
+    #[derive(Serialize, Clone)]
+    pub struct SearchCancelled {
+        pub search_id: u64,
+        pub scanned: u64,
+        pub matched: u64,
+    }
+
+    /// One update of a running search, tagged so the frontend can match on `type`.
+    /// Every payload carries the `search_id` returned by the command that started it.
+    #[derive(Serialize, Clone)]
+    #[serde(tag = "type", content = "data", rename_all = "snake_case")]
+    pub enum SearchEvent {
+        Result(ScoredChild),
+        DirectoryResult(DirectoryMatch),
+        Progress(SearchProgress),
+        Finished(SearchFinished),
+        Cancelled(SearchCancelled),
+    }
+
+    fn emit_search(window: &Window, event: SearchEvent) {
+        let _ = window.emit(SEARCH_EVENT, event);
+    }
+
+    /// Sends search events from its own thread through a bounded channel. Results are paced
+    /// to `max_results_per_sec`, and once `max_pending` events are waiting the search loop
+    /// blocks, so a query matching everything can't queue events faster than the UI takes them.
+    /// Dropping it waits until every queued event has been sent.
+    /// Both block, so it is only used from the blocking search threads.
+    struct SearchEmitter {
+        sender: Option<SyncSender<SearchEvent>>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl SearchEmitter {
+        fn new(
+            send: impl Fn(SearchEvent) + Send + 'static,
+            max_pending: usize,
+            max_results_per_sec: u32,
+        ) -> Self {
+            let (sender, receiver) = sync_channel::<SearchEvent>(max_pending.max(1));
+            let min_gap = Duration::from_secs(1) / max_results_per_sec.max(1);
+
+            let thread = std::thread::spawn(move || {
+                let mut next_result = Instant::now();
+                for event in receiver {
+                    if matches!(event, SearchEvent::Result(_) | SearchEvent::DirectoryResult(_)) {
+                        let now = Instant::now();
+                        if next_result > now {
+                            std::thread::sleep(next_result - now);
+                        }
+                        next_result = next_result.max(now) + min_gap;
+                    }
+                    send(event);
+                }
+            });
+
+            Self { sender: Some(sender), thread: Some(thread) }
+        }
+
+        /// An emitter sending to `window` under `SEARCH_EVENT`
+        fn for_window(window: Window, max_pending: Option<usize>, max_results_per_sec: Option<u32>) -> Self {
+            Self::new(
+                move |event| emit_search(&window, event),
+                max_pending.unwrap_or(DEFAULT_MAX_PENDING_EVENTS),
+                max_results_per_sec.unwrap_or(DEFAULT_MAX_RESULTS_PER_SEC),
+            )
+        }
+
+        fn emit(&self, event: SearchEvent) {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(event);
+            }
+        }
+    }
+
+    impl Drop for SearchEmitter {
+        fn drop(&mut self) {
+            // Closing the channel lets the thread drain what is queued and exit
+            self.sender.take();
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    /// What to match, shared by the cached and live searches
+    struct SearchParams {
+        query_lower: String,
+        extension: String,
+        accept_files: bool,
+        accept_directories: bool,
+        short_query_len: usize,
+        progress_interval: Duration,
+        group_by_directory: bool,
+    }
+
+    /// Whether `search_id` is still the active search
+    fn is_active_search(state_mux: &StateSafe, search_id: u64) -> bool {
+        let state = state_mux.lock().unwrap();
+        state.active_search_id.load(std::sync::atomic::Ordering::SeqCst) == search_id
+    }
+
+    /// Starts a new search, which cancels any running one, and returns its id
+    fn next_search_id(state_mux: &StateSafe) -> u64 {
+        let state = state_mux.lock().unwrap();
+        state.active_search_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1
+    }
+
+    /// Matching files counted per containing directory
+    type DirectoryGroups = HashMap<String, (u64, i16)>;
+
+    fn add_to_group(groups: &mut DirectoryGroups, file_path: &str, score: i16) {
+        let parent = Path::new(file_path)
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let group = groups.entry(parent).or_insert((0, score));
+        group.0 += 1;
+        group.1 = group.1.max(score);
+    }
+
+    /// Sends one result per directory, most matches first
+    fn emit_directory_groups(emitter: &SearchEmitter, search_id: u64, groups: DirectoryGroups) {
+        let mut groups: Vec<DirectoryMatch> = groups
+            .into_iter()
+            .map(|(path, (match_count, best_score))| DirectoryMatch { search_id, path, match_count, best_score })
+            .collect();
+        groups.sort_by(|a, b| {
+            b.match_count
+                .cmp(&a.match_count)
+                .then_with(|| b.best_score.cmp(&a.best_score))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        for group in groups {
+            emitter.emit(SearchEvent::DirectoryResult(group));
+        }
+    }
+
+    /// Lowercased extension used to tally files, `<no-ext>` when there is none
+    fn extension_key(filename: &str) -> String {
+        Path::new(filename)
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase())
+            .unwrap_or_else(|| String::from("<no-ext>"))
+    }
+
     /// Checks if the filename passes the extension filter, also checks if extension filter is provided.
     fn passed_extension(filename: &str, extension: &String) -> bool {
         if extension.is_empty() {
@@ -45,10 +230,21 @@
 
     /// Gives a filename a fuzzy matcher score
     /// Returns 1000 if there is an exact match for prioritizing
-    fn score_filename(matcher: &SkimMatcherV2, filename: &str, query: &str) -> i16 {
+    /// Queries shorter than `short_query_len` characters only match as a prefix or substring
+    fn score_filename(matcher: &SkimMatcherV2, filename: &str, query: &str, short_query_len: usize) -> i16 {
         let filename_lower = filename.to_lowercase();
         let query_lower = query.to_lowercase();
 
+        if query_lower.chars().count() < short_query_len {
+            return if filename_lower.starts_with(&query_lower) {
+                SHORT_QUERY_PREFIX_SCORE
+            } else if filename_lower.contains(&query_lower) {
+                SHORT_QUERY_SUBSTRING_SCORE
+            } else {
+                0
+            };
+        }
+
         // Exact continuous substring of at least 5 chars
         if query_lower.len() >= 5 && filename_lower.contains(&query_lower) {
             return 1000;
@@ -88,7 +284,7 @@
             .and_then(|stem| stem.to_str())
             .unwrap_or("");
 
-        let score = score_filename(matcher, cleaned_filename, query.as_str());
+        let score = score_filename(matcher, cleaned_filename, query.as_str(), DEFAULT_SHORT_QUERY_LEN);
         if score < MINIMUM_SCORE {
             return;
         }
@@ -107,81 +303,138 @@
         fuzzy_scores.push(score);
     }
 
+    /// Drops roots nested inside another root, so overlapping selections don't
+    /// produce the same match twice. An empty root stands for the whole volume
+    /// and swallows every other root.
+    fn collapse_roots(roots: impl Iterator<Item = String>) -> Vec<PathBuf> {
+        let mut roots: Vec<PathBuf> = roots.map(PathBuf::from).collect();
+        roots.sort();
+        roots.dedup();
+
+        let mut collapsed: Vec<PathBuf> = Vec::new();
+        for root in roots {
+            if !collapsed.iter().any(|kept| root.starts_with(kept)) {
+                collapsed.push(root);
+            }
+        }
+        collapsed
+    }
+
+    /// Searches the cached index under one or more directories in a single pass.
+    /// `search_directory` and `search_directories` may be combined.
+    /// Returns the search id at once; results follow as `search_event`s tagged with it.
     #[tauri::command]
     pub async fn search_directory(
         window: Window,
         state_mux: State<'_, StateSafe>,
         query: String,
-        search_directory: String,
+        search_directory: Option<String>,
+        search_directories: Option<Vec<String>>,
         mount_pnt: String,
         extension: String,
         accept_files: bool,
         accept_directories: bool,
-    ) -> Result<(), ()> {
-        let start = Instant::now();
-        let matcher = SkimMatcherV2::default().smart_case();
-
-        let search_id = {
-            let state = state_mux.lock().unwrap();
-            state.active_search_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1
+        progress_interval_ms: Option<u64>,
+        short_query_length: Option<usize>,
+        group_by_directory: Option<bool>,
+        max_pending_events: Option<usize>,
+        max_results_per_sec: Option<u32>,
+    ) -> Result<u64, ()> {
+        let state = state_mux.inner().clone();
+        let params = SearchParams {
+            query_lower: query.to_lowercase(),
+            extension,
+            accept_files,
+            accept_directories,
+            short_query_len: short_query_length.unwrap_or(DEFAULT_SHORT_QUERY_LEN),
+            progress_interval: Duration::from_millis(progress_interval_ms.unwrap_or(DEFAULT_PROGRESS_INTERVAL_MS)),
+            group_by_directory: group_by_directory.unwrap_or(false),
         };
+        let roots = collapse_roots(search_directory.into_iter().chain(search_directories.unwrap_or_default()));
 
-        let query_lower = query.to_lowercase();
+        // The search runs on in the background; its events carry the id returned here
+        let search_id = next_search_id(&state);
+        tauri::async_runtime::spawn_blocking(move || {
+            let emitter = SearchEmitter::for_window(window, max_pending_events, max_results_per_sec);
+            search_cache(&state, search_id, &params, &roots, &mount_pnt, &emitter);
+        });
+        Ok(search_id)
+    }
+
+    /// The loop behind `search_directory`: scores every cached path under `roots`
+    fn search_cache(
+        state_mux: &StateSafe,
+        search_id: u64,
+        params: &SearchParams,
+        roots: &[PathBuf],
+        mount_pnt: &str,
+        emitter: &SearchEmitter,
+    ) {
+        let start = Instant::now();
+        let mut groups: Option<DirectoryGroups> = params.group_by_directory.then(HashMap::new);
+        let matcher = SkimMatcherV2::default().smart_case();
 
         let system_cache = {
             let state = state_mux.lock().unwrap();
-            state.system_cache.get(&mount_pnt).cloned()
+            state.system_cache.get(mount_pnt).cloned()
         };
 
-        if system_cache.is_none() {
-            return Ok(()); 
-        }
-        let system_cache = system_cache.unwrap();
+        // Nothing indexed for this volume yet; finish at once so listeners don't wait forever
+        let Some(system_cache) = system_cache else {
+            emitter.emit(SearchEvent::Finished(SearchFinished {
+                search_id,
+                elapsed_ms: 0,
+                scanned: 0,
+                matched: 0,
+                counts_by_type: HashMap::new(),
+                counts_by_extension: HashMap::new(),
+            }));
+            return;
+        };
 
         let mut scanned_count: u64 = 0;
         let mut matched_count: u64 = 0;
         let mut counts_by_type: HashMap<String, u64> = HashMap::new();
         let mut counts_by_extension: HashMap<String, u64> = HashMap::new();
 
-        let mut since_last_emit: u64 = 0;
+        let mut last_emit = Instant::now();
 
         for (filename, paths) in system_cache {
             for path in paths {
 
-                let current_id = {
-                    let state = state_mux.lock().unwrap();
-                    state.active_search_id.load(std::sync::atomic::Ordering::SeqCst)
-                };
-                if current_id != search_id {
-                    return Ok(()); 
+                if !is_active_search(state_mux, search_id) {
+                    emitter.emit(SearchEvent::Cancelled(SearchCancelled {
+                        search_id,
+                        scanned: scanned_count,
+                        matched: matched_count,
+                    }));
+                    return;
                 }
 
                 let file_path = &path.file_path;
                 let file_type = &path.file_type;
 
-                if !file_path.starts_with(&search_directory) {
+                if !roots.iter().any(|root| Path::new(file_path).starts_with(root)) {
                     continue;
                 }
 
                 // Update scanned counters / maps
                 scanned_count += 1;
-                since_last_emit += 1;
                 *counts_by_type.entry(file_type.clone()).or_insert(0) += 1;
 
                 // count by extension (if present)
-                let ext = Path::new(&filename)
-                    .extension()
-                    .and_then(|s| s.to_str())
-                    .map(|s| s.to_lowercase())
-                    .unwrap_or_else(|| String::from("<no-ext>"));
-                *counts_by_extension.entry(ext).or_insert(0) += 1;
-
-                let score = score_filename(&matcher, &filename, &query_lower);
+                *counts_by_extension.entry(extension_key(&filename)).or_insert(0) += 1;
+
+                let score = score_filename(&matcher, &filename, &params.query_lower, params.short_query_len);
                 if score < MINIMUM_SCORE {
                     continue;
                 }
 
-                if file_type == "file" && accept_files {
+                let is_file_match = file_type == "file" && params.accept_files;
+                if let (true, Some(groups)) = (is_file_match, groups.as_mut()) {
+                    add_to_group(groups, file_path, score);
+                    matched_count += 1;
+                } else if file_type == "file" && params.accept_files {
                     let meta = FileMeta {
                         name: filename.clone(),
                         path: file_path.clone(),
@@ -191,12 +444,13 @@
                         is_dir: false,
                     };
                     let scored = ScoredChild {
+                        search_id,
                         child: DirectoryChild::File(meta),
                         score,
                     };
-                    let _ = window.emit("search_result", scored);
+                    emitter.emit(SearchEvent::Result(scored));
                     matched_count += 1;
-                } else if file_type == "directory" && accept_directories {
+                } else if file_type == "directory" && params.accept_directories {
                     let meta = FileMeta {
                         name: filename.clone(),
                         path: file_path.clone(),
@@ -206,37 +460,49 @@
                         is_dir: true,
                     };
                     let scored = ScoredChild {
+                        search_id,
                         child: DirectoryChild::Directory(meta),
                         score,
                     };
-                    let _ = window.emit("search_result", scored);
+                    emitter.emit(SearchEvent::Result(scored));
                     matched_count += 1;
                 }
 
                 // Emit progress occasionally to keep frontend updated
-                if since_last_emit >= 500 {
-                    since_last_emit = 0;
+                if last_emit.elapsed() >= params.progress_interval {
+                    last_emit = Instant::now();
                     let progress = SearchProgress {
+                        search_id,
                         scanned: scanned_count,
                         matched: matched_count,
                         counts_by_type: counts_by_type.clone(),
                         counts_by_extension: counts_by_extension.clone(),
                     };
-                    let _ = window.emit("search_progress", progress);
+                    emitter.emit(SearchEvent::Progress(progress));
                 }
             }
         }
 
         // ✅ Only finish if this search wasn't cancelled
-        let current_id = {
-            let state = state_mux.lock().unwrap();
-            state.active_search_id.load(std::sync::atomic::Ordering::SeqCst)
-        };
-        if current_id == search_id {
+        if is_active_search(state_mux, search_id) {
             let elapsed_ms = start.elapsed().as_millis() as u64;
 
-            // emit final progress before finishing
+            // emit final progress before finishing, so small searches still get one update
+            let progress = SearchProgress {
+                search_id,
+                scanned: scanned_count,
+                matched: matched_count,
+                counts_by_type: counts_by_type.clone(),
+                counts_by_extension: counts_by_extension.clone(),
+            };
+            emitter.emit(SearchEvent::Progress(progress));
+
+            if let Some(groups) = groups {
+                emit_directory_groups(emitter, search_id, groups);
+            }
+
             let final_stats = SearchFinished {
+                search_id,
                 elapsed_ms,
                 scanned: scanned_count,
                 matched: matched_count,
@@ -244,8 +510,699 @@
                 counts_by_extension: counts_by_extension.clone(),
             };
 
-            let _ = window.emit("search_finished", final_stats);
+            emitter.emit(SearchEvent::Finished(final_stats));
+        } else {
+            emitter.emit(SearchEvent::Cancelled(SearchCancelled {
+                search_id,
+                scanned: scanned_count,
+                matched: matched_count,
+            }));
+        }
+    }
+
+    /// Searches by walking `search_directory` directly instead of using the volume cache.
+    /// Slower than `search_directory` but works before the volume has been indexed.
+    /// Returns the search id at once, like `search_directory`.
+    #[tauri::command]
+    pub async fn search_live(
+        window: Window,
+        state_mux: State<'_, StateSafe>,
+        query: String,
+        search_directory: String,
+        extension: String,
+        accept_files: bool,
+        accept_directories: bool,
+        progress_interval_ms: Option<u64>,
+        short_query_length: Option<usize>,
+        walk_options: Option<WalkOptions>,
+        group_by_directory: Option<bool>,
+        max_pending_events: Option<usize>,
+        max_results_per_sec: Option<u32>,
+    ) -> Result<u64, ()> {
+        let state = state_mux.inner().clone();
+        let params = SearchParams {
+            query_lower: query.to_lowercase(),
+            extension,
+            accept_files,
+            accept_directories,
+            short_query_len: short_query_length.unwrap_or(DEFAULT_SHORT_QUERY_LEN),
+            progress_interval: Duration::from_millis(progress_interval_ms.unwrap_or(DEFAULT_PROGRESS_INTERVAL_MS)),
+            group_by_directory: group_by_directory.unwrap_or(false),
+        };
+        let options = walk_options.unwrap_or_default();
+
+        let search_id = next_search_id(&state);
+        tauri::async_runtime::spawn_blocking(move || {
+            let emitter = SearchEmitter::for_window(window, max_pending_events, max_results_per_sec);
+            search_tree(&state, search_id, &params, &search_directory, &options, &emitter);
+        });
+        Ok(search_id)
+    }
+
+    /// The loop behind `search_live`: walks `search_directory` and scores each entry
+    fn search_tree(
+        state_mux: &StateSafe,
+        search_id: u64,
+        params: &SearchParams,
+        search_directory: &str,
+        options: &WalkOptions,
+        emitter: &SearchEmitter,
+    ) {
+        let start = Instant::now();
+        let mut groups: Option<DirectoryGroups> = params.group_by_directory.then(HashMap::new);
+        let matcher = SkimMatcherV2::default().smart_case();
+
+        let mut scanned_count: u64 = 0;
+        let mut matched_count: u64 = 0;
+        let mut counts_by_type: HashMap<String, u64> = HashMap::new();
+        let mut counts_by_extension: HashMap<String, u64> = HashMap::new();
+
+        let mut last_emit = Instant::now();
+
+        for entry in options.walk(search_directory) {
+            if !is_active_search(state_mux, search_id) {
+                emitter.emit(SearchEvent::Cancelled(SearchCancelled {
+                    search_id,
+                    scanned: scanned_count,
+                    matched: matched_count,
+                }));
+                return;
+            }
+
+            let filename = entry.file_name().to_string_lossy().to_string();
+            let file_path = entry.path().to_string_lossy().to_string();
+            let is_dir = entry.file_type().is_dir();
+            let file_type = if is_dir { DIRECTORY } else { FILE };
+
+            scanned_count += 1;
+            *counts_by_type.entry(file_type.to_string()).or_insert(0) += 1;
+
+            *counts_by_extension.entry(extension_key(&filename)).or_insert(0) += 1;
+
+            if last_emit.elapsed() >= params.progress_interval {
+                last_emit = Instant::now();
+                let progress = SearchProgress {
+                    search_id,
+                    scanned: scanned_count,
+                    matched: matched_count,
+                    counts_by_type: counts_by_type.clone(),
+                    counts_by_extension: counts_by_extension.clone(),
+                };
+                emitter.emit(SearchEvent::Progress(progress));
+            }
+
+            if (is_dir && !params.accept_directories) || (!is_dir && !params.accept_files) {
+                continue;
+            }
+            if !is_dir && !passed_extension(&filename, &params.extension) {
+                continue;
+            }
+
+            let score = score_filename(&matcher, &filename, &params.query_lower, params.short_query_len);
+            if score < MINIMUM_SCORE {
+                continue;
+            }
+
+            if let (false, Some(groups)) = (is_dir, groups.as_mut()) {
+                add_to_group(groups, &file_path, score);
+                matched_count += 1;
+                continue;
+            }
+
+            let meta = FileMeta {
+                name: filename,
+                path: file_path,
+                size: 0,
+                created: None,
+                modified: None,
+                is_dir,
+            };
+            let child = if is_dir {
+                DirectoryChild::Directory(meta)
+            } else {
+                DirectoryChild::File(meta)
+            };
+            emitter.emit(SearchEvent::Result(ScoredChild { search_id, child, score }));
+            matched_count += 1;
         }
 
+        if is_active_search(state_mux, search_id) {
+            let progress = SearchProgress {
+                search_id,
+                scanned: scanned_count,
+                matched: matched_count,
+                counts_by_type: counts_by_type.clone(),
+                counts_by_extension: counts_by_extension.clone(),
+            };
+            emitter.emit(SearchEvent::Progress(progress));
+
+            if let Some(groups) = groups {
+                emit_directory_groups(emitter, search_id, groups);
+            }
+
+            let final_stats = SearchFinished {
+                search_id,
+                elapsed_ms: start.elapsed().as_millis() as u64,
+                scanned: scanned_count,
+                matched: matched_count,
+                counts_by_type,
+                counts_by_extension,
+            };
+            emitter.emit(SearchEvent::Finished(final_stats));
+        } else {
+            emitter.emit(SearchEvent::Cancelled(SearchCancelled {
+                search_id,
+                scanned: scanned_count,
+                matched: matched_count,
+            }));
+        }
+    }
+
+    #[derive(Serialize, Clone)]
+    pub struct ExtensionStats {
+        pub extension: String,
+        pub count: u64,
+        pub total_bytes: u64,
+        pub avg_bytes: u64,
+    }
+
+    #[derive(Serialize, Clone)]
+    pub struct ExtensionStatsProgress {
+        pub scanned: u64,
+        pub total_bytes: u64,
+    }
+
+    /// Per-extension file counts and sizes for everything under `dir`, largest total first.
+    /// Sends `extension_stats_progress` events while walking.
+    #[tauri::command]
+    pub async fn directory_extension_stats(
+        window: Window,
+        dir: String,
+        walk_options: Option<WalkOptions>,
+        progress_interval_ms: Option<u64>,
+    ) -> Result<Vec<ExtensionStats>, String> {
+        if !Path::new(&dir).is_dir() {
+            return Err("Path is not a directory".to_string());
+        }
+        let options = walk_options.unwrap_or_default();
+        let progress_interval = Duration::from_millis(progress_interval_ms.unwrap_or(DEFAULT_PROGRESS_INTERVAL_MS));
+
+        Ok(extension_stats(&dir, &options, progress_interval, &|progress| {
+            let _ = window.emit("extension_stats_progress", progress);
+        }))
+    }
+
+    /// The walk behind `directory_extension_stats`, reporting progress to `on_progress`
+    fn extension_stats(
+        dir: &str,
+        options: &WalkOptions,
+        progress_interval: Duration,
+        on_progress: &dyn Fn(ExtensionStatsProgress),
+    ) -> Vec<ExtensionStats> {
+        let mut last_emit = Instant::now();
+
+        let mut totals: HashMap<String, (u64, u64)> = HashMap::new();
+        let mut scanned: u64 = 0;
+        let mut scanned_bytes: u64 = 0;
+
+        for entry in options.walk(dir) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            let total = totals
+                .entry(extension_key(&entry.file_name().to_string_lossy()))
+                .or_insert((0, 0));
+            total.0 += 1;
+            total.1 += size;
+
+            scanned += 1;
+            scanned_bytes += size;
+            if last_emit.elapsed() >= progress_interval {
+                last_emit = Instant::now();
+                on_progress(ExtensionStatsProgress { scanned, total_bytes: scanned_bytes });
+            }
+        }
+        on_progress(ExtensionStatsProgress { scanned, total_bytes: scanned_bytes });
+
+        let mut stats: Vec<ExtensionStats> = totals
+            .into_iter()
+            .map(|(extension, (count, total_bytes))| ExtensionStats {
+                extension,
+                count,
+                total_bytes,
+                avg_bytes: total_bytes / count,
+            })
+            .collect();
+        stats.sort_by(|a, b| {
+            b.total_bytes
+                .cmp(&a.total_bytes)
+                .then_with(|| b.count.cmp(&a.count))
+                .then_with(|| a.extension.cmp(&b.extension))
+        });
+        stats
+    }
+
+    /// Stops the running search or export; they check the active search id as they walk
+    #[tauri::command]
+    pub async fn cancel_search(state_mux: State<'_, StateSafe>) -> Result<(), ()> {
+        let state = state_mux.lock().unwrap();
+        state.active_search_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         Ok(())
     }
+
+    #[derive(Serialize, Clone)]
+    pub struct ExportedMatch {
+        pub path: String,
+        pub name: String,
+        pub is_dir: bool,
+        pub score: i16,
+    }
+
+    #[derive(Serialize, Clone)]
+    pub struct SearchExportResult {
+        pub written: u64,
+        pub scanned: u64,
+        /// The export was stopped early; the file holds the matches written until then
+        pub cancelled: bool,
+    }
+
+    /// Quotes a CSV field when it contains a delimiter, quote or line break
+    fn csv_field(value: &str) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// Runs a live search like `search_live` and writes each match to `output_path` as it is
+    /// found, one path per line (`txt`), as CSV with a header row (`csv`) or as a JSON array (`json`).
+    /// Cancelled by `cancel_search` or by starting another search.
+    #[tauri::command]
+    pub async fn search_export(
+        state_mux: State<'_, StateSafe>,
+        query: String,
+        search_directory: String,
+        extension: String,
+        accept_files: bool,
+        accept_directories: bool,
+        short_query_length: Option<usize>,
+        walk_options: Option<WalkOptions>,
+        output_path: String,
+        format: String,
+    ) -> Result<SearchExportResult, String> {
+        let format = format.to_lowercase();
+        if !matches!(format.as_str(), "txt" | "csv" | "json") {
+            return Err(format!("Unsupported export format: {}", format));
+        }
+
+        let output_path = resolve_path_in_state(&output_path, &state_mux).map_err(|e| e.to_string())?;
+        let state = state_mux.inner().clone();
+        let params = SearchParams {
+            query_lower: query.to_lowercase(),
+            extension,
+            accept_files,
+            accept_directories,
+            short_query_len: short_query_length.unwrap_or(DEFAULT_SHORT_QUERY_LEN),
+            // Exports send no progress and write every match on its own
+            progress_interval: Duration::from_millis(DEFAULT_PROGRESS_INTERVAL_MS),
+            group_by_directory: false,
+        };
+        let options = walk_options.unwrap_or_default();
+
+        let search_id = next_search_id(&state);
+        tauri::async_runtime::spawn_blocking(move || {
+            export_tree(&state, search_id, &params, &search_directory, &options, &format, Path::new(&output_path))
+        })
+        .await
+        .map_err(|e| format!("Export failed: {}", e))?
+    }
+
+    /// The loop behind `search_export`: walks `search_directory` and writes each match
+    /// to `output_path` in `format`
+    fn export_tree(
+        state_mux: &StateSafe,
+        search_id: u64,
+        params: &SearchParams,
+        search_directory: &str,
+        options: &WalkOptions,
+        format: &str,
+        output_path: &Path,
+    ) -> Result<SearchExportResult, String> {
+        let matcher = SkimMatcherV2::default().smart_case();
+        let file = File::create(output_path).map_err(|e| format!("Failed to create export file: {}", e))?;
+        let mut out = BufWriter::new(file);
+        let write_err = |e: std::io::Error| format!("Failed to write export file: {}", e);
+
+        match format {
+            "csv" => writeln!(out, "path,name,type,score").map_err(write_err)?,
+            "json" => write!(out, "[").map_err(write_err)?,
+            _ => {}
+        }
+
+        let mut written: u64 = 0;
+        let mut scanned: u64 = 0;
+        let mut cancelled = false;
+
+        for entry in options.walk(search_directory) {
+            if !is_active_search(state_mux, search_id) {
+                cancelled = true;
+                break;
+            }
+            scanned += 1;
+
+            let filename = entry.file_name().to_string_lossy().to_string();
+            let is_dir = entry.file_type().is_dir();
+            if (is_dir && !params.accept_directories) || (!is_dir && !params.accept_files) {
+                continue;
+            }
+            if !is_dir && !passed_extension(&filename, &params.extension) {
+                continue;
+            }
+            let score = score_filename(&matcher, &filename, &params.query_lower, params.short_query_len);
+            if score < MINIMUM_SCORE {
+                continue;
+            }
+
+            let path = entry.path().to_string_lossy().to_string();
+            match format {
+                "txt" => writeln!(out, "{}", path),
+                "csv" => writeln!(
+                    out,
+                    "{},{},{},{}",
+                    csv_field(&path),
+                    csv_field(&filename),
+                    if is_dir { DIRECTORY } else { FILE },
+                    score
+                ),
+                _ => {
+                    let record = ExportedMatch { path, name: filename, is_dir, score };
+                    let json = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+                    write!(out, "{}\n  {}", if written == 0 { "" } else { "," }, json)
+                }
+            }
+            .map_err(write_err)?;
+            written += 1;
+        }
+
+        if format == "json" {
+            writeln!(out, "{}]", if written == 0 { "" } else { "\n" }).map_err(write_err)?;
+        }
+        out.flush().map_err(write_err)?;
+
+        Ok(SearchExportResult { written, scanned, cancelled })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{CachedPath, VolumeCache};
+        use std::fs;
+        use std::sync::{Arc, Mutex};
+
+        fn params(query: &str) -> SearchParams {
+            SearchParams {
+                query_lower: query.to_lowercase(),
+                extension: String::new(),
+                accept_files: true,
+                accept_directories: false,
+                short_query_len: DEFAULT_SHORT_QUERY_LEN,
+                progress_interval: Duration::from_secs(60),
+                group_by_directory: false,
+            }
+        }
+
+        /// Runs `search_tree` over `dir` under `search_id` and returns the events it sent
+        fn run_live(state: &StateSafe, search_id: u64, params: &SearchParams, dir: &Path) -> Vec<serde_json::Value> {
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let sink = events.clone();
+            {
+                let emitter = SearchEmitter::new(
+                    move |event| sink.lock().unwrap().push(serde_json::to_value(event).unwrap()),
+                    4,
+                    1_000_000,
+                );
+                search_tree(state, search_id, params, &dir.to_string_lossy(), &WalkOptions::default(), &emitter);
+            }
+            let events = events.lock().unwrap().clone();
+            events
+        }
+
+        fn events_of<'a>(events: &'a [serde_json::Value], kind: &str) -> Vec<&'a serde_json::Value> {
+            events.iter().filter(|e| e["type"] == kind).map(|e| &e["data"]).collect()
+        }
+
+        #[test]
+        fn live_search_finds_files_in_an_unindexed_directory() {
+            let dir = tempfile::tempdir().unwrap();
+            fs::create_dir(dir.path().join("nested")).unwrap();
+            fs::write(dir.path().join("nested").join("report_final.txt"), "x").unwrap();
+            fs::write(dir.path().join("notes.md"), "x").unwrap();
+            let state: StateSafe = Default::default();
+            let search_id = next_search_id(&state);
+
+            let events = run_live(&state, search_id, &params("report"), dir.path());
+
+            let results = events_of(&events, "result");
+            assert_eq!(results.len(), 1);
+            assert!(results[0]["child"]["File"]["path"].as_str().unwrap().ends_with("report_final.txt"));
+            let finished = events_of(&events, "finished");
+            assert_eq!(finished.len(), 1);
+            assert_eq!(finished[0]["matched"], 1);
+        }
+
+        #[test]
+        fn nested_and_repeated_roots_are_collapsed() {
+            let roots = ["/data/a", "/data/a/b", "/data/c", "/data/a"].map(String::from);
+
+            let collapsed = collapse_roots(roots.into_iter());
+            assert_eq!(collapsed, vec![PathBuf::from("/data/a"), PathBuf::from("/data/c")]);
+        }
+
+        #[test]
+        fn an_empty_root_searches_the_whole_volume() {
+            let roots = ["/data/a", "", "/data/c"].map(String::from);
+
+            let collapsed = collapse_roots(roots.into_iter());
+            assert_eq!(collapsed, vec![PathBuf::new()]);
+            assert!(Path::new("/elsewhere/file.txt").starts_with(&collapsed[0]));
+        }
+
+        #[test]
+        fn sibling_directories_are_searched_in_one_pass() {
+            let cached = |p: &str| CachedPath { file_path: p.to_string(), file_type: "file".to_string() };
+            let mut volume = VolumeCache::new();
+            volume.insert("report_a.txt".to_string(), vec![cached("/data/a/report_a.txt")]);
+            volume.insert("report_b.txt".to_string(), vec![cached("/data/b/report_b.txt")]);
+            volume.insert("report_c.txt".to_string(), vec![cached("/data/c/report_c.txt")]);
+            let state: StateSafe = Default::default();
+            state.lock().unwrap().system_cache.insert("/".to_string(), volume);
+            let search_id = next_search_id(&state);
+            let roots = collapse_roots(["/data/a", "/data/b"].map(String::from).into_iter());
+
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let sink = events.clone();
+            {
+                let emitter = SearchEmitter::new(
+                    move |event| sink.lock().unwrap().push(serde_json::to_value(event).unwrap()),
+                    4,
+                    1_000_000,
+                );
+                search_cache(&state, search_id, &params("report"), &roots, "/", &emitter);
+            }
+            let events = events.lock().unwrap().clone();
+
+            let mut found: Vec<&str> = events_of(&events, "result")
+                .iter()
+                .map(|r| r["child"]["File"]["path"].as_str().unwrap())
+                .collect();
+            found.sort();
+            assert_eq!(found, vec!["/data/a/report_a.txt", "/data/b/report_b.txt"]);
+            assert_eq!(events_of(&events, "finished")[0]["matched"], 2);
+        }
+
+        #[test]
+        fn progress_follows_the_requested_interval() {
+            let dir = tempfile::tempdir().unwrap();
+            for i in 0..5 {
+                fs::write(dir.path().join(format!("file{}.txt", i)), "x").unwrap();
+            }
+            let state: StateSafe = Default::default();
+
+            // Only the final update when the interval is never reached
+            let search_id = next_search_id(&state);
+            let events = run_live(&state, search_id, &params("zzzzz"), dir.path());
+            assert_eq!(events_of(&events, "progress").len(), 1);
+
+            let mut every_entry = params("zzzzz");
+            every_entry.progress_interval = Duration::ZERO;
+            let search_id = next_search_id(&state);
+            let events = run_live(&state, search_id, &every_entry, dir.path());
+            assert!(events_of(&events, "progress").len() > 5);
+        }
+
+        #[test]
+        fn every_event_carries_the_search_id() {
+            let dir = tempfile::tempdir().unwrap();
+            fs::write(dir.path().join("report.txt"), "x").unwrap();
+            let state: StateSafe = Default::default();
+            let search_id = next_search_id(&state);
+
+            let events = run_live(&state, search_id, &params("report"), dir.path());
+            assert!(events.iter().all(|e| e["data"]["search_id"] == search_id));
+            assert_eq!(events.last().unwrap()["type"], "finished");
+        }
+
+        #[test]
+        fn superseded_search_reports_cancelled() {
+            let dir = tempfile::tempdir().unwrap();
+            fs::write(dir.path().join("report.txt"), "x").unwrap();
+            let state: StateSafe = Default::default();
+            let stale_id = next_search_id(&state);
+            next_search_id(&state);
+
+            let events = run_live(&state, stale_id, &params("report"), dir.path());
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0]["type"], "cancelled");
+            assert_eq!(events[0]["data"]["search_id"], stale_id);
+        }
+
+        #[test]
+        fn short_queries_match_only_as_prefix_or_substring() {
+            let matcher = SkimMatcherV2::default().smart_case();
+
+            assert_eq!(score_filename(&matcher, "Abstract", "ab", 3), SHORT_QUERY_PREFIX_SCORE);
+            assert_eq!(score_filename(&matcher, "crab", "ab", 3), SHORT_QUERY_SUBSTRING_SCORE);
+            assert_eq!(score_filename(&matcher, "a_b", "ab", 3), 0);
+        }
+
+        #[test]
+        fn grouped_search_sends_one_result_per_directory() {
+            let dir = tempfile::tempdir().unwrap();
+            for (sub, name) in [("a", "report1.txt"), ("a", "report2.txt"), ("b", "report3.txt")] {
+                fs::create_dir_all(dir.path().join(sub)).unwrap();
+                fs::write(dir.path().join(sub).join(name), "x").unwrap();
+            }
+            let state: StateSafe = Default::default();
+            let mut grouped = params("report");
+            grouped.group_by_directory = true;
+            let search_id = next_search_id(&state);
+
+            let events = run_live(&state, search_id, &grouped, dir.path());
+            assert!(events_of(&events, "result").is_empty());
+            let groups = events_of(&events, "directory_result");
+            assert_eq!(groups.len(), 2);
+            assert!(groups[0]["path"].as_str().unwrap().ends_with("a"));
+            assert_eq!(groups[0]["match_count"], 2);
+            assert_eq!(groups[1]["match_count"], 1);
+        }
+
+        #[test]
+        fn extension_keys_are_lowercased() {
+            assert_eq!(extension_key("Photo.JPG"), "jpg");
+            assert_eq!(extension_key("archive.tar.gz"), "gz");
+            assert_eq!(extension_key("Makefile"), "<no-ext>");
+        }
+
+        #[test]
+        fn extension_stats_count_files_and_bytes_per_extension() {
+            let dir = tempfile::tempdir().unwrap();
+            fs::create_dir(dir.path().join("nested")).unwrap();
+            fs::write(dir.path().join("a.TXT"), [0; 10]).unwrap();
+            fs::write(dir.path().join("nested").join("b.txt"), [0; 20]).unwrap();
+            fs::write(dir.path().join("c.md"), [0; 5]).unwrap();
+            fs::write(dir.path().join("Makefile"), [0; 7]).unwrap();
+            fs::write(dir.path().join("nested").join("README"), [0; 3]).unwrap();
+            let root = dir.path().to_string_lossy().to_string();
+            let last_progress = Mutex::new(None);
+
+            let stats = extension_stats(&root, &WalkOptions::default(), Duration::from_secs(60), &|progress| {
+                *last_progress.lock().unwrap() = Some((progress.scanned, progress.total_bytes));
+            });
+
+            let summary: Vec<(&str, u64, u64, u64)> = stats
+                .iter()
+                .map(|s| (s.extension.as_str(), s.count, s.total_bytes, s.avg_bytes))
+                .collect();
+            assert_eq!(summary, vec![("txt", 2, 30, 15), ("<no-ext>", 2, 10, 5), ("md", 1, 5, 5)]);
+            assert_eq!(last_progress.into_inner().unwrap(), Some((5, 45)));
+        }
+
+        #[test]
+        fn csv_fields_are_quoted_when_needed() {
+            assert_eq!(csv_field("plain.txt"), "plain.txt");
+            assert_eq!(csv_field("a,b.txt"), "\"a,b.txt\"");
+            assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+            assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        }
+
+        #[test]
+        fn exported_csv_reads_back_with_every_match() {
+            let dir = tempfile::tempdir().unwrap();
+            fs::write(dir.path().join("report,final.txt"), "x").unwrap();
+            fs::write(dir.path().join("report.md"), "x").unwrap();
+            fs::write(dir.path().join("notes.txt"), "x").unwrap();
+            let output = dir.path().join("export.csv");
+            let state: StateSafe = Default::default();
+            let root = dir.path().to_string_lossy().to_string();
+
+            let search_id = next_search_id(&state);
+            let result = export_tree(&state, search_id, &params("report"), &root, &WalkOptions::default(), "csv", &output)
+                .unwrap();
+            assert_eq!((result.written, result.cancelled), (2, false));
+
+            let csv = fs::read_to_string(&output).unwrap();
+            let mut lines: Vec<&str> = csv.lines().collect();
+            assert_eq!(lines.remove(0), "path,name,type,score");
+            lines.sort();
+            assert_eq!(lines.len(), 2);
+            let quoted = csv_field(&dir.path().join("report,final.txt").to_string_lossy());
+            assert!(lines[0].starts_with(&format!("{},\"report,final.txt\",{},", quoted, FILE)), "{}", lines[0]);
+            assert!(lines[1].contains(",report.md,"));
+
+            // A superseded export stops before writing anything past the header
+            let stale_id = next_search_id(&state);
+            next_search_id(&state);
+            let result = export_tree(&state, stale_id, &params("report"), &root, &WalkOptions::default(), "csv", &output)
+                .unwrap();
+            assert_eq!((result.written, result.cancelled), (0, true));
+            assert_eq!(fs::read_to_string(&output).unwrap(), "path,name,type,score\n");
+        }
+
+        #[test]
+        fn emitter_delivers_every_event_in_order_before_drop_returns() {
+            let sent = Arc::new(Mutex::new(Vec::new()));
+            let sink = sent.clone();
+            let started = Instant::now();
+            {
+                let emitter = SearchEmitter::new(
+                    move |event| {
+                        if let SearchEvent::Result(scored) = event {
+                            sink.lock().unwrap().push(scored.score);
+                        }
+                    },
+                    1,
+                    200,
+                );
+                for score in 0..10 {
+                    emitter.emit(SearchEvent::Result(ScoredChild {
+                        search_id: 1,
+                        child: DirectoryChild::File(FileMeta {
+                            name: String::new(),
+                            path: String::new(),
+                            size: 0,
+                            created: None,
+                            modified: None,
+                            is_dir: false,
+                        }),
+                        score,
+                    }));
+                }
+            }
+
+            assert_eq!(*sent.lock().unwrap(), (0..10).collect::<Vec<i16>>());
+            // Ten results at 200 per second are spread over at least 45ms
+            assert!(started.elapsed() >= Duration::from_millis(45));
+        }
+    }
@@ -2,7 +2,9 @@
 ///
 /// Provides secure encrypted storage for sensitive files.
 /// - Container format: header | encrypted manifest | encrypted file blobs
+/// - Split storage: header + manifest in `*.meta`, blobs in a sibling `*.blobs` file
 /// - Encryption: XChaCha20-Poly1305 AEAD with Argon2id KDF
+/// - Keys: separate HKDF subkeys for the manifest and the file blobs (format v2+)
 /// - Features: tamper detection, auto-lock, decoy vault support
 ///
 /// WARNING: Strong encryption means data is irrecoverable without keys.
@@ -11,18 +13,140 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use chrono::{DateTime, Utc};
-use zeroize::Zeroize;
-use argon2::Argon2;
+use zeroize::{Zeroize, Zeroizing};
+use argon2::{Algorithm, Argon2, Params, Version};
 use chacha20poly1305::aead::{Aead, KeyInit, Payload};
 use chacha20poly1305::{ChaCha20Poly1305, Nonce};
 use rand::Rng;
 use base64::{engine::general_purpose, Engine as _};
+use sha2::{Digest, Sha256};
+use hkdf::Hkdf;
+use tauri::{Emitter, State, Window};
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::StateSafe;
+use crate::filesystem::fs_utils::resolve_path_in_state;
+use crate::file_preview::mime_from_extension;
+use crate::filesystem::fs_utils::check_free_space;
+use crate::filesystem::walk::WalkOptions;
 
 /// Maximum vault size: 10 GB
-const MAX_VAULT_SIZE: u64 = 10 * 1024 * 1024 * 1024;
+pub(crate) const MAX_VAULT_SIZE: u64 = 10 * 1024 * 1024 * 1024;
+
+/// Percentage of MAX_VAULT_SIZE at which `vault_near_capacity` fires
+static CAPACITY_WARNING_PERCENT: AtomicU64 = AtomicU64::new(90);
+
+/// Unlocked sessions are dropped after 15 minutes without activity
+const SESSION_INACTIVITY_SECS: u64 = 15 * 60;
+
+/// Auto-backup settings for a vault, keyed by vault_id
+#[derive(Clone, Debug)]
+struct AutoBackupConfig {
+    backup_dir: PathBuf,
+    keep_n: usize,
+}
+
+/// Our own writes to a container file, so the watcher can tell them from external ones
+#[derive(Clone, Debug, Default)]
+struct OwnWrite {
+    pending: bool,
+    expected: Option<(SystemTime, u64)>,  // mtime and length after our last write
+}
+
+lazy_static! {
+    static ref AUTOBACKUP: Mutex<HashMap<String, AutoBackupConfig>> = Mutex::new(HashMap::new());
+    static ref OWN_WRITES: Mutex<HashMap<PathBuf, OwnWrite>> = Mutex::new(HashMap::new());
+    static ref VAULT_WATCHERS: Mutex<HashMap<String, RecommendedWatcher>> = Mutex::new(HashMap::new());
+    /// Generated thumbnails by (vault_id, entry_id, max_dimension); dropped when the vault locks
+    static ref THUMBNAILS: Mutex<HashMap<(String, String, u32), CachedThumbnail>> = Mutex::new(HashMap::new());
+}
+
+/// Thumbnails kept in memory before the cache is emptied
+const MAX_CACHED_THUMBNAILS: usize = 500;
+
+/// Image entries larger than this are not decoded for a thumbnail
+const MAX_THUMBNAIL_SOURCE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Image formats `vault_thumbnail` can decode; keep in step with the `image` crate features
+pub(crate) const THUMBNAIL_FORMATS: &[&str] = &["jpeg", "png", "gif", "webp", "bmp", "tiff"];
+
+/// Longest plaintext header `discover_vaults` looks through for the boundary
+const MAX_HEADER_BYTES: u64 = 64 * 1024;
+
+/// Edge length used when the caller gives no `max_dimension`
+const DEFAULT_THUMBNAIL_DIMENSION: u32 = 256;
+
+/// Separates the plaintext header from the encrypted manifest
+const VAULT_BOUNDARY: &[u8] = b"\n---VAULT_BOUNDARY---\n";
+
+/// Separates the manifest from streamed blobs appended by `ingest_file`
+const BLOB_BOUNDARY: &[u8] = b"\n---VAULT_BLOBS---\n";
+
+/// The markers as they look after a text editor converted the container to CRLF line endings
+const VAULT_BOUNDARY_CRLF: &[u8] = b"\r\n---VAULT_BOUNDARY---\r\n";
+const BLOB_BOUNDARY_CRLF: &[u8] = b"\r\n---VAULT_BLOBS---\r\n";
+
+/// Plaintext bytes per chunk for streamed blobs
+const STREAM_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Nonce + Poly1305 tag added to every encrypted chunk
+const CHUNK_OVERHEAD: u64 = 12 + 16;
+
+/// Manifests are padded to a multiple of this, so most saves fit the space the previous
+/// one took and can be written in place instead of moving every blob behind it
+const MANIFEST_SLOT: usize = 64 * 1024;
+
+/// BIP-39 English wordlist: 2048 short, distinct words (11 bits of entropy each)
+const PASSPHRASE_WORDS: &str = include_str!("wordlists/bip39_english.txt");
+
+/// Container format version written for new vaults
+pub(crate) const VAULT_FORMAT_VERSION: u32 = 3;
+
+/// First format version whose manifest and blobs use separate subkeys
+const KEY_SEPARATION_VERSION: u32 = 2;
+
+/// First format version whose key derivation honours `argon2_params`;
+/// older headers carry the string but were derived with the Argon2 defaults
+const KDF_PARAMS_VERSION: u32 = 3;
+
+/// Argon2id parameters used when no calibration is requested
+const DEFAULT_ARGON2_PARAMS: &str = "m=65536,t=4,p=4";
+
+/// Calibration bounds: memory in KiB and iteration count
+const CALIBRATION_MIN_MEMORY_KIB: u32 = 19 * 1024;
+const CALIBRATION_MAX_ITERATIONS: u32 = 10;
+
+/// HKDF info strings; distinct so the manifest and blob keys can never coincide
+const MANIFEST_KEY_INFO: &[u8] = b"neura-vault/manifest/v1";
+const BLOB_KEY_INFO: &[u8] = b"neura-vault/blob/v1";
+
+/// Standalone recovery codes issued at a time, and words in each. A single code unlocks
+/// the vault, so each needs far more words than the codes in a recovery set.
+const STANDALONE_RECOVERY_CODES: usize = 8;
+const STANDALONE_CODE_WORDS: usize = 8;
+
+/// Shortest PIN accepted for a recovery-code file. The file may sit on a USB stick
+/// where it can be attacked offline; every guess then costs a full Argon2id derivation
+/// under the vault's params, which is what keeps a short PIN workable.
+const MIN_RECOVERY_PIN_LEN: usize = 6;
+
+/// Largest entry that may be copied to the clipboard
+const MAX_CLIPBOARD_ENTRY: u64 = 64 * 1024;
+
+/// Format version written into log archives
+const LOG_ARCHIVE_VERSION: u32 = 1;
+
+/// Audit log entries per page when the caller gives no limit
+const DEFAULT_AUDIT_PAGE_SIZE: usize = 50;
+
+/// Audit log timestamps further ahead of the clock than this are flagged as future-dated
+const LOG_FUTURE_TOLERANCE_SECS: i64 = 300;
 
 /// Vault container header (plaintext metadata)
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -32,6 +156,39 @@ pub struct VaultHeader {
     pub salt: String,
     pub argon2_params: String,
     pub vault_id: String,
+    #[serde(default)]
+    pub split_storage: bool,  // blobs live in a sibling `.blobs` file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery: Option<RecoveryWrap>,  // cipher key wrapped under the recovery codes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_code_wraps: Option<RecoveryCodeWraps>,  // cipher key wrapped under each standalone code
+}
+
+/// A vault found by `discover_vaults`, described from its plaintext header only
+#[derive(Serialize, Clone, Debug)]
+pub struct DiscoveredVault {
+    pub path: String,
+    pub vault_id: String,
+    pub version: u32,
+    pub created_at: String,
+    pub split_storage: bool,
+    pub has_recovery: bool,
+    pub size: u64,
+}
+
+/// Cipher key encrypted under a key derived from the vault's recovery codes
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecoveryWrap {
+    pub salt: String,
+    pub wrapped_key: String,  // base64 of nonce || ciphertext
+}
+
+/// Cipher key encrypted once under each standalone recovery code, so any one code unlocks
+/// the vault. The codes share a salt, so unlocking costs a single Argon2 derivation.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecoveryCodeWraps {
+    pub salt: String,
+    pub wrapped_keys: Vec<String>,  // base64 of nonce || ciphertext, one per code
 }
 
 /// Vault entry metadata (encrypted)
@@ -47,6 +204,124 @@ pub struct VaultEntry {
     pub tags: Vec<String>,
     #[serde(default)]
     pub encrypted_data: String,  // Base64-encoded encrypted file data
+    #[serde(default)]
+    pub blob: Option<StreamedBlob>,  // Set instead of encrypted_data for streamed entries
+    #[serde(default)]
+    pub content_hash: Option<String>,  // SHA-256 of the plaintext, when known
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub sort_order: Option<u32>,  // position among pinned/unpinned entries; unset sorts last
+}
+
+/// Listing view of an entry, without the ciphertext
+#[derive(Serialize, Clone, Debug)]
+pub struct VaultEntrySummary {
+    pub id: String,
+    pub filename: String,
+    pub file_size: u64,
+    pub mime_type: Option<String>,
+    pub imported_at: String,
+    pub tags: Vec<String>,
+    pub pinned: bool,
+}
+
+/// Downscaled JPEG of an image entry, base64-encoded
+#[derive(Serialize, Clone, Debug)]
+pub struct VaultThumbnail {
+    pub data: String,
+    pub mime: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A cached thumbnail and the entry state it was made from
+struct CachedThumbnail {
+    fingerprint: String,
+    thumbnail: VaultThumbnail,
+}
+
+/// Result of comparing an exported file with the entry it came from
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportVerification {
+    pub matches: bool,
+    pub entry_size: u64,
+    pub exported_size: u64,
+    pub entry_hash: String,
+    pub exported_hash: String,
+}
+
+/// On-disk cost of a vault compared to the data it holds
+#[derive(Serialize, Clone, Debug)]
+pub struct VaultDiagnostics {
+    pub split_storage: bool,
+    pub container_bytes: u64,       // meta/container file plus any `.blobs` file
+    pub logical_bytes: u64,         // sum of the entries' plaintext sizes
+    pub manifest_bytes: u64,        // encrypted manifest as stored, inline payloads included
+    pub metadata_bytes: u64,        // manifest JSON without the inline payloads
+    pub inline_payload_bytes: u64,  // base64 ciphertext embedded in the manifest
+    pub streamed_bytes: u64,        // ciphertext stored outside the manifest
+    pub inline_entries: usize,
+    pub streamed_entries: usize,
+    pub overhead_ratio: f64,        // container_bytes / logical_bytes - 1
+}
+
+/// An entry present in both vaults, with the metadata fields that differ
+#[derive(Serialize, Clone, Debug)]
+pub struct VaultEntryPair {
+    pub a: VaultEntrySummary,
+    pub b: VaultEntrySummary,
+    pub differences: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct VaultDiff {
+    pub only_in_a: Vec<VaultEntrySummary>,
+    pub only_in_b: Vec<VaultEntrySummary>,
+    pub in_both: Vec<VaultEntryPair>,
+}
+
+impl From<&VaultEntry> for VaultEntrySummary {
+    fn from(entry: &VaultEntry) -> Self {
+        Self {
+            id: entry.id.clone(),
+            filename: entry.filename.clone(),
+            file_size: entry.file_size,
+            mime_type: entry.mime_type.clone(),
+            imported_at: entry.imported_at.clone(),
+            tags: entry.tags.clone(),
+            pinned: entry.pinned,
+        }
+    }
+}
+
+/// Location of a streamed entry's chunks inside the container's blob section
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StreamedBlob {
+    pub offset: u64,      // relative to the start of the blob section
+    pub length: u64,      // encrypted bytes, including per-chunk nonces and tags
+    pub chunk_size: u64,  // plaintext bytes per chunk
+}
+
+impl StreamedBlob {
+    fn chunk_count(file_size: u64, chunk_size: u64) -> u64 {
+        file_size.div_ceil(chunk_size).max(1)
+    }
+
+    fn encrypted_len(file_size: u64, chunk_size: u64) -> u64 {
+        file_size + Self::chunk_count(file_size, chunk_size) * CHUNK_OVERHEAD
+    }
+}
+
+/// Parsed container file; streamed blob bytes are never loaded
+struct ContainerSections {
+    header: VaultHeader,
+    header_and_boundary: Vec<u8>,
+    encrypted_manifest: Vec<u8>,
+    manifest_start: u64,      // absolute file offset of the encrypted manifest
+    blob_start: Option<u64>,  // absolute file offset of the first blob byte
+    /// Only the CRLF form of the boundary was found, so the manifest bytes may be mangled too
+    crlf_mangled: bool,
 }
 
 /// Vault manifest (encrypted)
@@ -55,6 +330,10 @@ pub struct VaultManifest {
     pub entries: HashMap<String, VaultEntry>,
     pub last_accessed: String,
     pub access_log: Vec<AuditLog>,
+    /// Hex key that log archives are encrypted under. It lives in the manifest rather than
+    /// being derived, so archives stay readable after `upgrade_kdf` changes the vault key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_key: Option<String>,
 }
 
 /// Tamper detection audit log entry
@@ -66,15 +345,158 @@ pub struct AuditLog {
     pub status: String,
 }
 
+/// One page of the audit log, newest first, with totals over the whole log
+#[derive(Serialize, Clone, Debug)]
+pub struct AuditPage {
+    pub entries: Vec<AuditLog>,
+    pub total: usize,           // entries matching the filter
+    pub counts_by_action: HashMap<String, usize>,
+}
+
+/// An audit log entry flagged by `repair_log`; `index` is its position before any reordering
+#[derive(Serialize, Clone, Debug)]
+pub struct LogAnomaly {
+    pub index: usize,
+    pub timestamp: String,
+    pub action: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LogTruncation {
+    /// Entries written to the archive, i.e. the whole log before trimming
+    pub archived: usize,
+    /// Entries left in the manifest, including the `log_truncated` record
+    pub kept: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LogRepairReport {
+    pub total: usize,
+    /// Entries dated earlier than one logged before them
+    pub out_of_order: Vec<LogAnomaly>,
+    pub future_dated: Vec<LogAnomaly>,
+    pub unparseable: Vec<LogAnomaly>,
+    /// The log was re-sorted and saved
+    pub reordered: bool,
+}
+
+/// Merkle digest over the vault's encrypted entries
+#[derive(Serialize, Clone, Debug)]
+pub struct VaultMerkleRoot {
+    pub root: String,
+    pub leaves: HashMap<String, String>,
+}
+
+/// Payload of the `vault_near_capacity` event
+#[derive(Serialize, Clone, Debug)]
+pub struct VaultCapacityWarning {
+    pub vault_id: String,
+    pub used_bytes: u64,
+    pub max_bytes: u64,
+    pub percent_used: f64,
+}
+
+/// Generated passphrase with its estimated strength
+#[derive(Serialize, Clone, Debug)]
+pub struct GeneratedPassphrase {
+    pub passphrase: String,
+    pub entropy_bits: f64,
+}
+
+/// Payload of the `vault_external_change` event
+#[derive(Serialize, Clone, Debug)]
+pub struct VaultExternalChange {
+    pub vault_id: String,
+    pub path: String,
+    pub kind: String,  // "modified" or "deleted"
+}
+
+/// zxcvbn estimate for a candidate vault password
+#[derive(Serialize, Clone, Debug)]
+pub struct PasswordStrength {
+    pub score: u8,              // 0 (guessable) to 4 (very strong)
+    pub crack_time: String,     // offline attack against a slow hash, e.g. "3 centuries"
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+/// A source file and the entry it became
+#[derive(Serialize, Clone, Debug)]
+pub struct IngestedFile {
+    pub source: String,
+    pub entry_id: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PathFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// Outcome of `vault_ingest_and_wipe`; `wiped` is false whenever any import failed
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct IngestWipeSummary {
+    pub imported: Vec<IngestedFile>,
+    pub import_failures: Vec<PathFailure>,
+    pub wiped: bool,
+    pub wiped_files: usize,
+    pub wipe_failures: Vec<PathFailure>,
+    pub free_space_wiped_bytes: Option<u64>,
+    pub free_space_error: Option<String>,
+}
+
+/// A container copy found in a backup directory
+#[derive(Serialize, Clone, Debug)]
+pub struct VaultBackupInfo {
+    pub path: String,
+    pub file_name: String,
+    pub size: u64,
+    pub vault_id: String,
+    pub modified_at: String,
+}
+
+/// Argon2id parameters picked by `calibrate_argon2`
+#[derive(Serialize, Clone, Debug)]
+pub struct Argon2Calibration {
+    pub params: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    pub measured_ms: u64,
+}
+
+/// Recovery codes encrypted under a PIN, written as JSON
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct RecoveryFile {
+    version: u32,
+    salt: String,
+    argon2_params: String,  // the vault's calibrated params, reused for the PIN
+    data: String,  // base64 of nonce || ciphertext
+}
+
+/// Audit log entries encrypted under the vault's archive key
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct LogArchiveFile {
+    version: u32,
+    vault_id: String,
+    created_at: String,
+    entries: usize,
+    data: String,  // base64 of nonce || ciphertext
+}
+
 /// In-memory vault session (unlocked)
 #[derive(Clone)]
 pub struct VaultSession {
     pub vault_id: String,
     pub vault_path: PathBuf,
-    pub cipher_key: Vec<u8>,
+    /// Cleared when the session is locked and wherever a copy is dropped
+    pub manifest_key: Zeroizing<Vec<u8>>,
+    pub blob_key: Zeroizing<Vec<u8>>,
     pub manifest: VaultManifest,
     pub locked: bool,
     pub last_accessed: DateTime<Utc>,
+    /// A long operation is working on a copy of this session (see `with_session_detached`)
+    pub busy: bool,
 }
 
 impl VaultSession {
@@ -98,15 +520,23 @@ pub struct Vault;
 impl Vault {
     /// Create a new vault container
     ///
+    /// With `split_storage`, `vault_path` holds only the header and manifest and the
+    /// blobs go to a sibling `.blobs` file, so manifest saves never rewrite them.
+    ///
     /// Returns: (vault_id, recovery_codes)
     pub fn create_vault(
         vault_path: &Path,
         password: &str,
         _vault_name: Option<String>,
+        split_storage: bool,
+        argon2_params: Option<String>,
     ) -> Result<(String, Vec<String>), String> {
         if vault_path.exists() {
             return Err("Vault already exists at this path".to_string());
         }
+        if split_storage && Self::blobs_path(vault_path).exists() {
+            return Err("A blob file already exists next to this vault".to_string());
+        }
 
         // Generate vault ID and salt
         let vault_id = uuid::Uuid::new_v4().to_string();
@@ -115,15 +545,19 @@ impl Vault {
         let salt = hex::encode(&salt_bytes);
 
         // Argon2id parameters (adjust for your hardware)
-        let argon2_params = "m=65536,t=4,p=4".to_string();
+        let argon2_params = argon2_params.unwrap_or_else(|| DEFAULT_ARGON2_PARAMS.to_string());
+        Self::argon2_for(&argon2_params)?;
 
         // Create header
-        let header = VaultHeader {
-            version: 1,
+        let mut header = VaultHeader {
+            version: VAULT_FORMAT_VERSION,
             created_at: Utc::now().to_rfc3339(),
             salt,
             argon2_params,
             vault_id: vault_id.clone(),
+            split_storage,
+            recovery: None,
+            recovery_code_wraps: None,
         };
 
         // Create empty manifest
@@ -136,34 +570,285 @@ impl Vault {
                 entry_id: None,
                 status: "success".to_string(),
             }],
+            archive_key: None,
         };
 
-        // Derive cipher key from password
-        let cipher_key = Self::derive_key(password, &header.salt, &header.argon2_params)?;
+        // Derive cipher key from password, then the manifest subkey from it
+        let master_key = Self::derive_key(password, &header.salt, Self::kdf_params(&header))?;
+        let (manifest_key, _) = Self::derive_subkeys(&master_key, &header)?;
+
+        // Recovery codes wrap the same cipher key, so they can be checked without the password
+        let recovery_codes = Self::generate_recovery_codes();
+        header.recovery = Some(Self::wrap_for_recovery(&master_key, &recovery_codes, &header)?);
+
+        Self::write_fresh_container(vault_path, &header, &manifest, &manifest_key)?;
+
+        Ok((vault_id, recovery_codes))
+    }
 
+    /// Write a container holding only `header` and `manifest` (plus an empty blob
+    /// file for split storage)
+    fn write_fresh_container(
+        vault_path: &Path,
+        header: &VaultHeader,
+        manifest: &VaultManifest,
+        manifest_key: &[u8],
+    ) -> Result<(), String> {
         // Encrypt manifest
-        let encrypted_manifest = Self::encrypt_data(&manifest, &cipher_key)?;
+        let encrypted_manifest = Self::encrypt_manifest(manifest, manifest_key, 0)?;
 
         // Write vault file
         let mut file = File::create(vault_path)
             .map_err(|e| format!("Failed to create vault file: {}", e))?;
 
         // Write header (plaintext)
-        let header_json = serde_json::to_string(&header)
+        let header_json = serde_json::to_string(header)
             .map_err(|e| format!("Failed to serialize header: {}", e))?;
         file.write_all(header_json.as_bytes())
             .map_err(|e| format!("Failed to write header: {}", e))?;
-        file.write_all(b"\n---VAULT_BOUNDARY---\n")
+        file.write_all(VAULT_BOUNDARY)
             .map_err(|e| format!("Failed to write boundary: {}", e))?;
 
         // Write encrypted manifest
         file.write_all(&encrypted_manifest)
             .map_err(|e| format!("Failed to write manifest: {}", e))?;
+        file.sync_all()
+            .map_err(|e| format!("Failed to flush vault file: {}", e))?;
+
+        if header.split_storage {
+            File::create(Self::blobs_path(vault_path))
+                .map_err(|e| format!("Failed to create blob file: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Re-encrypt the whole vault under a key derived from `password` with `new_params`
+    /// and a fresh salt. Everything is written to a temporary container first and only
+    /// swapped in once complete, so a failure leaves the vault as it was.
+    ///
+    /// The recovery codes wrap the old key and can't be unwrapped with the password, so
+    /// they only keep working if the vault's current `recovery_codes` are given to re-bind
+    /// to the new key. Without them the upgraded vault has no recovery codes until
+    /// `rotate_recovery_codes` issues new ones. Standalone codes are always dropped.
+    pub fn upgrade_kdf(
+        session: &mut VaultSession,
+        password: &str,
+        new_params: &str,
+        recovery_codes: Option<&[String]>,
+    ) -> Result<(), String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+        Self::argon2_for(new_params)?;
+
+        let old_header = Self::read_container(&session.vault_path)?.header;
+        let old_key = Self::derive_key(password, &old_header.salt, Self::kdf_params(&old_header))?;
+        let (old_manifest_key, _) = Self::derive_subkeys(&old_key, &old_header)?;
+        if old_manifest_key != session.manifest_key {
+            return Err("Incorrect password".to_string());
+        }
+
+        if let Some(codes) = recovery_codes {
+            if !Self::test_recovery(&session.vault_path, codes)? {
+                return Err("Recovery codes do not match this vault".to_string());
+            }
+        }
+
+        let salt_bytes: [u8; 16] = rand::thread_rng().gen();
+        let mut header = VaultHeader {
+            version: VAULT_FORMAT_VERSION,
+            created_at: old_header.created_at.clone(),
+            salt: hex::encode(salt_bytes),
+            argon2_params: new_params.to_string(),
+            vault_id: old_header.vault_id.clone(),
+            split_storage: old_header.split_storage,
+            recovery: None,
+            recovery_code_wraps: None,
+        };
+        let master_key = Self::derive_key(password, &header.salt, Self::kdf_params(&header))?;
+        let (manifest_key, blob_key) = Self::derive_subkeys(&master_key, &header)?;
+        header.recovery = match recovery_codes {
+            Some(codes) => Some(Self::wrap_for_recovery(&master_key, codes, &header)?),
+            None => None,
+        };
+
+        let temp_path = session.vault_path.with_extension("kdf.tmp");
+        let temp_blobs = Self::blobs_path(&temp_path);
+        let remove_temp = || {
+            let _ = std::fs::remove_file(&temp_path);
+            let _ = std::fs::remove_file(&temp_blobs);
+        };
+        remove_temp();
+
+        let empty = VaultManifest {
+            entries: HashMap::new(),
+            last_accessed: Utc::now().to_rfc3339(),
+            access_log: Vec::new(),
+            archive_key: None,
+        };
+        let staged = Self::write_fresh_container(&temp_path, &header, &empty, &manifest_key)
+            .and_then(|_| {
+                // The keys are already derived, so the staged container needn't be reopened
+                let mut target = VaultSession {
+                    vault_id: header.vault_id.clone(),
+                    vault_path: temp_path.clone(),
+                    manifest_key,
+                    blob_key,
+                    manifest: empty.clone(),
+                    locked: false,
+                    last_accessed: Utc::now(),
+                    busy: false,
+                };
+                let entry_ids: Vec<String> = session.manifest.entries.keys().cloned().collect();
+                Self::copy_entries(session, &mut target, &entry_ids)?;
+                // Keep the original history rather than one transfer_in per entry
+                target.manifest.access_log = session.manifest.access_log.clone();
+                target.manifest.archive_key = session.manifest.archive_key.clone();
+                target.manifest.access_log.push(AuditLog {
+                    timestamp: Utc::now().to_rfc3339(),
+                    action: "kdf_upgraded".to_string(),
+                    entry_id: None,
+                    status: "success".to_string(),
+                });
+                Self::save_manifest(&target)?;
+                Ok(target)
+            });
+        let mut target = match staged {
+            Ok(target) => target,
+            Err(e) => {
+                remove_temp();
+                return Err(e);
+            }
+        };
+
+        Self::auto_backup(session)?;
+        let paths = [session.vault_path.clone(), Self::blobs_path(&session.vault_path)];
+        Self::mark_own_write(&paths, true);
+        let swapped = Self::swap_in_container(&temp_path, &session.vault_path, header.split_storage);
+        Self::mark_own_write(&paths, false);
+        if let Err(e) = swapped {
+            remove_temp();
+            return Err(e);
+        }
+
+        session.manifest_key = std::mem::take(&mut target.manifest_key);
+        session.blob_key = std::mem::take(&mut target.blob_key);
+        session.manifest = std::mem::replace(&mut target.manifest, empty);
+        session.touch();
+        Ok(())
+    }
+
+    /// Issues new recovery codes after checking `password` and binds them to the vault's
+    /// key in place of the old ones, which stop working. Returns the new codes.
+    pub fn rotate_recovery_codes(session: &mut VaultSession, password: &str) -> Result<Vec<String>, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+
+        let mut sections = Self::read_container(&session.vault_path)?;
+        let master_key = Self::derive_key(password, &sections.header.salt, Self::kdf_params(&sections.header))?;
+        let (manifest_key, _) = Self::derive_subkeys(&master_key, &sections.header)?;
+        if manifest_key != session.manifest_key {
+            return Err("Incorrect password".to_string());
+        }
 
-        // Generate recovery codes (12-word phrases; simplified here)
         let recovery_codes = Self::generate_recovery_codes();
+        sections.header.recovery = Some(Self::wrap_for_recovery(&master_key, &recovery_codes, &sections.header)?);
+        Self::rewrite_header(session, &mut sections, "recovery_codes_rotated")?;
+        Ok(recovery_codes)
+    }
 
-        Ok((vault_id, recovery_codes))
+    /// Issues standalone recovery codes after checking `password`. Any one of them unlocks
+    /// the vault through `open_with_recovery_code`. They replace earlier standalone codes and
+    /// leave the recovery code set alone. Returns the new codes.
+    pub fn issue_recovery_codes(session: &mut VaultSession, password: &str) -> Result<Vec<String>, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+
+        let mut sections = Self::read_container(&session.vault_path)?;
+        let master_key = Self::derive_key(password, &sections.header.salt, Self::kdf_params(&sections.header))?;
+        let (manifest_key, _) = Self::derive_subkeys(&master_key, &sections.header)?;
+        if manifest_key != session.manifest_key {
+            return Err("Incorrect password".to_string());
+        }
+
+        let codes = Self::random_codes(STANDALONE_RECOVERY_CODES, STANDALONE_CODE_WORDS);
+        let salt = hex::encode(rand::thread_rng().gen::<[u8; 16]>());
+        let wrapped_keys = codes
+            .iter()
+            .map(|code| {
+                let secret = Self::recovery_secret(std::slice::from_ref(code));
+                let key = Self::derive_key(&secret, &salt, Self::kdf_params(&sections.header))?;
+                let nonce: [u8; 12] = rand::thread_rng().gen();
+                let wrapped = Self::encrypt_bytes_with_nonce(&master_key, &key, &nonce)?;
+                Ok(general_purpose::STANDARD.encode(wrapped))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        sections.header.recovery_code_wraps = Some(RecoveryCodeWraps { salt, wrapped_keys });
+        Self::rewrite_header(session, &mut sections, "recovery_codes_issued")?;
+        Ok(codes)
+    }
+
+    /// Writes the container back with `sections.header` in place of the stored header and
+    /// logs `action`
+    fn rewrite_header(session: &mut VaultSession, sections: &mut ContainerSections, action: &str) -> Result<(), String> {
+        let header_json = serde_json::to_vec(&sections.header)
+            .map_err(|e| format!("Failed to serialize header: {}", e))?;
+        sections.header_and_boundary = [header_json.as_slice(), VAULT_BOUNDARY].concat();
+
+        Self::auto_backup(session)?;
+        let previous_manifest = session.manifest.clone();
+        session.manifest.access_log.push(AuditLog {
+            timestamp: Utc::now().to_rfc3339(),
+            action: action.to_string(),
+            entry_id: None,
+            status: "success".to_string(),
+        });
+
+        let paths = [session.vault_path.clone(), Self::blobs_path(&session.vault_path)];
+        Self::mark_own_write(&paths, true);
+        let written = if sections.header.split_storage {
+            Self::write_split(session, sections, |_| Ok(()))
+        } else {
+            Self::rewrite_container(session, sections, |_| Ok(()))
+        };
+        Self::mark_own_write(&paths, false);
+        if let Err(e) = written {
+            session.manifest = previous_manifest;
+            return Err(e);
+        }
+
+        session.touch();
+        Ok(())
+    }
+
+    /// Move a staged container over `vault_path`. With split storage the old blob file is
+    /// set aside first and put back if any rename fails, so the pair never mismatches.
+    fn swap_in_container(staged: &Path, vault_path: &Path, split_storage: bool) -> Result<(), String> {
+        if !split_storage {
+            return std::fs::rename(staged, vault_path)
+                .map_err(|e| format!("Failed to replace vault file: {}", e));
+        }
+
+        let blobs = Self::blobs_path(vault_path);
+        let set_aside = blobs.with_extension("blobs.old");
+        std::fs::rename(&blobs, &set_aside)
+            .map_err(|e| format!("Failed to replace blob file: {}", e))?;
+        let swapped = std::fs::rename(Self::blobs_path(staged), &blobs)
+            .map_err(|e| format!("Failed to replace blob file: {}", e))
+            .and_then(|_| {
+                std::fs::rename(staged, vault_path).map_err(|e| {
+                    let _ = std::fs::rename(&blobs, Self::blobs_path(staged));
+                    format!("Failed to replace vault file: {}", e)
+                })
+            });
+        if swapped.is_err() {
+            let _ = std::fs::rename(&set_aside, &blobs);
+            return swapped;
+        }
+        let _ = std::fs::remove_file(&set_aside);
+        Ok(())
     }
 
     /// Open and unlock a vault session
@@ -172,97 +857,425 @@ impl Vault {
             return Err("Vault file not found".to_string());
         }
 
-        // Read vault file as binary
-        let mut file = File::open(vault_path)
-            .map_err(|e| format!("Failed to open vault: {}", e))?;
-        let mut contents = Vec::new();
-        file.read_to_end(&mut contents)
-            .map_err(|e| format!("Failed to read vault: {}", e))?;
-
-        // Find boundary marker in binary data
-        let boundary = b"\n---VAULT_BOUNDARY---\n";
-        let boundary_pos = contents
-            .windows(boundary.len())
-            .position(|w| w == boundary)
-            .ok_or("Invalid vault format: boundary not found")?;
+        let sections = Self::read_container(vault_path)?;
+        let master_key = Self::derive_key(password, &sections.header.salt, Self::kdf_params(&sections.header))?;
+        Self::unlock_sections(vault_path, sections, &master_key)
+    }
 
-        // Extract header (before boundary)
-        let header_bytes = &contents[..boundary_pos];
-        let header_str = String::from_utf8(header_bytes.to_vec())
-            .map_err(|e| format!("Invalid header encoding: {}", e))?;
+    /// Open and unlock a vault with one of its standalone recovery codes, e.g. after the
+    /// password is lost
+    pub fn open_with_recovery_code(vault_path: &Path, code: &str) -> Result<VaultSession, String> {
+        if !vault_path.exists() {
+            return Err("Vault file not found".to_string());
+        }
 
-        let header: VaultHeader = serde_json::from_str(&header_str)
-            .map_err(|e| format!("Failed to parse header: {}", e))?;
+        let sections = Self::read_container(vault_path)?;
+        let Some(wraps) = &sections.header.recovery_code_wraps else {
+            return Err("This vault has no standalone recovery codes".to_string());
+        };
+        let secret = Self::recovery_secret(&[code.to_string()]);
+        let key = Self::derive_key(&secret, &wraps.salt, Self::kdf_params(&sections.header))?;
+        let master_key = wraps
+            .wrapped_keys
+            .iter()
+            .filter_map(|wrapped| general_purpose::STANDARD.decode(wrapped).ok())
+            .find_map(|wrapped| Self::decrypt_bytes(&wrapped, &key).ok())
+            .map(Zeroizing::new)
+            .ok_or("Recovery code does not match this vault")?;
+        Self::unlock_sections(vault_path, sections, &master_key)
+    }
 
-        // Extract encrypted manifest (after boundary, rest is binary)
-        let manifest_start = boundary_pos + boundary.len();
-        let encrypted_manifest = &contents[manifest_start..];
+    /// Decrypt the manifest of an already read container with `master_key` and start a session
+    fn unlock_sections(vault_path: &Path, sections: ContainerSections, master_key: &[u8]) -> Result<VaultSession, String> {
+        let header = sections.header;
+        let encrypted_manifest = &sections.encrypted_manifest;
 
-        // Derive cipher key
-        let mut cipher_key = Self::derive_key(password, &header.salt, &header.argon2_params)?;
+        // Derive the manifest/blob subkeys
+        let (manifest_key, blob_key) = Self::derive_subkeys(master_key, &header)?;
 
-        // Decrypt manifest
-        let manifest: VaultManifest = Self::decrypt_json(encrypted_manifest, &cipher_key)?;
+        // Decrypt manifest. A CRLF-mangled container most likely had each LF in the
+        // ciphertext expanded too, so undo that before trying the bytes as found.
+        let manifest: VaultManifest = if sections.crlf_mangled {
+            Self::decrypt_json(&crlf_to_lf(encrypted_manifest), &manifest_key)
+                .or_else(|_| Self::decrypt_json(encrypted_manifest, &manifest_key))
+                .map_err(|_| vault_corrupted_crlf("manifest could not be decrypted after repairing CRLF line endings, or the password is wrong"))?
+        } else {
+            Self::decrypt_json(encrypted_manifest, &manifest_key).or_else(|e| {
+                // A save interrupted while overwriting the manifest in place left it in the journal
+                std::fs::read(Self::journal_path(vault_path))
+                    .ok()
+                    .and_then(|journal| Self::decrypt_json(&journal, &manifest_key).ok())
+                    .ok_or(e)
+            })?
+        };
 
         // Verify tamper (check manifest integrity)
-        Self::verify_tamper(&manifest, &cipher_key)?;
+        Self::verify_tamper(&manifest, &manifest_key)?;
 
         // Create session
         let session = VaultSession {
             vault_id: header.vault_id,
             vault_path: vault_path.to_path_buf(),
-            cipher_key: cipher_key.clone(),
+            manifest_key,
+            blob_key,
             manifest,
             locked: false,
             last_accessed: Utc::now(),
+            busy: false,
         };
 
-        cipher_key.zeroize();
-
         Ok(session)
     }
 
+    /// Measures how much larger the vault is on disk than its contents, and where the bytes go
+    pub fn diagnostics(session: &VaultSession) -> Result<VaultDiagnostics, String> {
+        let sections = Self::read_container(&session.vault_path)?;
+        let file_len = |path: &Path| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+        let mut container_bytes = file_len(&session.vault_path);
+        if sections.header.split_storage {
+            container_bytes += file_len(&Self::blobs_path(&session.vault_path));
+        }
+
+        let entries = session.manifest.entries.values();
+        let logical_bytes = Self::used_bytes(session);
+        let inline_payload_bytes: u64 = entries.clone().map(|e| e.encrypted_data.len() as u64).sum();
+        let streamed_bytes: u64 = entries.clone().filter_map(|e| e.blob.as_ref()).map(|b| b.length).sum();
+        let streamed_entries = entries.clone().filter(|e| e.blob.is_some()).count();
+
+        let manifest_json = serde_json::to_vec(&session.manifest)
+            .map_err(|e| format!("Serialization failed: {}", e))?;
+
+        Ok(VaultDiagnostics {
+            split_storage: sections.header.split_storage,
+            container_bytes,
+            logical_bytes,
+            manifest_bytes: sections.encrypted_manifest.len() as u64,
+            metadata_bytes: (manifest_json.len() as u64).saturating_sub(inline_payload_bytes),
+            inline_payload_bytes,
+            streamed_bytes,
+            inline_entries: session.manifest.entries.len() - streamed_entries,
+            streamed_entries,
+            overhead_ratio: if logical_bytes == 0 {
+                0.0
+            } else {
+                container_bytes as f64 / logical_bytes as f64 - 1.0
+            },
+        })
+    }
+
+    /// Sum of the plaintext sizes of all entries
+    pub fn used_bytes(session: &VaultSession) -> u64 {
+        session.manifest.entries.values().map(|e| e.file_size).sum()
+    }
+
     /// Lock a vault session (erase in-memory key)
     pub fn lock_session(session: &mut VaultSession) -> Result<(), String> {
         session.locked = true;
-        session.cipher_key.zeroize();
+        session.manifest_key.zeroize();
+        session.blob_key.zeroize();
+        if let Ok(mut thumbnails) = THUMBNAILS.lock() {
+            thumbnails.retain(|(vault_id, _, _), _| vault_id != &session.vault_id);
+        }
         Ok(())
     }
 
+    /// Thumbnail of an image entry no larger than `max_dimension` on either side, with
+    /// EXIF orientation applied. The entry is decrypted in memory only. Results are cached
+    /// until the vault locks or the entry is re-encrypted.
+    pub fn thumbnail(session: &VaultSession, entry_id: &str, max_dimension: u32) -> Result<VaultThumbnail, String> {
+        use image::{DynamicImage, ImageDecoder, ImageReader};
+
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+        let entry = session.manifest.entries.get(entry_id).ok_or("Entry not found")?;
+        if Self::mime_category(entry) != "images" {
+            return Err("Entry is not an image".to_string());
+        }
+        if entry.file_size > MAX_THUMBNAIL_SOURCE_BYTES {
+            return Err("Image too large to thumbnail".to_string());
+        }
+
+        // Any re-import or re-encryption changes the nonce or blob location
+        let fingerprint = format!(
+            "{}:{}:{}:{:?}",
+            entry.nonce,
+            entry.file_size,
+            entry.imported_at,
+            entry.blob.as_ref().map(|b| (b.offset, b.length))
+        );
+        let key = (session.vault_id.clone(), entry_id.to_string(), max_dimension);
+        if let Ok(thumbnails) = THUMBNAILS.lock() {
+            if let Some(cached) = thumbnails.get(&key).filter(|c| c.fingerprint == fingerprint) {
+                return Ok(cached.thumbnail.clone());
+            }
+        }
+
+        let data = Zeroizing::new(Self::decrypt_entry(session, entry_id)?);
+        let mut decoder = ImageReader::new(std::io::Cursor::new(data.as_slice()))
+            .with_guessed_format()
+            .map_err(|e| format!("Failed to read image: {}", e))?
+            .into_decoder()
+            .map_err(|_| "Entry is not a supported image".to_string())?;
+        let orientation = decoder.orientation().map_err(|e| format!("Failed to read image: {}", e))?;
+        let mut image = DynamicImage::from_decoder(decoder).map_err(|e| format!("Failed to decode image: {}", e))?;
+        image.apply_orientation(orientation);
+
+        let small = DynamicImage::ImageRgb8(image.thumbnail(max_dimension, max_dimension).to_rgb8());
+        let mut jpeg = Vec::new();
+        small
+            .write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 80))
+            .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+
+        let thumbnail = VaultThumbnail {
+            data: general_purpose::STANDARD.encode(&jpeg),
+            mime: "image/jpeg".to_string(),
+            width: small.width(),
+            height: small.height(),
+        };
+        if let Ok(mut thumbnails) = THUMBNAILS.lock() {
+            if thumbnails.len() >= MAX_CACHED_THUMBNAILS {
+                thumbnails.clear();
+            }
+            thumbnails.insert(key, CachedThumbnail { fingerprint, thumbnail: thumbnail.clone() });
+        }
+        Ok(thumbnail)
+    }
+
     /// List vault entries
     pub fn list_entries(session: &VaultSession) -> Result<Vec<VaultEntry>, String> {
         if session.locked {
             return Err("Vault is locked".to_string());
         }
-        Ok(session.manifest.entries.values().cloned().collect())
+        Ok(Self::sorted_entries(&session.manifest))
     }
 
-    /// Import a file into the vault
-    pub fn import_file(
+    /// Entries in display order: pinned first, then by `sort_order`, then newest first
+    pub fn sorted_entries(manifest: &VaultManifest) -> Vec<VaultEntry> {
+        let mut entries: Vec<VaultEntry> = manifest.entries.values().cloned().collect();
+        entries.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then_with(|| a.sort_order.unwrap_or(u32::MAX).cmp(&b.sort_order.unwrap_or(u32::MAX)))
+                .then_with(|| b.imported_at.cmp(&a.imported_at))
+        });
+        entries
+    }
+
+    /// Pin or unpin an entry, optionally setting its position
+    pub fn set_pinned(
         session: &mut VaultSession,
-        source_path: &Path,
-        tags: Vec<String>,
-    ) -> Result<String, String> {
+        entry_id: &str,
+        pinned: bool,
+        sort_order: Option<u32>,
+    ) -> Result<(), String> {
         if session.locked {
             return Err("Vault is locked".to_string());
         }
 
-        // Read file
-        let mut file = File::open(source_path)
-            .map_err(|e| format!("Failed to open source file: {}", e))?;
-        let mut file_data = Vec::new();
-        file.read_to_end(&mut file_data)
-            .map_err(|e| format!("Failed to read source file: {}", e))?;
+        let entry = session.manifest.entries.get_mut(entry_id).ok_or("Entry not found")?;
+        entry.pinned = pinned;
+        entry.sort_order = sort_order;
+
+        session.manifest.access_log.push(AuditLog {
+            timestamp: Utc::now().to_rfc3339(),
+            action: if pinned { "pin" } else { "unpin" }.to_string(),
+            entry_id: Some(entry_id.to_string()),
+            status: "success".to_string(),
+        });
+
+        session.touch();
+        Self::save_manifest(session)
+    }
+
+    /// Flags audit log entries that are out of chronological order, dated in the future or
+    /// have no valid timestamp. With `apply`, a log that is out of order is stably re-sorted
+    /// by timestamp (the timestamps themselves are kept) and the manifest is saved.
+    pub fn repair_log(session: &mut VaultSession, apply: bool) -> Result<LogRepairReport, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+
+        let now = Utc::now();
+        let anomaly = |index: usize, entry: &AuditLog| LogAnomaly {
+            index,
+            timestamp: entry.timestamp.clone(),
+            action: entry.action.clone(),
+        };
+
+        let mut out_of_order = Vec::new();
+        let mut future_dated = Vec::new();
+        let mut unparseable = Vec::new();
+        // Sort keys; an unparseable entry stays right after the entry logged before it
+        let mut keys = Vec::with_capacity(session.manifest.access_log.len());
+        let mut latest: Option<DateTime<Utc>> = None;
+        let mut previous: Option<DateTime<Utc>> = None;
+
+        for (index, entry) in session.manifest.access_log.iter().enumerate() {
+            match DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(parsed) => {
+                    let at = parsed.with_timezone(&Utc);
+                    if matches!(latest, Some(latest) if at < latest) {
+                        out_of_order.push(anomaly(index, entry));
+                    }
+                    if (at - now).num_seconds() > LOG_FUTURE_TOLERANCE_SECS {
+                        future_dated.push(anomaly(index, entry));
+                    }
+                    latest = latest.max(Some(at));
+                    previous = Some(at);
+                }
+                Err(_) => unparseable.push(anomaly(index, entry)),
+            }
+            keys.push(previous);
+        }
+
+        let reordered = apply && !out_of_order.is_empty();
+        if reordered {
+            let log = std::mem::take(&mut session.manifest.access_log);
+            let mut keyed: Vec<(Option<DateTime<Utc>>, AuditLog)> = keys.into_iter().zip(log).collect();
+            keyed.sort_by_key(|(key, _)| *key);
+            session.manifest.access_log = keyed.into_iter().map(|(_, entry)| entry).collect();
+
+            session.manifest.access_log.push(AuditLog {
+                timestamp: now.to_rfc3339(),
+                action: "log_repaired".to_string(),
+                entry_id: None,
+                status: "success".to_string(),
+            });
+            session.touch();
+            Self::save_manifest(session)?;
+        }
+
+        Ok(LogRepairReport {
+            total: session.manifest.access_log.len(),
+            out_of_order,
+            future_dated,
+            unparseable,
+            reordered,
+        })
+    }
+
+    /// Write the full audit log to `archive_path`, encrypted under the vault's archive key,
+    /// then keep only the newest `keep_recent` entries in the manifest. The archive is
+    /// complete on disk before the log is trimmed, and the log is restored if the save fails.
+    /// An existing file at `archive_path` is never overwritten.
+    pub fn truncate_log(session: &mut VaultSession, keep_recent: usize, archive_path: &Path) -> Result<LogTruncation, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+        if archive_path.exists() {
+            return Err("A file already exists at the archive path".to_string());
+        }
+
+        let archive_key = Self::archive_key(session)?;
+        let log = &session.manifest.access_log;
+        let archive = LogArchiveFile {
+            version: LOG_ARCHIVE_VERSION,
+            vault_id: session.vault_id.clone(),
+            created_at: Utc::now().to_rfc3339(),
+            entries: log.len(),
+            data: general_purpose::STANDARD.encode(Self::encrypt_data(log, &archive_key)?),
+        };
+        let json = serde_json::to_vec_pretty(&archive)
+            .map_err(|e| format!("Failed to serialize log archive: {}", e))?;
+        let temp_path = archive_path.with_extension("tmp");
+        std::fs::write(&temp_path, json)
+            .and_then(|_| std::fs::rename(&temp_path, archive_path))
+            .map_err(|e| {
+                let _ = std::fs::remove_file(&temp_path);
+                format!("Failed to write log archive: {}", e)
+            })?;
+
+        Self::auto_backup(session)?;
+        let previous = session.manifest.access_log.clone();
+        let archived = previous.len();
+        let trimmed_from = archived.saturating_sub(keep_recent);
+        session.manifest.access_log.drain(..trimmed_from);
+        session.manifest.access_log.push(AuditLog {
+            timestamp: Utc::now().to_rfc3339(),
+            action: "log_truncated".to_string(),
+            entry_id: None,
+            status: "success".to_string(),
+        });
+        session.touch();
+        if let Err(e) = Self::save_manifest(session) {
+            session.manifest.access_log = previous;
+            return Err(e);
+        }
+
+        Ok(LogTruncation {
+            archived,
+            kept: session.manifest.access_log.len(),
+        })
+    }
+
+    /// Read back the entries of a log archive written by `truncate_log` for this vault
+    pub fn read_log_archive(session: &VaultSession, archive_path: &Path) -> Result<Vec<AuditLog>, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+
+        let json = std::fs::read(archive_path)
+            .map_err(|e| format!("Failed to read log archive: {}", e))?;
+        let archive: LogArchiveFile = serde_json::from_slice(&json)
+            .map_err(|e| format!("Not a log archive: {}", e))?;
+        if archive.version != LOG_ARCHIVE_VERSION {
+            return Err(format!("Unsupported log archive version {}", archive.version));
+        }
+        if archive.vault_id != session.vault_id {
+            return Err("Log archive belongs to another vault".to_string());
+        }
+
+        let data = general_purpose::STANDARD.decode(&archive.data)
+            .map_err(|e| format!("Failed to decode log archive: {}", e))?;
+        let key = session.manifest.archive_key.as_ref().ok_or("This vault has no log archive key")?;
+        let key = Zeroizing::new(hex::decode(key).map_err(|e| vault_corrupted(format!("invalid archive key: {}", e)))?);
+        Self::decrypt_json(&data, &key)
+            .map_err(|_| "Log archive was written under a different vault key or is corrupted".to_string())
+    }
+
+    /// The vault's log archive key, generated and saved with the manifest on first use
+    fn archive_key(session: &mut VaultSession) -> Result<Zeroizing<Vec<u8>>, String> {
+        if session.manifest.archive_key.is_none() {
+            let key: [u8; 32] = rand::thread_rng().gen();
+            session.manifest.archive_key = Some(hex::encode(key));
+            if let Err(e) = Self::save_manifest(session) {
+                session.manifest.archive_key = None;
+                return Err(e);
+            }
+        }
+        let key = session.manifest.archive_key.as_ref().ok_or("This vault has no log archive key")?;
+        hex::decode(key)
+            .map(Zeroizing::new)
+            .map_err(|e| vault_corrupted(format!("invalid archive key: {}", e)))
+    }
+
+    /// Import a file into the vault. Split-storage vaults keep every blob in the `.blobs`
+    /// file, so there the file is streamed in like `ingest_file` does.
+    pub fn import_file(
+        session: &mut VaultSession,
+        source_path: &Path,
+        tags: Vec<String>,
+    ) -> Result<String, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+        if Self::is_split_storage(&session.vault_path)? {
+            return Self::ingest_file(session, source_path, tags, false);
+        }
+
+        // Read file
+        let mut file = File::open(source_path)
+            .map_err(|e| format!("Failed to open source file: {}", e))?;
+        let mut file_data = Vec::new();
+        file.read_to_end(&mut file_data)
+            .map_err(|e| format!("Failed to read source file: {}", e))?;
 
         let file_size = file_data.len() as u64;
 
         // Check vault size
-        let current_size: u64 = session
-            .manifest
-            .entries
-            .values()
-            .map(|e| e.file_size)
-            .sum();
+        let current_size = Self::used_bytes(session);
         if current_size + file_size > MAX_VAULT_SIZE {
             return Err("Vault size limit exceeded".to_string());
         }
@@ -274,7 +1287,7 @@ impl Vault {
         let nonce = hex::encode(&nonce_bytes);
 
         // Encrypt file data with random nonce
-        let encrypted_with_nonce = Self::encrypt_bytes_with_nonce(&file_data, &session.cipher_key, &nonce_bytes)?;
+        let encrypted_with_nonce = Self::encrypt_bytes_with_nonce(&file_data, &session.blob_key, &nonce_bytes)?;
         let encrypted_data_b64 = general_purpose::STANDARD.encode(&encrypted_with_nonce);
 
         // Create entry metadata
@@ -292,6 +1305,10 @@ impl Vault {
             nonce,
             tags,
             encrypted_data: encrypted_data_b64,
+            blob: None,
+            content_hash: Some(format!("{:x}", Sha256::digest(&file_data))),
+            pinned: false,
+            sort_order: None,
         };
 
         session.manifest.entries.insert(entry_id.clone(), entry);
@@ -310,12 +1327,155 @@ impl Vault {
         Ok(entry_id)
     }
 
-    /// Export a file from the vault
-    pub fn export_file(
+    /// Stream a file into the container's blob section in encrypted chunks.
+    ///
+    /// The source is read once and never held in memory as a whole. With `shred`,
+    /// the source is overwritten and removed only after the container is safely
+    /// replaced, so a failed ingest never destroys it.
+    pub fn ingest_file(
         session: &mut VaultSession,
-        entry_id: &str,
-        output_path: &Path,
-    ) -> Result<(), String> {
+        source_path: &Path,
+        tags: Vec<String>,
+        shred: bool,
+    ) -> Result<String, String> {
+        let entry_id = Self::ingest_files(session, &[source_path.to_path_buf()], tags)?.remove(0);
+
+        if shred {
+            let file_size = session.manifest.entries[&entry_id].file_size;
+            Self::shred_file(source_path, file_size)
+                .map_err(|e| format!("File ingested but shredding failed: {}", e))?;
+        }
+
+        Ok(entry_id)
+    }
+
+    /// Streams several files into the blob section with a single container write.
+    /// Either every file is ingested or none is; ids are returned in `sources` order.
+    pub fn ingest_files(
+        session: &mut VaultSession,
+        sources: &[PathBuf],
+        tags: Vec<String>,
+    ) -> Result<Vec<String>, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+
+        let mut sizes = Vec::with_capacity(sources.len());
+        let mut hashes = Vec::with_capacity(sources.len());
+        for source_path in sources {
+            let file_size = std::fs::metadata(source_path)
+                .map_err(|e| format!("Failed to read metadata of {}: {}", source_path.display(), e))?
+                .len();
+            sizes.push(file_size);
+            hashes.push(Self::file_sha256(source_path)?);
+        }
+
+        let current_size = Self::used_bytes(session);
+        if current_size + sizes.iter().sum::<u64>() > MAX_VAULT_SIZE {
+            return Err("Vault size limit exceeded".to_string());
+        }
+
+        // New chunks go right after the existing blob section
+        let mut next_offset = Self::blob_section_len(&session.vault_path)?;
+
+        let previous_manifest = session.manifest.clone();
+        let mut entry_ids = Vec::with_capacity(sources.len());
+        for ((source_path, &file_size), hash) in sources.iter().zip(&sizes).zip(&hashes) {
+            let entry_id = uuid::Uuid::new_v4().to_string();
+            let blob = StreamedBlob {
+                offset: next_offset,
+                length: StreamedBlob::encrypted_len(file_size, STREAM_CHUNK_SIZE),
+                chunk_size: STREAM_CHUNK_SIZE,
+            };
+            next_offset += blob.length;
+            let entry = VaultEntry {
+                id: entry_id.clone(),
+                filename: source_path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                original_path: source_path.to_string_lossy().to_string(),
+                file_size,
+                mime_type: Self::guess_mime_type(source_path),
+                imported_at: Utc::now().to_rfc3339(),
+                nonce: String::new(),
+                tags: tags.clone(),
+                encrypted_data: String::new(),
+                blob: Some(blob),
+                content_hash: Some(hash.clone()),
+                pinned: false,
+                sort_order: None,
+            };
+
+            session.manifest.entries.insert(entry_id.clone(), entry);
+            session.manifest.access_log.push(AuditLog {
+                timestamp: Utc::now().to_rfc3339(),
+                action: "ingest".to_string(),
+                entry_id: Some(entry_id.clone()),
+                status: "success".to_string(),
+            });
+            entry_ids.push(entry_id);
+        }
+
+        let blob_key = session.blob_key.clone();
+        let result = Self::write_container(session, |out| {
+            for (((source_path, &file_size), hash), entry_id) in sources.iter().zip(&sizes).zip(&hashes).zip(&entry_ids) {
+                // The recorded hash must describe the bytes actually stored
+                if Self::encrypt_stream(source_path, file_size, entry_id, &blob_key, out)? != *hash {
+                    return Err(format!("Source file changed while ingesting: {}", source_path.display()));
+                }
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            session.manifest = previous_manifest;
+            return Err(e);
+        }
+
+        session.touch();
+        Ok(entry_ids)
+    }
+
+    /// Ids of entries whose plaintext matches the file at `source_path`.
+    /// Only same-size entries are compared; entries without a stored hash are decrypted and hashed.
+    pub fn find_duplicates(session: &VaultSession, source_path: &Path) -> Result<Vec<String>, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+
+        let file_size = std::fs::metadata(source_path)
+            .map_err(|e| format!("Failed to read source metadata: {}", e))?
+            .len();
+        let candidates: Vec<&VaultEntry> = session
+            .manifest
+            .entries
+            .values()
+            .filter(|e| e.file_size == file_size)
+            .collect();
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let source_hash = Self::file_sha256(source_path)?;
+
+        let mut matches = Vec::new();
+        for entry in candidates {
+            let entry_hash = match &entry.content_hash {
+                Some(hash) => hash.clone(),
+                None => format!("{:x}", Sha256::digest(Self::decrypt_entry(session, &entry.id)?)),
+            };
+            if entry_hash == source_hash {
+                matches.push(entry.id.clone());
+            }
+        }
+
+        matches.sort();
+        Ok(matches)
+    }
+
+    /// Decrypt an entry's contents in memory
+    pub fn decrypt_entry(session: &VaultSession, entry_id: &str) -> Result<Vec<u8>, String> {
         if session.locked {
             return Err("Vault is locked".to_string());
         }
@@ -324,8 +1484,16 @@ impl Vault {
             .manifest
             .entries
             .get(entry_id)
-            .ok_or("Entry not found")?
-            .clone();
+            .ok_or("Entry not found")?;
+
+        if let Some(blob) = &entry.blob {
+            let mut data = Vec::with_capacity(entry.file_size as usize);
+            Self::read_streamed_blob(session, entry, blob, |chunk| {
+                data.extend_from_slice(chunk);
+                Ok(())
+            })?;
+            return Ok(data);
+        }
 
         // Check if encrypted data exists (backward compatibility)
         if entry.encrypted_data.is_empty() {
@@ -338,14 +1506,79 @@ impl Vault {
         // Decode and decrypt file data
         let encrypted_data = general_purpose::STANDARD.decode(&entry.encrypted_data)
             .map_err(|e| format!("Failed to decode encrypted data: {}", e))?;
-        let file_data = Self::decrypt_bytes(&encrypted_data, &session.cipher_key)?;
+        Self::decrypt_bytes(&encrypted_data, &session.blob_key)
+    }
+
+    /// Decrypts an entry in memory and compares its SHA-256 with `exported_path`.
+    /// Nothing is written, neither to the vault nor to the exported file.
+    pub fn verify_export(
+        session: &VaultSession,
+        entry_id: &str,
+        exported_path: &Path,
+    ) -> Result<ExportVerification, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+        let entry = session.manifest.entries.get(entry_id).ok_or("Entry not found")?;
+
+        let mut entry_hasher = Sha256::new();
+        let mut entry_size: u64 = 0;
+        if let Some(blob) = &entry.blob {
+            Self::read_streamed_blob(session, entry, blob, |chunk| {
+                entry_hasher.update(chunk);
+                entry_size += chunk.len() as u64;
+                Ok(())
+            })?;
+        } else {
+            let data = Zeroizing::new(Self::decrypt_entry(session, entry_id)?);
+            entry_hasher.update(&*data);
+            entry_size = data.len() as u64;
+        }
+
+        let mut exported = File::open(exported_path)
+            .map_err(|e| format!("Failed to open exported file: {}", e))?;
+        let mut exported_hasher = Sha256::new();
+        let exported_size = std::io::copy(&mut exported, &mut exported_hasher)
+            .map_err(|e| format!("Failed to read exported file: {}", e))?;
+
+        let entry_hash = format!("{:x}", entry_hasher.finalize());
+        let exported_hash = format!("{:x}", exported_hasher.finalize());
+        Ok(ExportVerification {
+            matches: entry_size == exported_size && entry_hash == exported_hash,
+            entry_size,
+            exported_size,
+            entry_hash,
+            exported_hash,
+        })
+    }
+
+    /// Export a file from the vault
+    pub fn export_file(
+        session: &mut VaultSession,
+        entry_id: &str,
+        output_path: &Path,
+    ) -> Result<(), String> {
+        let entry = session.manifest.entries.get(entry_id).ok_or("Entry not found")?;
+        check_free_space(output_path, entry.file_size).map_err(|e| e.to_string())?;
 
-        // Write to output path
         let mut output = File::create(output_path)
             .map_err(|e| format!("Failed to create output file: {}", e))?;
-        output
-            .write_all(&file_data)
-            .map_err(|e| format!("Failed to write output file: {}", e))?;
+
+        if let Some(blob) = &entry.blob {
+            // Streamed entries are decrypted chunk by chunk straight to disk
+            Self::read_streamed_blob(session, entry, blob, |chunk| {
+                output
+                    .write_all(chunk)
+                    .map_err(|e| format!("Failed to write output file: {}", e))
+            })?;
+        } else {
+            let file_data = Self::decrypt_entry(session, entry_id)?;
+
+            // Write to output path
+            output
+                .write_all(&file_data)
+                .map_err(|e| format!("Failed to write output file: {}", e))?;
+        }
 
         // Log action
         session.manifest.access_log.push(AuditLog {
@@ -367,11 +1600,13 @@ impl Vault {
             return Err("Vault is locked".to_string());
         }
 
-        session
-            .manifest
-            .entries
-            .remove(entry_id)
-            .ok_or("Entry not found")?;
+        if !session.manifest.entries.contains_key(entry_id) {
+            return Err("Entry not found".to_string());
+        }
+
+        Self::auto_backup(session)?;
+
+        session.manifest.entries.remove(entry_id);
 
         session.manifest.access_log.push(AuditLog {
             timestamp: Utc::now().to_rfc3339(),
@@ -386,21 +1621,349 @@ impl Vault {
         Ok(())
     }
 
+    /// Compute a Merkle root over every entry's ciphertext
+    ///
+    /// Leaves are ordered by entry id so the root is independent of
+    /// manifest ordering; unchanged entries keep their leaf hashes.
+    pub fn merkle_root(session: &VaultSession) -> Result<VaultMerkleRoot, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+
+        let mut ids: Vec<&String> = session.manifest.entries.keys().collect();
+        ids.sort();
+
+        let blob_source = Self::blob_source(&session.vault_path)?;
+
+        let mut leaves = HashMap::new();
+        let mut level: Vec<[u8; 32]> = Vec::with_capacity(ids.len());
+        for id in ids {
+            let entry = &session.manifest.entries[id];
+
+            let mut hasher = Sha256::new();
+            hasher.update([0x00]);
+            hasher.update(id.as_bytes());
+            match &entry.blob {
+                Some(blob) => {
+                    let (blob_path, blob_start) = blob_source.as_ref().ok_or("Vault has no blob section")?;
+                    let mut file = File::open(blob_path)
+                        .map_err(|e| format!("Failed to open vault: {}", e))?;
+                    file.seek(SeekFrom::Start(blob_start + blob.offset))
+                        .map_err(|e| format!("Failed to seek vault blob: {}", e))?;
+                    std::io::copy(&mut file.take(blob.length), &mut hasher)
+                        .map_err(|e| format!("Failed to read vault blob: {}", e))?;
+                }
+                None => {
+                    let ciphertext = general_purpose::STANDARD.decode(&entry.encrypted_data)
+                        .map_err(|e| format!("Failed to decode encrypted data: {}", e))?;
+                    hasher.update(&ciphertext);
+                }
+            }
+            let leaf: [u8; 32] = hasher.finalize().into();
+
+            leaves.insert(id.clone(), hex::encode(leaf));
+            level.push(leaf);
+        }
+
+        if level.is_empty() {
+            level.push(Sha256::digest(b"").into());
+        }
+
+        // Odd nodes are paired with themselves
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| {
+                    let right = pair.get(1).unwrap_or(&pair[0]);
+                    let mut hasher = Sha256::new();
+                    hasher.update([0x01]);
+                    hasher.update(pair[0]);
+                    hasher.update(right);
+                    hasher.finalize().into()
+                })
+                .collect();
+        }
+
+        Ok(VaultMerkleRoot {
+            root: hex::encode(level[0]),
+            leaves,
+        })
+    }
+
+    /// Manifest as pretty JSON with every entry's ciphertext removed
+    pub fn export_metadata(session: &VaultSession) -> Result<String, String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+
+        let mut manifest = serde_json::to_value(&session.manifest)
+            .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        if let Some(entries) = manifest.get_mut("entries").and_then(|e| e.as_object_mut()) {
+            for entry in entries.values_mut() {
+                if let Some(fields) = entry.as_object_mut() {
+                    fields.remove("encrypted_data");
+                    // A plaintext hash lets anyone holding the export confirm a guessed file
+                    fields.remove("content_hash");
+                }
+            }
+        }
+        if let Some(fields) = manifest.as_object_mut() {
+            fields.remove("archive_key");
+        }
+
+        serde_json::to_string_pretty(&manifest)
+            .map_err(|e| format!("Failed to serialize manifest: {}", e))
+    }
+
+    /// Write recovery codes to `output_path`, encrypted under a key derived from `pin`
+    /// with `argon2_params`, normally the vault's own calibrated params
+    pub fn export_recovery_codes(codes: &[String], output_path: &Path, pin: &str, argon2_params: &str) -> Result<(), String> {
+        if pin.chars().count() < MIN_RECOVERY_PIN_LEN {
+            return Err(format!("PIN must be at least {} characters", MIN_RECOVERY_PIN_LEN));
+        }
+
+        let salt_bytes: [u8; 16] = rand::thread_rng().gen();
+        let salt = hex::encode(salt_bytes);
+        let key = Self::derive_key(pin, &salt, argon2_params)?;
+
+        let file = RecoveryFile {
+            version: 1,
+            salt,
+            argon2_params: argon2_params.to_string(),
+            data: general_purpose::STANDARD.encode(Self::encrypt_data(&codes, &key)?),
+        };
+        let json = serde_json::to_vec_pretty(&file)
+            .map_err(|e| format!("Failed to serialize recovery file: {}", e))?;
+        std::fs::write(output_path, json)
+            .map_err(|e| format!("Failed to write recovery file: {}", e))
+    }
+
+    /// Read recovery codes back from a file written by `export_recovery_codes`
+    pub fn import_recovery_codes(path: &Path, pin: &str) -> Result<Vec<String>, String> {
+        let json = std::fs::read(path)
+            .map_err(|e| format!("Failed to read recovery file: {}", e))?;
+        let file: RecoveryFile = serde_json::from_slice(&json)
+            .map_err(|e| format!("Not a recovery file: {}", e))?;
+
+        let key = Self::derive_key(pin, &file.salt, &file.argon2_params)?;
+        let data = general_purpose::STANDARD.decode(&file.data)
+            .map_err(|e| format!("Failed to decode recovery file: {}", e))?;
+        Self::decrypt_json(&data, &key).map_err(|_| "Wrong PIN or corrupted recovery file".to_string())
+    }
+
+    /// Checks that `codes` unwrap the vault's cipher key and that the key decrypts the
+    /// manifest. Needs no password and writes nothing.
+    pub fn test_recovery(vault_path: &Path, codes: &[String]) -> Result<bool, String> {
+        let sections = Self::read_container(vault_path)?;
+        let header = &sections.header;
+        let Some(recovery) = &header.recovery else {
+            return Err("This vault has no recovery codes bound to it".to_string());
+        };
+
+        let secret = Self::recovery_secret(codes);
+        let key = Self::derive_key(&secret, &recovery.salt, Self::kdf_params(header))?;
+        let wrapped = general_purpose::STANDARD.decode(&recovery.wrapped_key)
+            .map_err(|e| vault_corrupted(format!("invalid recovery key: {}", e)))?;
+        let Ok(master_key) = Self::decrypt_bytes(&wrapped, &key).map(Zeroizing::new) else {
+            return Ok(false);
+        };
+
+        let (manifest_key, _) = Self::derive_subkeys(&master_key, header)?;
+        let manifest = if sections.crlf_mangled {
+            crlf_to_lf(&sections.encrypted_manifest)
+        } else {
+            sections.encrypted_manifest
+        };
+        Self::decrypt_json::<VaultManifest>(&manifest, &manifest_key)
+            .map(|_| true)
+            .map_err(|_| {
+                let detail = "recovery codes unwrap the key but the manifest does not decrypt";
+                if sections.crlf_mangled { vault_corrupted_crlf(detail) } else { vault_corrupted(detail) }
+            })
+    }
+
     // ========== Private Helper Methods ==========
 
+    /// The codes as one passphrase; case, surrounding spaces and blank codes don't matter
+    fn recovery_secret(codes: &[String]) -> Zeroizing<String> {
+        Zeroizing::new(
+            codes
+                .iter()
+                .map(|code| code.trim().to_lowercase())
+                .filter(|code| !code.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    /// Encrypts `master_key` under a key derived from `codes` with the vault's Argon2 params
+    fn wrap_for_recovery(master_key: &[u8], codes: &[String], header: &VaultHeader) -> Result<RecoveryWrap, String> {
+        let salt_bytes: [u8; 16] = rand::thread_rng().gen();
+        let salt = hex::encode(salt_bytes);
+        let key = Self::derive_key(&Self::recovery_secret(codes), &salt, Self::kdf_params(header))?;
+        let nonce: [u8; 12] = rand::thread_rng().gen();
+        let wrapped = Self::encrypt_bytes_with_nonce(master_key, &key, &nonce)?;
+        Ok(RecoveryWrap {
+            salt,
+            wrapped_key: general_purpose::STANDARD.encode(wrapped),
+        })
+    }
+
+    /// Copy the container into the configured backup dir before a destructive change.
+    /// Does nothing unless auto-backup was enabled for this vault.
+    fn auto_backup(session: &VaultSession) -> Result<(), String> {
+        let config = {
+            let configs = AUTOBACKUP.lock().map_err(|_| "Failed to acquire backup lock".to_string())?;
+            match configs.get(&session.vault_id) {
+                Some(config) => config.clone(),
+                None => return Ok(()),
+            }
+        };
+
+        std::fs::create_dir_all(&config.backup_dir)
+            .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+        // Backups are the encrypted container itself, so no extra crypto is needed
+        let prefix = format!("{}-", session.vault_id);
+        let backup_name = format!("{}{}.vault", prefix, Utc::now().format("%Y%m%dT%H%M%S%.3fZ"));
+        let backup_path = config.backup_dir.join(backup_name);
+        let blobs_path = Self::blobs_path(&session.vault_path);
+        if Self::is_split_storage(&session.vault_path)? {
+            std::fs::copy(&blobs_path, Self::blobs_path(&backup_path))
+                .map_err(|e| format!("Failed to back up vault blobs: {}", e))?;
+        }
+        std::fs::copy(&session.vault_path, &backup_path)
+            .map_err(|e| format!("Failed to back up vault: {}", e))?;
+
+        // Timestamps sort lexically, so the oldest backups come first
+        let mut backups: Vec<PathBuf> = std::fs::read_dir(&config.backup_dir)
+            .map_err(|e| format!("Failed to read backup directory: {}", e))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                let name = p.file_name().unwrap_or_default().to_string_lossy();
+                name.starts_with(&prefix) && name.ends_with(".vault")
+            })
+            .collect();
+        backups.sort();
+
+        let excess = backups.len().saturating_sub(config.keep_n);
+        for old in &backups[..excess] {
+            let _ = std::fs::remove_file(old);
+            let _ = std::fs::remove_file(Self::blobs_path(old));
+        }
+
+        Ok(())
+    }
+
     /// Derive encryption key from password using Argon2id
-    fn derive_key(password: &str, salt: &str, _argon2_params: &str) -> Result<Vec<u8>, String> {
+    /// Empty params mean the Argon2 defaults. The stack buffer and the returned key
+    /// are both zeroized when dropped.
+    fn derive_key(password: &str, salt: &str, argon2_params: &str) -> Result<Zeroizing<Vec<u8>>, String> {
         let salt_bytes = hex::decode(salt)
             .map_err(|e| format!("Failed to decode salt: {}", e))?;
 
-        let argon2 = Argon2::default();
-        let mut key = [0u8; 32];
+        let argon2 = Self::argon2_for(argon2_params)?;
+        let mut key = Zeroizing::new([0u8; 32]);
+
+        argon2
+            .hash_password_into(password.as_bytes(), &salt_bytes, key.as_mut())
+            .map_err(|e| format!("Argon2 hashing failed: {}", e))?;
+
+        Ok(Zeroizing::new(key.to_vec()))
+    }
+
+    /// Params the header's key was actually derived with
+    fn kdf_params(header: &VaultHeader) -> &str {
+        if header.version >= KDF_PARAMS_VERSION {
+            &header.argon2_params
+        } else {
+            ""
+        }
+    }
+
+    /// Argon2id instance for a `m=<KiB>,t=<iterations>,p=<lanes>` string
+    fn argon2_for(params: &str) -> Result<Argon2<'static>, String> {
+        if params.is_empty() {
+            return Ok(Argon2::default());
+        }
+
+        let (mut m, mut t, mut p) = (None, None, None);
+        for part in params.split(',') {
+            let (name, value) = part.trim().split_once('=').ok_or("Invalid Argon2 parameters")?;
+            let value: u32 = value.parse().map_err(|_| format!("Invalid Argon2 parameter: {}", part))?;
+            match name {
+                "m" => m = Some(value),
+                "t" => t = Some(value),
+                "p" => p = Some(value),
+                _ => return Err(format!("Unknown Argon2 parameter: {}", name)),
+            }
+        }
 
+        let (Some(m), Some(t), Some(p)) = (m, t, p) else {
+            return Err("Argon2 parameters need m, t and p".to_string());
+        };
+        let params = Params::new(m, t, p, Some(32))
+            .map_err(|e| format!("Invalid Argon2 parameters: {}", e))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+
+    /// Time one 32-byte derivation with the given params
+    fn time_derivation(memory_kib: u32, iterations: u32, parallelism: u32) -> Result<u64, String> {
+        let argon2 = Self::argon2_for(&format!("m={},t={},p={}", memory_kib, iterations, parallelism))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        let start = std::time::Instant::now();
         argon2
-            .hash_password_into(password.as_bytes(), &salt_bytes, &mut key)
+            .hash_password_into(b"calibration", b"calibration-salt", key.as_mut())
             .map_err(|e| format!("Argon2 hashing failed: {}", e))?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    /// Pick Argon2id params whose derivation takes about `target_ms` on this machine.
+    /// Memory is lowered first if even one pass is too slow, then passes are added.
+    pub fn calibrate_argon2(target_ms: u64) -> Result<Argon2Calibration, String> {
+        let parallelism = std::thread::available_parallelism()
+            .map(|n| n.get().min(4) as u32)
+            .unwrap_or(1);
+        let mut memory_kib = 64 * 1024;
+
+        let mut per_pass = Self::time_derivation(memory_kib, 1, parallelism)?.max(1);
+        while per_pass > target_ms && memory_kib / 2 >= CALIBRATION_MIN_MEMORY_KIB {
+            memory_kib /= 2;
+            per_pass = Self::time_derivation(memory_kib, 1, parallelism)?.max(1);
+        }
+
+        let iterations = ((target_ms / per_pass) as u32).clamp(1, CALIBRATION_MAX_ITERATIONS);
+        let measured_ms = Self::time_derivation(memory_kib, iterations, parallelism)?;
 
-        Ok(key.to_vec())
+        Ok(Argon2Calibration {
+            params: format!("m={},t={},p={}", memory_kib, iterations, parallelism),
+            memory_kib,
+            iterations,
+            parallelism,
+            measured_ms,
+        })
+    }
+
+    /// Split the master key into (manifest, blob) subkeys with HKDF-SHA256, salted
+    /// with the vault id. Vaults older than `KEY_SEPARATION_VERSION` use the master
+    /// key for both, as they were written that way.
+    fn derive_subkeys(
+        master_key: &[u8],
+        header: &VaultHeader,
+    ) -> Result<(Zeroizing<Vec<u8>>, Zeroizing<Vec<u8>>), String> {
+        if header.version < KEY_SEPARATION_VERSION {
+            return Ok((Zeroizing::new(master_key.to_vec()), Zeroizing::new(master_key.to_vec())));
+        }
+
+        let hkdf = Hkdf::<Sha256>::new(Some(header.vault_id.as_bytes()), master_key);
+        let mut manifest_key = Zeroizing::new(vec![0u8; 32]);
+        let mut blob_key = Zeroizing::new(vec![0u8; 32]);
+        hkdf.expand(MANIFEST_KEY_INFO, &mut manifest_key)
+            .and_then(|_| hkdf.expand(BLOB_KEY_INFO, &mut blob_key))
+            .map_err(|e| format!("Key derivation failed: {}", e))?;
+
+        Ok((manifest_key, blob_key))
     }
 
     /// Encrypt data using ChaCha20-Poly1305
@@ -513,50 +2076,782 @@ impl Vault {
         Ok(())
     }
 
-    /// Save updated manifest to vault file
-    fn save_manifest(session: &VaultSession) -> Result<(), String> {
-        // Read existing vault file
-        let mut file = File::open(&session.vault_path)
-            .map_err(|e| format!("Failed to open vault for saving: {}", e))?;
-        let mut contents = Vec::new();
-        file.read_to_end(&mut contents)
-            .map_err(|e| format!("Failed to read vault: {}", e))?;
-
-        // Find boundary marker
-        let boundary = b"\n---VAULT_BOUNDARY---\n";
-        let boundary_pos = contents
-            .windows(boundary.len())
-            .position(|w| w == boundary)
-            .ok_or("Invalid vault format: boundary not found")?;
+    /// Find `marker` in `haystack`, starting the search at `from`
+    fn find_marker(haystack: &[u8], marker: &[u8], from: usize) -> Option<usize> {
+        haystack
+            .get(from..)?
+            .windows(marker.len())
+            .position(|w| w == marker)
+            .map(|p| p + from)
+    }
 
-        // Serialize new manifest
-        let manifest_json = serde_json::to_vec(&session.manifest)
-            .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    /// The plaintext header of `path` if it starts like a vault container. Reads at most
+    /// MAX_HEADER_BYTES, so scanning large unrelated files stays cheap.
+    fn peek_header(path: &Path) -> Option<VaultHeader> {
+        let mut file = File::open(path).ok()?;
+        let mut first = [0u8; 1];
+        file.read_exact(&mut first).ok()?;
+        if first[0] != b'{' {
+            return None;
+        }
+        let mut start = first.to_vec();
+        file.take(MAX_HEADER_BYTES - 1).read_to_end(&mut start).ok()?;
+        let end = Self::find_marker(&start, VAULT_BOUNDARY, 0)
+            .or_else(|| Self::find_marker(&start, VAULT_BOUNDARY_CRLF, 0))?;
+        let header: VaultHeader = serde_json::from_slice(&start[..end]).ok()?;
+        (!header.vault_id.is_empty() && hex::decode(&header.salt).is_ok()).then_some(header)
+    }
 
-        // Encrypt manifest with same key
-        let encrypted_manifest = Self::encrypt_data(&session.manifest, &session.cipher_key)?;
+    /// Whether the vault at `vault_path` keeps its blobs in a separate file, read from
+    /// the header alone
+    fn is_split_storage(vault_path: &Path) -> Result<bool, String> {
+        Self::peek_header(vault_path)
+            .map(|header| header.split_storage)
+            .ok_or_else(|| "Failed to read vault header".to_string())
+    }
 
-        // Rebuild vault file: header + boundary + encrypted_manifest + file blobs
-        let header_and_boundary = &contents[..boundary_pos + boundary.len()];
+    /// Whether `path` is a vault container, or the blob file of a split-storage vault
+    /// whose meta file sits next to it
+    pub(crate) fn is_vault_file(path: &Path) -> bool {
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("blobs")) {
+            return ["vault", "meta"].iter().any(|ext| {
+                Self::peek_header(&path.with_extension(ext)).is_some_and(|h| h.split_storage)
+            });
+        }
+        Self::peek_header(path).is_some()
+    }
 
-        let mut new_file = File::create(&session.vault_path)
-            .map_err(|e| format!("Failed to create vault file: {}", e))?;
+    /// Read the header and encrypted manifest, stopping at the blob section
+    fn read_container(vault_path: &Path) -> Result<ContainerSections, String> {
+        let mut file = File::open(vault_path)
+            .map_err(|e| format!("Failed to open vault: {}", e))?;
 
-        new_file.write_all(header_and_boundary)
-            .map_err(|e| format!("Failed to write header: {}", e))?;
+        let mut contents = Vec::new();
+        let mut chunk = vec![0u8; 64 * 1024];
+        let mut boundary_pos = None;
+        let mut blob_marker_pos = None;
+
+        loop {
+            let n = file.read(&mut chunk)
+                .map_err(|e| format!("Failed to read vault: {}", e))?;
+            if n == 0 {
+                break;
+            }
+            let searched = contents.len();
+            contents.extend_from_slice(&chunk[..n]);
+
+            if boundary_pos.is_none() {
+                boundary_pos = Self::find_marker(&contents, VAULT_BOUNDARY, searched.saturating_sub(VAULT_BOUNDARY.len()));
+            }
+            if let Some(pos) = boundary_pos {
+                let from = searched
+                    .saturating_sub(BLOB_BOUNDARY.len())
+                    .max(pos + VAULT_BOUNDARY.len());
+                blob_marker_pos = Self::find_marker(&contents, BLOB_BOUNDARY, from);
+                if blob_marker_pos.is_some() {
+                    break;
+                }
+            }
+        }
+
+        // Without the LF boundary the whole file has been read; look for the CRLF form
+        let (boundary_pos, boundary_len, blob_marker_len, crlf_mangled) = match boundary_pos {
+            Some(pos) => (pos, VAULT_BOUNDARY.len(), BLOB_BOUNDARY.len(), false),
+            None => {
+                let pos = Self::find_marker(&contents, VAULT_BOUNDARY_CRLF, 0)
+                    .ok_or_else(|| vault_corrupted("boundary not found"))?;
+                blob_marker_pos = Self::find_marker(&contents, BLOB_BOUNDARY_CRLF, pos + VAULT_BOUNDARY_CRLF.len());
+                (pos, VAULT_BOUNDARY_CRLF.len(), BLOB_BOUNDARY_CRLF.len(), true)
+            }
+        };
+
+        // Extract header (before boundary)
+        let header_str = String::from_utf8(contents[..boundary_pos].to_vec())
+            .map_err(|e| vault_corrupted(format!("invalid header encoding: {}", e)))?;
+        let header: VaultHeader = serde_json::from_str(header_str.trim_end_matches('\r')).map_err(|e| {
+            let detail = format!("failed to parse header: {}", e);
+            if crlf_mangled { vault_corrupted_crlf(detail) } else { vault_corrupted(detail) }
+        })?;
+
+        // Encrypted manifest runs until the blob section, or to the end of the file
+        let manifest_start = boundary_pos + boundary_len;
+        let manifest_end = blob_marker_pos.unwrap_or(contents.len());
+
+        // Rewrites always use the LF boundary, so saving a mangled vault repairs its header
+        let mut header_and_boundary = contents[..boundary_pos].to_vec();
+        header_and_boundary.extend_from_slice(VAULT_BOUNDARY);
+
+        Ok(ContainerSections {
+            header,
+            header_and_boundary,
+            encrypted_manifest: contents[manifest_start..manifest_end].to_vec(),
+            manifest_start: manifest_start as u64,
+            blob_start: blob_marker_pos.map(|p| (p + blob_marker_len) as u64),
+            crlf_mangled,
+        })
+    }
+
+    /// Sibling file holding the blobs of a split-storage vault
+    fn blobs_path(vault_path: &Path) -> PathBuf {
+        vault_path.with_extension("blobs")
+    }
+
+    /// File and absolute offset where streamed blobs start, if the vault has any
+    fn blob_source(vault_path: &Path) -> Result<Option<(PathBuf, u64)>, String> {
+        let sections = Self::read_container(vault_path)?;
+        if sections.header.split_storage {
+            return Ok(Some((Self::blobs_path(vault_path), 0)));
+        }
+        Ok(sections.blob_start.map(|start| (vault_path.to_path_buf(), start)))
+    }
+
+    /// Save updated manifest to vault file
+    fn save_manifest(session: &VaultSession) -> Result<(), String> {
+        Self::write_container(session, |_| Ok(()))
+    }
+
+    /// Save the manifest and let `append` add new blob bytes after the existing ones.
+    /// Existing blobs are only copied when the manifest outgrows its slot; either way a
+    /// failure leaves a container that opens with the old or the new manifest.
+    fn write_container(
+        session: &VaultSession,
+        append: impl FnOnce(&mut File) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let paths = [session.vault_path.clone(), Self::blobs_path(&session.vault_path)];
+        Self::mark_own_write(&paths, true);
+        let result = Self::write_container_files(session, append);
+        Self::mark_own_write(&paths, false);
+        result
+    }
+
+    /// Flags `paths` as being written by us; clearing the flag records their new
+    /// mtime and length so the watcher recognises the resulting events
+    fn mark_own_write(paths: &[PathBuf], pending: bool) {
+        let Ok(mut writes) = OWN_WRITES.lock() else {
+            return;
+        };
+        for path in paths {
+            let write = writes.entry(Self::watch_key(path)).or_default();
+            write.pending = pending;
+            if !pending {
+                write.expected = Self::file_fingerprint(path);
+            }
+        }
+    }
+
+    fn file_fingerprint(path: &Path) -> Option<(SystemTime, u64)> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    /// Path with a canonical parent, matching the paths notify reports for the parent watch
+    fn watch_key(path: &Path) -> PathBuf {
+        match (path.parent().and_then(|p| std::fs::canonicalize(p).ok()), path.file_name()) {
+            (Some(parent), Some(name)) => parent.join(name),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Checks whether an event on `path` came from someone other than this app
+    fn external_change(path: &Path) -> Option<&'static str> {
+        let key = Self::watch_key(path);
+        let writes = OWN_WRITES.lock().ok()?;
+        let own = writes.get(&key).cloned().unwrap_or_default();
+        if own.pending {
+            return None;
+        }
+        match Self::file_fingerprint(&key) {
+            None => Some("deleted"),
+            Some(fingerprint) if own.expected != Some(fingerprint) => Some("modified"),
+            _ => None,
+        }
+    }
+
+    /// Watch a session's container (and blob file) and lock the session in `state_mux`
+    /// as soon as another process modifies or deletes it.
+    fn watch_container(window: Window, state_mux: StateSafe, session: &VaultSession) -> Result<(), String> {
+        let paths = [session.vault_path.clone(), Self::blobs_path(&session.vault_path)];
+        let watched: Vec<PathBuf> = paths.iter().map(|p| Self::watch_key(p)).collect();
+        Self::mark_own_write(&paths, false);
+
+        let vault_id = session.vault_id.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else {
+                return;
+            };
+            let Some((path, kind)) = event
+                .paths
+                .iter()
+                .filter(|p| watched.contains(p))
+                .find_map(|p| Some((p.clone(), Self::external_change(p)?)))
+            else {
+                return;
+            };
+
+            let Ok(mut state) = state_mux.lock() else {
+                return;
+            };
+            if let Some(mut session) = state.vault_sessions.remove(&vault_id) {
+                let _ = Self::lock_session(&mut session);
+                let _ = window.emit("vault_external_change", VaultExternalChange {
+                    vault_id: vault_id.clone(),
+                    path: path.to_string_lossy().to_string(),
+                    kind: kind.to_string(),
+                });
+            }
+        })
+        .map_err(|e| format!("Failed to create vault watcher: {}", e))?;
+
+        // Saves replace the container by rename, so the directory is watched
+        let parent = Self::watch_key(&session.vault_path)
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or("Vault path has no parent directory")?;
+        watcher
+            .watch(&parent, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch vault: {}", e))?;
+
+        VAULT_WATCHERS
+            .lock()
+            .map_err(|_| "Failed to acquire watcher lock".to_string())?
+            .insert(session.vault_id.clone(), watcher);
+        Ok(())
+    }
+
+    fn write_container_files(
+        session: &VaultSession,
+        append: impl FnOnce(&mut File) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let sections = Self::read_container(&session.vault_path)?;
+        if sections.header.split_storage {
+            return Self::write_split(session, &sections, append);
+        }
+
+        // With a blob section in place, new blobs go on the end of the file and the manifest
+        // is rewritten where it is. Only a manifest outgrowing its slot moves the blobs.
+        let Some(blob_start) = sections.blob_start.filter(|_| !sections.crlf_mangled) else {
+            return Self::rewrite_container(session, &sections, append);
+        };
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&session.vault_path)
+            .map_err(|e| format!("Failed to open vault for saving: {}", e))?;
+        let file_len = file.metadata()
+            .map_err(|e| format!("Failed to read vault metadata: {}", e))?
+            .len();
+        let appended = append(&mut file).and_then(|_| {
+            file.sync_all()
+                .map_err(|e| format!("Failed to flush vault file: {}", e))
+        });
+        if let Err(e) = appended {
+            let _ = file.set_len(file_len);
+            return Err(e);
+        }
+        drop(file);
+
+        let slot = (blob_start - sections.manifest_start) as usize - BLOB_BOUNDARY.len();
+        let encrypted_manifest = Self::encrypt_manifest(&session.manifest, &session.manifest_key, slot)?;
+        if encrypted_manifest.len() == slot {
+            // Keeps the appended blobs even on failure: the journaled manifest may use them
+            return Self::overwrite_manifest(&session.vault_path, sections.manifest_start, &encrypted_manifest);
+        }
+        let rewritten = Self::rewrite_container(session, &sections, |_| Ok(()));
+        if rewritten.is_err() {
+            // The old container is untouched and doesn't reference the new blobs
+            if let Ok(file) = std::fs::OpenOptions::new().write(true).open(&session.vault_path) {
+                let _ = file.set_len(file_len);
+            }
+        }
+        rewritten
+    }
+
+    /// Manifest ciphertext whose plaintext is padded with trailing spaces (which JSON
+    /// ignores) to `fit` bytes of ciphertext if it can, else to the next MANIFEST_SLOT
+    fn encrypt_manifest(manifest: &VaultManifest, key: &[u8], fit: usize) -> Result<Vec<u8>, String> {
+        let mut json = serde_json::to_vec(manifest)
+            .map_err(|e| format!("Serialization failed: {}", e))?;
+        let overhead = CHUNK_OVERHEAD as usize;
+        let target = if json.len() + overhead <= fit {
+            fit - overhead
+        } else {
+            json.len().div_ceil(MANIFEST_SLOT) * MANIFEST_SLOT
+        };
+        json.resize(target, b' ');
+
+        let nonce: [u8; 12] = rand::thread_rng().gen();
+        Self::encrypt_bytes_with_nonce(&json, key, &nonce)
+    }
 
-        new_file.write_all(&encrypted_manifest)
+    /// Copy of the manifest being overwritten in place, used by `open_vault` if the
+    /// overwrite was interrupted
+    fn journal_path(vault_path: &Path) -> PathBuf {
+        vault_path.with_extension("journal")
+    }
+
+    /// Overwrites the manifest slot at `offset` with `encrypted_manifest` of the same length.
+    /// The new manifest is journaled first, so a crash mid-write leaves one that decrypts.
+    fn overwrite_manifest(vault_path: &Path, offset: u64, encrypted_manifest: &[u8]) -> Result<(), String> {
+        let journal_path = Self::journal_path(vault_path);
+        let mut journal = File::create(&journal_path)
+            .map_err(|e| format!("Failed to create manifest journal: {}", e))?;
+        journal.write_all(encrypted_manifest)
+            .and_then(|_| journal.sync_all())
+            .map_err(|e| format!("Failed to write manifest journal: {}", e))?;
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(vault_path)
+            .map_err(|e| format!("Failed to open vault for saving: {}", e))?;
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.write_all(encrypted_manifest))
+            .and_then(|_| file.sync_all())
             .map_err(|e| format!("Failed to write manifest: {}", e))?;
 
+        let _ = std::fs::remove_file(&journal_path);
+        Ok(())
+    }
+
+    /// Writes header + manifest + existing blobs + `append` to a temp file and renames it
+    /// over the original, so a failure leaves the previous container untouched
+    fn rewrite_container(
+        session: &VaultSession,
+        sections: &ContainerSections,
+        append: impl FnOnce(&mut File) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let encrypted_manifest = Self::encrypt_manifest(&session.manifest, &session.manifest_key, 0)?;
+        let temp_path = session.vault_path.with_extension("vault.tmp");
+
+        let result = (|| {
+            let mut new_file = File::create(&temp_path)
+                .map_err(|e| format!("Failed to create vault file: {}", e))?;
+
+            new_file.write_all(&sections.header_and_boundary)
+                .map_err(|e| format!("Failed to write header: {}", e))?;
+            new_file.write_all(&encrypted_manifest)
+                .map_err(|e| format!("Failed to write manifest: {}", e))?;
+            let has_blobs = sections.blob_start.is_some()
+                || session.manifest.entries.values().any(|e| e.blob.is_some());
+            if has_blobs {
+                new_file.write_all(BLOB_BOUNDARY)
+                    .map_err(|e| format!("Failed to write boundary: {}", e))?;
+            }
+
+            if let Some(blob_start) = sections.blob_start {
+                let mut old = File::open(&session.vault_path)
+                    .map_err(|e| format!("Failed to open vault for saving: {}", e))?;
+                old.seek(SeekFrom::Start(blob_start))
+                    .map_err(|e| format!("Failed to read vault blobs: {}", e))?;
+                std::io::copy(&mut old, &mut new_file)
+                    .map_err(|e| format!("Failed to copy vault blobs: {}", e))?;
+            }
+
+            append(&mut new_file)?;
+
+            new_file.sync_all()
+                .map_err(|e| format!("Failed to flush vault file: {}", e))
+        })();
+
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e);
+        }
+
+        std::fs::rename(&temp_path, &session.vault_path)
+            .map_err(|e| format!("Failed to replace vault file: {}", e))?;
+        let _ = std::fs::remove_file(Self::journal_path(&session.vault_path));
+        Ok(())
+    }
+
+    /// Split-storage save: new blob bytes are appended to the `.blobs` file in place,
+    /// then only the small meta file is replaced. Existing blobs are never rewritten.
+    fn write_split(
+        session: &VaultSession,
+        sections: &ContainerSections,
+        append: impl FnOnce(&mut File) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let blobs_path = Self::blobs_path(&session.vault_path);
+        let mut blobs = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&blobs_path)
+            .map_err(|e| format!("Failed to open blob file: {}", e))?;
+        let blobs_len = blobs.metadata()
+            .map_err(|e| format!("Failed to read blob file metadata: {}", e))?
+            .len();
+
+        // Cut off partial chunks so a failed append leaves the blob file as it was
+        let appended = append(&mut blobs).and_then(|_| {
+            blobs.sync_all()
+                .map_err(|e| format!("Failed to flush blob file: {}", e))
+        });
+        if let Err(e) = appended {
+            let _ = blobs.set_len(blobs_len);
+            return Err(e);
+        }
+
+        let encrypted_manifest = Self::encrypt_manifest(&session.manifest, &session.manifest_key, 0)?;
+        let temp_path = session.vault_path.with_extension("meta.tmp");
+
+        let result = (|| {
+            let mut new_file = File::create(&temp_path)
+                .map_err(|e| format!("Failed to create vault file: {}", e))?;
+            new_file.write_all(&sections.header_and_boundary)
+                .map_err(|e| format!("Failed to write header: {}", e))?;
+            new_file.write_all(&encrypted_manifest)
+                .map_err(|e| format!("Failed to write manifest: {}", e))?;
+            new_file.sync_all()
+                .map_err(|e| format!("Failed to flush vault file: {}", e))
+        })();
+
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&temp_path);
+            let _ = blobs.set_len(blobs_len);
+            return Err(e);
+        }
+
+        std::fs::rename(&temp_path, &session.vault_path)
+            .map_err(|e| format!("Failed to replace vault file: {}", e))
+    }
+
+    /// Encrypt `source` chunk by chunk into `out`, binding each chunk to the entry and its index
+    fn encrypt_stream(
+        source: &Path,
+        file_size: u64,
+        entry_id: &str,
+        key: &[u8],
+        out: &mut File,
+    ) -> Result<String, String> {
+        let mut input = File::open(source)
+            .map_err(|e| format!("Failed to open source file: {}", e))?;
+        Self::encrypt_chunks(&mut input, file_size, entry_id, key, out)
+    }
+
+    /// Chunked encryption of `file_size` bytes from `input`, in the streamed blob layout.
+    /// Returns the SHA-256 of the plaintext that was encrypted.
+    fn encrypt_chunks(
+        input: &mut impl Read,
+        file_size: u64,
+        entry_id: &str,
+        key: &[u8],
+        out: &mut File,
+    ) -> Result<String, String> {
+        let cipher = ChaCha20Poly1305::new_from_slice(key)
+            .map_err(|e| format!("Invalid cipher key: {}", e))?;
+        let mut rng = rand::thread_rng();
+
+        let mut remaining = file_size;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; STREAM_CHUNK_SIZE as usize];
+        for index in 0..StreamedBlob::chunk_count(file_size, STREAM_CHUNK_SIZE) {
+            let plain_len = remaining.min(STREAM_CHUNK_SIZE) as usize;
+            input.read_exact(&mut buffer[..plain_len])
+                .map_err(|e| format!("Source file changed while ingesting: {}", e))?;
+            hasher.update(&buffer[..plain_len]);
+
+            let nonce_bytes: [u8; 12] = rng.gen();
+            let aad = format!("{}:{}", entry_id, index);
+            let ciphertext = cipher
+                .encrypt(Nonce::from_slice(&nonce_bytes), Payload { msg: &buffer[..plain_len], aad: aad.as_bytes() })
+                .map_err(|e| format!("Encryption failed: {}", e))?;
+
+            out.write_all(&nonce_bytes)
+                .and_then(|_| out.write_all(&ciphertext))
+                .map_err(|e| format!("Failed to write vault blob: {}", e))?;
+
+            remaining -= plain_len as u64;
+        }
+
+        buffer.zeroize();
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Hex SHA-256 of a file's contents, read in a streaming pass
+    fn file_sha256(path: &Path) -> Result<String, String> {
+        let mut file = File::open(path)
+            .map_err(|e| format!("Failed to open source file: {}", e))?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)
+            .map_err(|e| format!("Failed to read source file: {}", e))?;
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Overwrite a file with zeros, flush it, then remove it
+    fn shred_file(path: &Path, file_size: u64) -> std::io::Result<()> {
+        let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+        let zeros = vec![0u8; STREAM_CHUNK_SIZE as usize];
+        let mut remaining = file_size;
+        while remaining > 0 {
+            let n = remaining.min(STREAM_CHUNK_SIZE) as usize;
+            file.write_all(&zeros[..n])?;
+            remaining -= n as u64;
+        }
+        file.sync_all()?;
+        drop(file);
+        std::fs::remove_file(path)
+    }
+
+    /// Read the encrypted bytes of a streamed blob and decrypt them chunk by chunk
+    fn read_streamed_blob(
+        session: &VaultSession,
+        entry: &VaultEntry,
+        blob: &StreamedBlob,
+        mut sink: impl FnMut(&[u8]) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let (blob_path, blob_start) = Self::blob_source(&session.vault_path)?
+            .ok_or("Vault has no blob section")?;
+
+        let mut file = File::open(&blob_path)
+            .map_err(|e| format!("Failed to open vault: {}", e))?;
+        file.seek(SeekFrom::Start(blob_start + blob.offset))
+            .map_err(|e| format!("Failed to seek vault blob: {}", e))?;
+
+        let cipher = ChaCha20Poly1305::new_from_slice(&session.blob_key)
+            .map_err(|e| format!("Invalid cipher key: {}", e))?;
+
+        let mut remaining = entry.file_size;
+        for index in 0..StreamedBlob::chunk_count(entry.file_size, blob.chunk_size) {
+            let plain_len = remaining.min(blob.chunk_size);
+            let mut chunk = vec![0u8; (plain_len + CHUNK_OVERHEAD) as usize];
+            file.read_exact(&mut chunk)
+                .map_err(|e| format!("Failed to read vault blob: {}", e))?;
+
+            let aad = format!("{}:{}", entry.id, index);
+            let plaintext = cipher
+                .decrypt(Nonce::from_slice(&chunk[..12]), Payload { msg: &chunk[12..], aad: aad.as_bytes() })
+                .map_err(|e| format!("Decryption failed: {}", e))?;
+            sink(&plaintext)?;
+
+            remaining -= plain_len;
+        }
+
+        Ok(())
+    }
+
+    /// Bytes currently in the vault's blob section, i.e. the offset the next blob gets
+    fn blob_section_len(vault_path: &Path) -> Result<u64, String> {
+        match Self::blob_source(vault_path)? {
+            Some((path, start)) => Ok(std::fs::metadata(&path)
+                .map_err(|e| format!("Failed to read vault metadata: {}", e))?
+                .len()
+                - start),
+            None => Ok(0),
+        }
+    }
+
+    /// Copies entries from `source` into `target`, re-encrypting them under the target's key.
+    /// Streamed entries are re-encrypted chunk by chunk, so no plaintext is ever written to disk.
+    /// A split-storage target gets inline entries as streamed blobs in its `.blobs` file.
+    pub fn copy_entries(
+        source: &VaultSession,
+        target: &mut VaultSession,
+        entry_ids: &[String],
+    ) -> Result<(), String> {
+        if source.locked || target.locked {
+            return Err("Vault is locked".to_string());
+        }
+
+        enum PendingBlob {
+            Stored(VaultEntry, StreamedBlob),
+            Inline(String, Zeroizing<Vec<u8>>),
+        }
+
+        let previous_manifest = target.manifest.clone();
+        let split_target = Self::is_split_storage(&target.vault_path)?;
+        let mut next_offset = Self::blob_section_len(&target.vault_path)?;
+        let mut pending: Vec<PendingBlob> = Vec::new();
+
+        for entry_id in entry_ids {
+            let entry = source.manifest.entries.get(entry_id).ok_or("Entry not found")?;
+            let mut copy = entry.clone();
+
+            if let Some(blob) = &entry.blob {
+                let moved = StreamedBlob {
+                    offset: next_offset,
+                    length: blob.length,
+                    chunk_size: blob.chunk_size,
+                };
+                next_offset += blob.length;
+                copy.blob = Some(moved);
+                pending.push(PendingBlob::Stored(entry.clone(), blob.clone()));
+            } else if split_target {
+                let plaintext = Zeroizing::new(Self::decrypt_entry(source, entry_id)?);
+                let length = StreamedBlob::encrypted_len(plaintext.len() as u64, STREAM_CHUNK_SIZE);
+                copy.blob = Some(StreamedBlob {
+                    offset: next_offset,
+                    length,
+                    chunk_size: STREAM_CHUNK_SIZE,
+                });
+                next_offset += length;
+                copy.nonce = String::new();
+                copy.encrypted_data = String::new();
+                pending.push(PendingBlob::Inline(entry_id.clone(), plaintext));
+            } else {
+                let plaintext = Zeroizing::new(Self::decrypt_entry(source, entry_id)?);
+                let nonce_bytes: [u8; 12] = rand::thread_rng().gen();
+                let encrypted = Self::encrypt_bytes_with_nonce(&plaintext, &target.blob_key, &nonce_bytes)?;
+                copy.nonce = hex::encode(nonce_bytes);
+                copy.encrypted_data = general_purpose::STANDARD.encode(&encrypted);
+            }
+
+            target.manifest.entries.insert(entry_id.clone(), copy);
+            target.manifest.access_log.push(AuditLog {
+                timestamp: Utc::now().to_rfc3339(),
+                action: "transfer_in".to_string(),
+                entry_id: Some(entry_id.clone()),
+                status: "success".to_string(),
+            });
+        }
+
+        let target_key = target.blob_key.clone();
+        let result = Self::write_container(target, |out| {
+            let cipher = ChaCha20Poly1305::new_from_slice(&target_key)
+                .map_err(|e| format!("Invalid cipher key: {}", e))?;
+            let mut rng = rand::thread_rng();
+            for item in &pending {
+                let (entry, blob) = match item {
+                    PendingBlob::Stored(entry, blob) => (entry, blob),
+                    PendingBlob::Inline(entry_id, plaintext) => {
+                        let size = plaintext.len() as u64;
+                        Self::encrypt_chunks(&mut plaintext.as_slice(), size, entry_id, &target_key, out)?;
+                        continue;
+                    }
+                };
+                let mut index = 0;
+                Self::read_streamed_blob(source, entry, blob, |chunk| {
+                    let nonce_bytes: [u8; 12] = rng.gen();
+                    let aad = format!("{}:{}", entry.id, index);
+                    let ciphertext = cipher
+                        .encrypt(Nonce::from_slice(&nonce_bytes), Payload { msg: chunk, aad: aad.as_bytes() })
+                        .map_err(|e| format!("Encryption failed: {}", e))?;
+                    index += 1;
+                    out.write_all(&nonce_bytes)
+                        .and_then(|_| out.write_all(&ciphertext))
+                        .map_err(|e| format!("Failed to write vault blob: {}", e))
+                })?;
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            target.manifest = previous_manifest;
+            return Err(e);
+        }
+
+        target.touch();
         Ok(())
     }
 
+    /// Moves entries into a brand-new vault at `new_vault_path`. The new vault is fully
+    /// written before anything is removed from `session`, so a failure never loses an entry.
+    /// The new vault keeps this one's Argon2 params, and its storage format unless
+    /// `split_storage` picks one.
+    pub fn split_vault(
+        session: &mut VaultSession,
+        new_vault_path: &Path,
+        new_password: &str,
+        entry_ids: &[String],
+        split_storage: Option<bool>,
+    ) -> Result<(String, Vec<String>), String> {
+        if session.locked {
+            return Err("Vault is locked".to_string());
+        }
+        if entry_ids.is_empty() {
+            return Err("No entries selected".to_string());
+        }
+        if let Some(missing) = entry_ids.iter().find(|id| !session.manifest.entries.contains_key(*id)) {
+            return Err(format!("Entry not found: {}", missing));
+        }
+
+        let header = Self::read_container(&session.vault_path)?.header;
+        let split_storage = split_storage.unwrap_or(header.split_storage);
+        let (new_vault_id, recovery_codes) =
+            Self::create_vault(new_vault_path, new_password, None, split_storage, Some(header.argon2_params))?;
+
+        let mut target = Self::open_vault(new_vault_path, new_password)?;
+        let copied = Self::copy_entries(session, &mut target, entry_ids);
+        Self::lock_session(&mut target)?;
+        if let Err(e) = copied {
+            let _ = std::fs::remove_file(new_vault_path);
+            let _ = std::fs::remove_file(Self::blobs_path(new_vault_path));
+            return Err(e);
+        }
+
+        Self::auto_backup(session)?;
+        for entry_id in entry_ids {
+            session.manifest.entries.remove(entry_id);
+            session.manifest.access_log.push(AuditLog {
+                timestamp: Utc::now().to_rfc3339(),
+                action: "transfer_out".to_string(),
+                entry_id: Some(entry_id.clone()),
+                status: "success".to_string(),
+            });
+        }
+
+        session.touch();
+        Self::save_manifest(session)
+            .map_err(|e| format!("Entries were copied to the new vault but not removed from this one: {}", e))?;
+
+        Ok((new_vault_id, recovery_codes))
+    }
+
     /// Read encrypted blob for entry (stub)
     fn read_encrypted_blob(_session: &VaultSession, _entry_id: &str) -> Result<Vec<u8>, String> {
         // In production, read from vault container file
         Ok(Vec::new())
     }
 
+    /// Coarse gallery bucket for an entry: images, documents, archives or other
+    fn mime_category(entry: &VaultEntry) -> &'static str {
+        let mime = match entry.mime_type.as_deref() {
+            Some(mime) if mime != "application/octet-stream" => mime,
+            _ => {
+                let ext = Path::new(&entry.filename)
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("");
+                mime_from_extension(ext)
+            }
+        };
+
+        if mime.starts_with("image/") {
+            "images"
+        } else if mime.starts_with("text/")
+            || mime == "application/pdf"
+            || mime == "application/rtf"
+            || mime.starts_with("application/msword")
+            || mime.starts_with("application/vnd.openxmlformats-officedocument")
+            || mime.starts_with("application/vnd.ms-")
+            || mime.starts_with("application/vnd.oasis.opendocument")
+        {
+            "documents"
+        } else if matches!(
+            mime,
+            "application/zip"
+                | "application/gzip"
+                | "application/x-tar"
+                | "application/x-7z-compressed"
+                | "application/x-rar-compressed"
+                | "application/vnd.rar"
+                | "application/x-bzip2"
+                | "application/x-xz"
+        ) {
+            "archives"
+        } else {
+            "other"
+        }
+    }
+
+    /// Entries grouped by coarse MIME category, each group in display order
+    pub fn entries_by_category(manifest: &VaultManifest) -> HashMap<String, Vec<VaultEntrySummary>> {
+        let mut groups: HashMap<String, Vec<VaultEntrySummary>> = HashMap::new();
+        for entry in Self::sorted_entries(manifest) {
+            groups
+                .entry(Self::mime_category(&entry).to_string())
+                .or_default()
+                .push(VaultEntrySummary::from(&entry));
+        }
+        groups
+    }
+
     /// Guess MIME type from file extension
     fn guess_mime_type(path: &Path) -> Option<String> {
         path.extension()
@@ -573,6 +2868,11 @@ impl Vault {
 
     /// Generate recovery codes (simplified: 12-word phrases)
     fn generate_recovery_codes() -> Vec<String> {
+        Self::random_codes(4, 3)
+    }
+
+    /// `count` codes of `words_per_code` random words joined by dashes
+    fn random_codes(count: usize, words_per_code: usize) -> Vec<String> {
         let words = vec![
             "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel",
             "india", "julia", "kilo", "lima", "mike", "november", "oscar", "papa",
@@ -581,9 +2881,9 @@ impl Vault {
         ];
 
         let mut rng = rand::thread_rng();
-        (0..4)
+        (0..count)
             .map(|_| {
-                (0..3)
+                (0..words_per_code)
                     .map(|_| {
                         let idx = rng.gen_range(0..words.len());
                         words[idx].to_string()
@@ -595,8 +2895,163 @@ impl Vault {
     }
 }
 
+/// Error for a container whose structure can't be parsed. It starts with `VaultCorrupted:`
+/// so the UI can tell it apart from other failures and offer to restore a backup.
+fn vault_corrupted(detail: impl std::fmt::Display) -> String {
+    format!("VaultCorrupted: {}. The container file is damaged; restore it from a backup", detail)
+}
+
+/// `vault_corrupted` for a container whose markers were found with CRLF line endings, so the
+/// damage most likely came from a text editor or transfer rather than truncation
+fn vault_corrupted_crlf(detail: impl std::fmt::Display) -> String {
+    format!(
+        "VaultCorrupted: {}. The container file is damaged by a text editor or transfer that \
+         changed its line endings to CRLF; restore it from a backup",
+        detail
+    )
+}
+
+/// Reverses a CRLF conversion by turning every `\r\n` back into `\n`
+fn crlf_to_lf(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut bytes = data.iter().peekable();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'\r' && bytes.peek() == Some(&&b'\n') {
+            continue;
+        }
+        out.push(byte);
+    }
+    out
+}
+
 // ========== Tauri Command Handlers ==========
 
+/// Run `f` against the unlocked session registered under `vault_id`.
+/// Expired sessions are locked and removed.
+fn with_session<T>(
+    state_mux: &StateSafe,
+    vault_id: &str,
+    f: impl FnOnce(&mut VaultSession) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut state = state_mux.lock().map_err(|_| "Failed to acquire state lock".to_string())?;
+
+    let expired = match state.vault_sessions.get(vault_id) {
+        Some(session) => session.is_expired(SESSION_INACTIVITY_SECS),
+        None => return Err("Vault is not open".to_string()),
+    };
+    if expired {
+        if let Some(mut session) = state.vault_sessions.remove(vault_id) {
+            Vault::lock_session(&mut session)?;
+        }
+        return Err("Vault session expired".to_string());
+    }
+
+    let session = state.vault_sessions.get_mut(vault_id).ok_or("Vault is not open")?;
+    if session.locked {
+        return Err("Vault is locked".to_string());
+    }
+    if session.busy {
+        return Err("Vault is busy with another operation".to_string());
+    }
+    f(session)
+}
+
+/// Run `f` against a detached session without holding the state lock, for work that
+/// takes a while (Argon2, streaming or re-encrypting files). Only the keys are copied; the
+/// manifest, with any inline ciphertext, is moved out and back. The session is marked busy
+/// meanwhile so nothing else reads or changes it. Operations roll their in-memory changes
+/// back on failure, so the detached session always matches the container and replaces the
+/// stored one afterwards. A session locked in the meantime stays locked.
+fn with_session_detached<T>(
+    state_mux: &StateSafe,
+    vault_id: &str,
+    f: impl FnOnce(&mut VaultSession) -> Result<T, String>,
+) -> Result<T, String> {
+    let working = with_session(state_mux, vault_id, |session| {
+        session.busy = true;
+        let manifest = std::mem::replace(
+            &mut session.manifest,
+            VaultManifest {
+                entries: HashMap::new(),
+                last_accessed: String::new(),
+                access_log: Vec::new(),
+                archive_key: None,
+            },
+        );
+        Ok(VaultSession {
+            vault_id: session.vault_id.clone(),
+            vault_path: session.vault_path.clone(),
+            manifest_key: session.manifest_key.clone(),
+            blob_key: session.blob_key.clone(),
+            manifest,
+            locked: false,
+            last_accessed: session.last_accessed,
+            busy: false,
+        })
+    })?;
+
+    let mut guard = DetachedSession { state_mux, vault_id, working: Some(working) };
+    f(guard.working.as_mut().expect("set above"))
+}
+
+/// Run `f` for a command that unlocks with `password` rather than a vault_id. If the same
+/// vault is open in the app state, `f` runs against that session instead, so its manifest
+/// can't go stale and overwrite the change on its next save.
+fn with_password_session<T>(
+    state_mux: &StateSafe,
+    vault_path: &Path,
+    password: &str,
+    f: impl FnOnce(&mut VaultSession) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut session = Vault::open_vault(vault_path, password)?;
+    let same_file = |open: &Path| match (std::fs::canonicalize(open), std::fs::canonicalize(vault_path)) {
+        (Ok(open), Ok(requested)) => open == requested,
+        _ => false,
+    };
+    let is_open = {
+        let state = state_mux.lock().map_err(|_| "Failed to acquire state lock".to_string())?;
+        state
+            .vault_sessions
+            .get(&session.vault_id)
+            .is_some_and(|open| !open.locked && same_file(&open.vault_path))
+    };
+    if is_open {
+        with_session_detached(state_mux, &session.vault_id.clone(), f)
+    } else {
+        f(&mut session)
+    }
+}
+
+/// Puts a detached session back when dropped and clears `busy`, also when `f` panics
+struct DetachedSession<'a> {
+    state_mux: &'a StateSafe,
+    vault_id: &'a str,
+    working: Option<VaultSession>,
+}
+
+impl Drop for DetachedSession<'_> {
+    fn drop(&mut self) {
+        let mut state = match self.state_mux.lock() {
+            Ok(state) => state,
+            Err(poison) => poison.into_inner(),
+        };
+        let Some(session) = state.vault_sessions.get_mut(self.vault_id) else {
+            return;
+        };
+        session.busy = false;
+        if session.locked {
+            return;
+        }
+        match self.working.take() {
+            Some(working) if !std::thread::panicking() => *session = working,
+            // A panic may have left the manifest half-changed, so the vault must be reopened
+            _ => {
+                let _ = Vault::lock_session(session);
+            }
+        }
+    }
+}
+
 #[tauri::command]
 pub fn vault_check_exists(vault_path: String) -> Result<bool, String> {
     Ok(Path::new(&vault_path).exists())
@@ -618,7 +3073,7 @@ pub fn vault_list_vaults() -> Result<Vec<(String, String)>, String> {
     for entry in entries {
         let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
         let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) == Some("vault") {
+        if path.extension().and_then(|e| e.to_str()).is_some_and(|ext| ext == "vault" || ext == "meta") {
             let name = path
                 .file_stem()
                 .and_then(|s| s.to_str())
@@ -633,12 +3088,65 @@ pub fn vault_list_vaults() -> Result<Vec<(String, String)>, String> {
     Ok(vaults)
 }
 
+/// Vault containers under `root`, found by their plaintext headers. Needs no password;
+/// files that don't start with a valid header are skipped.
+#[tauri::command]
+pub fn discover_vaults(
+    state_mux: State<'_, StateSafe>,
+    root: String,
+    walk_options: Option<WalkOptions>,
+) -> Result<Vec<DiscoveredVault>, String> {
+    let root = resolve_path_in_state(&root, &state_mux).map_err(|e| e.to_string())?;
+    find_vaults(&root, &walk_options.unwrap_or_default())
+}
+
+/// `discover_vaults` for an already resolved `root`
+fn find_vaults(root: &str, options: &WalkOptions) -> Result<Vec<DiscoveredVault>, String> {
+    if !Path::new(root).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let mut vaults: Vec<DiscoveredVault> = options
+        .walk(root)
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|entry| {
+            let header = Vault::peek_header(entry.path())?;
+            Some(DiscoveredVault {
+                path: entry.path().to_string_lossy().to_string(),
+                vault_id: header.vault_id,
+                version: header.version,
+                created_at: header.created_at,
+                split_storage: header.split_storage,
+                has_recovery: header.recovery.is_some(),
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            })
+        })
+        .collect();
+
+    vaults.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(vaults)
+}
+
 #[tauri::command]
 pub fn vault_create(
     vault_path: String,
     password: String,
     vault_name: Option<String>,
+    split_storage: Option<bool>,
+    calibrate_ms: Option<u64>,
+    min_password_score: Option<u8>,
 ) -> Result<(String, Vec<String>), String> {
+    if let Some(min_score) = min_password_score {
+        let strength = password_strength(&password);
+        if strength.score < min_score {
+            let hint = strength.warning.or_else(|| strength.suggestions.first().cloned());
+            return Err(match hint {
+                Some(hint) => format!("Password is too weak ({}/4): {}", strength.score, hint),
+                None => format!("Password is too weak ({}/4)", strength.score),
+            });
+        }
+    }
+
     // Ensure vault directory exists
     if let Some(parent) = Path::new(&vault_path).parent() {
         if !parent.exists() {
@@ -646,23 +3154,74 @@ pub fn vault_create(
         }
     }
     
-    Vault::create_vault(Path::new(&vault_path), &password, vault_name)
+    // Tune the KDF to this machine when asked; the params are stored in the header
+    let argon2_params = match calibrate_ms {
+        Some(target_ms) => Some(Vault::calibrate_argon2(target_ms)?.params),
+        None => None,
+    };
+
+    Vault::create_vault(
+        Path::new(&vault_path),
+        &password,
+        vault_name,
+        split_storage.unwrap_or(false),
+        argon2_params,
+    )
 }
 
 #[tauri::command]
-pub fn vault_open(vault_path: String, password: String) -> Result<(String, Vec<VaultEntry>), String> {
+pub fn vault_open(
+    window: Window,
+    state_mux: State<'_, StateSafe>,
+    vault_path: String,
+    password: String,
+) -> Result<(String, Vec<VaultEntry>), String> {
     // Open vault session
     let session = Vault::open_vault(Path::new(&vault_path), &password)?;
-    
+    register_session(window, &state_mux, session)
+}
+
+/// Like `vault_open`, but unlocks with a standalone recovery code instead of the password
+#[tauri::command(async)]
+pub fn vault_open_with_recovery_code(
+    window: Window,
+    state_mux: State<'_, StateSafe>,
+    vault_path: String,
+    code: String,
+) -> Result<(String, Vec<VaultEntry>), String> {
+    let session = Vault::open_with_recovery_code(Path::new(&vault_path), &code)?;
+    register_session(window, &state_mux, session)
+}
+
+/// Keep an unlocked session in the app state and watch its container; returns the vault
+/// id and sorted entries
+fn register_session(
+    window: Window,
+    state_mux: &StateSafe,
+    session: VaultSession,
+) -> Result<(String, Vec<VaultEntry>), String> {
     let vault_id = session.vault_id.clone();
-    let entries = session.manifest.entries.values().cloned().collect();
+    let entries = Vault::sorted_entries(&session.manifest);
+
+    // Lock the session if another process rewrites the container underneath it
+    Vault::watch_container(window, state_mux.clone(), &session)?;
+
+    // Keep the session so vault_id based commands can use it
+    let mut state = state_mux.lock().map_err(|_| "Failed to acquire state lock".to_string())?;
+    state.vault_sessions.insert(vault_id.clone(), session);
     
     Ok((vault_id, entries))
 }
 
 #[tauri::command]
-pub fn vault_lock(vault_id: String) -> Result<String, String> {
-    // In production, retrieve session from app state and lock it
+pub fn vault_lock(state_mux: State<'_, StateSafe>, vault_id: String) -> Result<String, String> {
+    let mut state = state_mux.lock().map_err(|_| "Failed to acquire state lock".to_string())?;
+    if let Some(mut session) = state.vault_sessions.remove(&vault_id) {
+        Vault::lock_session(&mut session)?;
+    }
+    if let Ok(mut watchers) = VAULT_WATCHERS.lock() {
+        watchers.remove(&vault_id);
+    }
     Ok(format!("Vault {} locked", vault_id))
 }
 
@@ -670,22 +3229,217 @@ pub fn vault_lock(vault_id: String) -> Result<String, String> {
 pub fn vault_list_entries(vault_path: String, password: String) -> Result<Vec<VaultEntry>, String> {
     // Open vault to get entries (in production, retrieve from app state)
     let session = Vault::open_vault(Path::new(&vault_path), &password)?;
-    Ok(session.manifest.entries.values().cloned().collect())
+    Ok(Vault::sorted_entries(&session.manifest))
 }
 
+/// Entries of an open vault grouped into images, documents, archives and other
 #[tauri::command]
-pub fn vault_import_file(
-    vault_path: String,
-    password: String,
-    source_path: String,
-    tags: Vec<String>,
-    delete_after: Option<bool>,
-) -> Result<String, String> {
-    // Open vault session
-    let mut session = Vault::open_vault(Path::new(&vault_path), &password)?;
-    
-    // Import file
-    let entry_id = Vault::import_file(&mut session, Path::new(&source_path), tags)?;
+pub fn vault_entries_by_category(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+) -> Result<HashMap<String, Vec<VaultEntrySummary>>, String> {
+    with_session(&state_mux, &vault_id, |session| {
+        Ok(Vault::entries_by_category(&session.manifest))
+    })
+}
+
+/// Audit log entries newest first, optionally only one `action`, from `offset` up to `limit`
+#[tauri::command]
+pub fn vault_audit_page(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    action_filter: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<AuditPage, String> {
+    with_session(&state_mux, &vault_id, |session| {
+        Ok(audit_page(
+            &session.manifest.access_log,
+            action_filter.as_deref(),
+            offset.unwrap_or(0),
+            limit.unwrap_or(DEFAULT_AUDIT_PAGE_SIZE),
+        ))
+    })
+}
+
+/// One page of `log`, newest first, with the counts taken over the whole log
+fn audit_page(log: &[AuditLog], action_filter: Option<&str>, offset: usize, limit: usize) -> AuditPage {
+    let mut counts_by_action: HashMap<String, usize> = HashMap::new();
+    for entry in log {
+        *counts_by_action.entry(entry.action.clone()).or_insert(0) += 1;
+    }
+
+    let matching = log
+        .iter()
+        .rev()
+        .filter(|e| !matches!(action_filter, Some(action) if e.action != action));
+    let total = matching.clone().count();
+    let entries = matching.skip(offset).take(limit).cloned().collect();
+
+    AuditPage { entries, total, counts_by_action }
+}
+
+/// Checks that a previously exported file is bit-for-bit the entry's plaintext
+#[tauri::command]
+pub fn vault_verify_export(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    entry_id: String,
+    exported_path: String,
+) -> Result<ExportVerification, String> {
+    with_session(&state_mux, &vault_id, |session| {
+        Vault::verify_export(session, &entry_id, Path::new(&exported_path))
+    })
+}
+
+/// Moves `entry_ids` out of an open vault into a new one, with split storage if `split_storage`
+/// is set; returns the new vault's id and recovery codes
+#[tauri::command(async)]
+pub fn vault_split(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    new_vault_path: String,
+    new_password: String,
+    entry_ids: Vec<String>,
+    split_storage: Option<bool>,
+) -> Result<(String, Vec<String>), String> {
+    with_session_detached(&state_mux, &vault_id, |session| {
+        Vault::split_vault(session, Path::new(&new_vault_path), &new_password, &entry_ids, split_storage)
+    })
+}
+
+/// Storage overhead of an open vault, to weigh the inline format against split storage
+#[tauri::command]
+pub fn vault_diagnostics(state_mux: State<'_, StateSafe>, vault_id: String) -> Result<VaultDiagnostics, String> {
+    with_session(&state_mux, &vault_id, |session| Vault::diagnostics(session))
+}
+
+/// Matching key for an entry across vaults: its content hash, or its id when unknown
+fn diff_key(entry: &VaultEntry) -> String {
+    match &entry.content_hash {
+        Some(hash) => format!("sha256:{}", hash),
+        None => format!("id:{}", entry.id),
+    }
+}
+
+/// Metadata fields that differ between two copies of an entry
+fn entry_differences(a: &VaultEntry, b: &VaultEntry) -> Vec<String> {
+    let mut differences = Vec::new();
+    if a.filename != b.filename {
+        differences.push("filename".to_string());
+    }
+    let mut tags_a = a.tags.clone();
+    let mut tags_b = b.tags.clone();
+    tags_a.sort();
+    tags_b.sort();
+    if tags_a != tags_b {
+        differences.push("tags".to_string());
+    }
+    if a.original_path != b.original_path {
+        differences.push("original_path".to_string());
+    }
+    if a.pinned != b.pinned {
+        differences.push("pinned".to_string());
+    }
+    differences
+}
+
+/// Compares two manifests by content hash (or id). Only metadata is read; nothing is decrypted.
+pub fn diff_manifests(a: &VaultManifest, b: &VaultManifest) -> VaultDiff {
+    let mut remaining_b: HashMap<String, &VaultEntry> =
+        b.entries.values().map(|e| (diff_key(e), e)).collect();
+    let mut diff = VaultDiff::default();
+
+    for entry in a.entries.values() {
+        match remaining_b.remove(&diff_key(entry)) {
+            Some(other) => diff.in_both.push(VaultEntryPair {
+                a: VaultEntrySummary::from(entry),
+                b: VaultEntrySummary::from(other),
+                differences: entry_differences(entry, other),
+            }),
+            None => diff.only_in_a.push(VaultEntrySummary::from(entry)),
+        }
+    }
+    diff.only_in_b = remaining_b.into_values().map(VaultEntrySummary::from).collect();
+
+    diff.only_in_a.sort_by(|x, y| x.filename.cmp(&y.filename));
+    diff.only_in_b.sort_by(|x, y| x.filename.cmp(&y.filename));
+    diff.in_both.sort_by(|x, y| x.a.filename.cmp(&y.a.filename));
+    diff
+}
+
+/// Diffs two vaults (e.g. a primary and its backup) without exposing any plaintext
+#[tauri::command(async)]
+pub fn vault_diff(
+    vault_a: String,
+    pw_a: String,
+    vault_b: String,
+    pw_b: String,
+) -> Result<VaultDiff, String> {
+    let mut session_a = Vault::open_vault(Path::new(&vault_a), &pw_a)?;
+    let mut session_b = match Vault::open_vault(Path::new(&vault_b), &pw_b) {
+        Ok(session) => session,
+        Err(e) => {
+            Vault::lock_session(&mut session_a)?;
+            return Err(e);
+        }
+    };
+
+    let diff = diff_manifests(&session_a.manifest, &session_b.manifest);
+
+    Vault::lock_session(&mut session_a)?;
+    Vault::lock_session(&mut session_b)?;
+    Ok(diff)
+}
+
+/// Emit `vault_near_capacity` when an import moves usage across the warning threshold
+fn warn_if_near_capacity(window: &Window, session: &VaultSession, used_before: u64) {
+    if let Some(warning) = capacity_warning(session, used_before) {
+        let _ = window.emit("vault_near_capacity", warning);
+    }
+}
+
+/// The `vault_near_capacity` payload, if usage crossed the threshold since `used_before`
+fn capacity_warning(session: &VaultSession, used_before: u64) -> Option<VaultCapacityWarning> {
+    let threshold = MAX_VAULT_SIZE / 100 * CAPACITY_WARNING_PERCENT.load(Ordering::Relaxed);
+    let used_bytes = Vault::used_bytes(session);
+    (used_before < threshold && used_bytes >= threshold).then(|| VaultCapacityWarning {
+        vault_id: session.vault_id.clone(),
+        used_bytes,
+        max_bytes: MAX_VAULT_SIZE,
+        percent_used: used_bytes as f64 * 100.0 / MAX_VAULT_SIZE as f64,
+    })
+}
+
+/// Set the usage percentage (1-100) at which imports emit `vault_near_capacity`
+#[tauri::command]
+pub fn vault_set_capacity_warning(percent: u64) -> Result<(), String> {
+    if !(1..=100).contains(&percent) {
+        return Err("Threshold must be between 1 and 100 percent".to_string());
+    }
+    CAPACITY_WARNING_PERCENT.store(percent, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn vault_import_file(
+    window: Window,
+    state_mux: State<'_, StateSafe>,
+    vault_path: String,
+    password: String,
+    source_path: String,
+    tags: Vec<String>,
+    delete_after: Option<bool>,
+) -> Result<String, String> {
+    let source_path = resolve_path_in_state(&source_path, &state_mux).map_err(|e| e.to_string())?;
+
+    // Import file
+    let entry_id = with_password_session(&state_mux, Path::new(&vault_path), &password, |session| {
+        let used_before = Vault::used_bytes(session);
+        let entry_id = Vault::import_file(session, Path::new(&source_path), tags)?;
+        warn_if_near_capacity(&window, session, used_before);
+        Ok(entry_id)
+    })?;
     
     // Optionally delete original file after successful import
     if delete_after.unwrap_or(false) {
@@ -698,36 +3452,1626 @@ pub fn vault_import_file(
 
 #[tauri::command]
 pub fn vault_export_file(
+    state_mux: State<'_, StateSafe>,
     vault_path: String,
     password: String,
     entry_id: String,
     output_path: String,
 ) -> Result<(), String> {
-    // Open vault session
-    let mut session = Vault::open_vault(Path::new(&vault_path), &password)?;
-    
-    // Export file
-    Vault::export_file(&mut session, &entry_id, Path::new(&output_path))?;
-    
-    Ok(())
+    export_entry(&state_mux, Path::new(&vault_path), &password, &entry_id, &output_path)
+}
+
+/// `vault_export_file` without the Tauri state wrapper
+fn export_entry(
+    state_mux: &StateSafe,
+    vault_path: &Path,
+    password: &str,
+    entry_id: &str,
+    output_path: &str,
+) -> Result<(), String> {
+    let output_path = resolve_path_in_state(output_path, state_mux).map_err(|e| e.to_string())?;
+    with_password_session(state_mux, vault_path, password, |session| {
+        Vault::export_file(session, entry_id, Path::new(&output_path))
+    })
 }
 
 #[tauri::command]
 pub fn vault_delete_entry(
+    state_mux: State<'_, StateSafe>,
     vault_path: String,
     password: String,
     entry_id: String,
 ) -> Result<(), String> {
-    let mut session = Vault::open_vault(&Path::new(&vault_path), &password)?;
-    Vault::delete_entry(&mut session, &entry_id)?;
+    with_password_session(&state_mux, Path::new(&vault_path), &password, |session| {
+        Vault::delete_entry(session, &entry_id)
+    })
+}
+
+/// Issues standalone recovery codes for an open vault after checking `password`. Any one
+/// of them unlocks the vault through `vault_open_with_recovery_code`.
+#[tauri::command(async)]
+pub fn vault_generate_recovery_codes(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    password: String,
+) -> Result<Vec<String>, String> {
+    with_session_detached(&state_mux, &vault_id, |session| {
+        Vault::issue_recovery_codes(session, &password)
+    })
+}
+
+/// Argon2id params that take about `target_ms` to derive on this machine
+#[tauri::command(async)]
+pub fn calibrate_argon2(target_ms: u64) -> Result<Argon2Calibration, String> {
+    Vault::calibrate_argon2(target_ms)
+}
+
+/// Re-encrypts an open vault under new Argon2 params (the defaults if none are given)
+/// after checking `password`. The recovery codes keep working only if they are passed in;
+/// otherwise call `vault_rotate_recovery_codes` afterwards to issue new ones.
+#[tauri::command(async)]
+pub fn vault_upgrade_kdf(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    password: String,
+    new_params: Option<String>,
+    recovery_codes: Option<Vec<String>>,
+) -> Result<(), String> {
+    let new_params = new_params.unwrap_or_else(|| DEFAULT_ARGON2_PARAMS.to_string());
+    with_session_detached(&state_mux, &vault_id, |session| {
+        Vault::upgrade_kdf(session, &password, &new_params, recovery_codes.as_deref())
+    })
+}
+
+/// Replaces an open vault's recovery codes with new ones after checking `password`
+#[tauri::command(async)]
+pub fn vault_rotate_recovery_codes(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    password: String,
+) -> Result<Vec<String>, String> {
+    with_session_detached(&state_mux, &vault_id, |session| {
+        Vault::rotate_recovery_codes(session, &password)
+    })
+}
+
+/// Whether `recovery_codes` open the vault at `vault_path`, so users can confirm they saved
+/// them correctly. Vaults created before recovery codes were bound to the key return an error.
+#[tauri::command]
+pub fn vault_test_recovery(vault_path: String, recovery_codes: Vec<String>) -> Result<bool, String> {
+    Vault::test_recovery(Path::new(&vault_path), &recovery_codes)
+}
+
+/// Store recovery codes in a PIN-encrypted file, e.g. on a USB stick. The PIN is
+/// stretched with the Argon2 params of the vault at `vault_path`.
+#[tauri::command(async)]
+pub fn vault_export_recovery(vault_path: String, codes: Vec<String>, output_path: String, pin: String) -> Result<(), String> {
+    let pin = Zeroizing::new(pin);
+    let header = Vault::read_container(Path::new(&vault_path))?.header;
+    Vault::export_recovery_codes(&codes, Path::new(&output_path), &pin, &header.argon2_params)
+}
+
+#[tauri::command(async)]
+pub fn vault_import_recovery(path: String, pin: String) -> Result<Vec<String>, String> {
+    let pin = Zeroizing::new(pin);
+    Vault::import_recovery_codes(Path::new(&path), &pin)
+}
+
+#[tauri::command]
+pub fn vault_merkle_root(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+) -> Result<VaultMerkleRoot, String> {
+    with_session(&state_mux, &vault_id, |session| Vault::merkle_root(session))
+}
+
+/// Back up the container to `backup_dir` before every destructive operation,
+/// keeping only the `keep_n` most recent copies.
+#[tauri::command]
+pub fn vault_enable_autobackup(vault_id: String, backup_dir: String, keep_n: usize) -> Result<(), String> {
+    if keep_n == 0 {
+        return Err("keep_n must be at least 1".to_string());
+    }
+    std::fs::create_dir_all(&backup_dir)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let mut configs = AUTOBACKUP.lock().map_err(|_| "Failed to acquire backup lock".to_string())?;
+    configs.insert(
+        vault_id,
+        AutoBackupConfig {
+            backup_dir: PathBuf::from(backup_dir),
+            keep_n,
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn vault_disable_autobackup(vault_id: String) -> Result<(), String> {
+    let mut configs = AUTOBACKUP.lock().map_err(|_| "Failed to acquire backup lock".to_string())?;
+    configs.remove(&vault_id);
+    Ok(())
+}
+
+/// Copy a small text entry to the clipboard, clearing it after `clear_after_secs`
+/// unless the user has copied something else in the meantime.
+#[tauri::command]
+pub fn vault_copy_to_clipboard(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    entry_id: String,
+    clear_after_secs: u64,
+) -> Result<(), String> {
+    let text = with_session(&state_mux, &vault_id, |session| {
+        let entry = session.manifest.entries.get(&entry_id).ok_or("Entry not found")?;
+        if entry.file_size > MAX_CLIPBOARD_ENTRY {
+            return Err("Entry is too large to copy to the clipboard".to_string());
+        }
+
+        let data = Vault::decrypt_entry(session, &entry_id)?;
+        if data.contains(&0) {
+            return Err("Only text entries can be copied to the clipboard".to_string());
+        }
+        let text = String::from_utf8(data)
+            .map_err(|_| "Only text entries can be copied to the clipboard".to_string())?;
+
+        session.manifest.access_log.push(AuditLog {
+            timestamp: Utc::now().to_rfc3339(),
+            action: "copy_to_clipboard".to_string(),
+            entry_id: Some(entry_id.clone()),
+            status: "success".to_string(),
+        });
+        session.touch();
+        Vault::save_manifest(session)?;
+
+        Ok(Zeroizing::new(text))
+    })?;
+
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|e| format!("Failed to access clipboard: {}", e))?;
+    clipboard
+        .set_text(text.as_str())
+        .map_err(|e| format!("Failed to write clipboard: {}", e))?;
+
+    // The handle moves into the thread: on X11 the selection is lost once it is dropped
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_secs(clear_after_secs));
+        let current = clipboard.get_text().ok().map(Zeroizing::new);
+        if clipboard_still_ours(current.as_ref().map(|current| current.as_str()), &text) {
+            let _ = clipboard.clear();
+        }
+    });
+
+    Ok(())
+}
+
+/// Whether the clipboard still holds exactly the `copied` text. Anything else, including
+/// contents that can't be read as text, was put there since and must be left alone.
+fn clipboard_still_ours(current: Option<&str>, copied: &str) -> bool {
+    current == Some(copied)
+}
+
+/// Small JPEG preview of an image entry, decrypted in memory and never written to disk
+#[tauri::command]
+pub fn vault_thumbnail(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    entry_id: String,
+    max_dimension: Option<u32>,
+) -> Result<VaultThumbnail, String> {
+    let max_dimension = max_dimension.unwrap_or(DEFAULT_THUMBNAIL_DIMENSION).clamp(16, 1024);
+    with_session(&state_mux, &vault_id, |session| Vault::thumbnail(session, &entry_id, max_dimension))
+}
+
+/// Pin an entry to the top of the listing (or unpin it)
+#[tauri::command]
+pub fn vault_set_pinned(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    entry_id: String,
+    pinned: bool,
+    sort_order: Option<u32>,
+) -> Result<(), String> {
+    with_session(&state_mux, &vault_id, |session| {
+        Vault::set_pinned(session, &entry_id, pinned, sort_order)
+    })
+}
+
+/// Checks the audit log for out-of-order, future-dated and unparseable timestamps.
+/// With `apply` (default false) an out-of-order log is re-sorted and saved.
+#[tauri::command]
+pub fn vault_repair_log(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    apply: Option<bool>,
+) -> Result<LogRepairReport, String> {
+    with_session(&state_mux, &vault_id, |session| {
+        Vault::repair_log(session, apply.unwrap_or(false))
+    })
+}
+
+/// Archives the full audit log to `archive_path` and trims the vault's log to the newest
+/// `keep_recent` entries
+#[tauri::command]
+pub fn vault_truncate_log(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    keep_recent: usize,
+    archive_path: String,
+) -> Result<LogTruncation, String> {
+    let archive_path = resolve_path_in_state(&archive_path, &state_mux).map_err(|e| e.to_string())?;
+    with_session(&state_mux, &vault_id, |session| {
+        Vault::truncate_log(session, keep_recent, Path::new(&archive_path))
+    })
+}
+
+/// Decrypts a log archive written by `vault_truncate_log` for an open vault
+#[tauri::command]
+pub fn vault_read_log_archive(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    archive_path: String,
+) -> Result<Vec<AuditLog>, String> {
+    let archive_path = resolve_path_in_state(&archive_path, &state_mux).map_err(|e| e.to_string())?;
+    with_session(&state_mux, &vault_id, |session| {
+        Vault::read_log_archive(session, Path::new(&archive_path))
+    })
+}
+
+/// Vault metadata (entries, tags, sizes, audit log) as JSON, without ciphertext or keys
+#[tauri::command]
+pub fn vault_export_metadata(state_mux: State<'_, StateSafe>, vault_id: String) -> Result<String, String> {
+    with_session(&state_mux, &vault_id, |session| Vault::export_metadata(session))
+}
+
+/// Stream a file straight into an open vault without reading it into memory.
+/// With `shred`, the source is overwritten and removed after a successful ingest.
+#[tauri::command(async)]
+pub fn vault_ingest(
+    window: Window,
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    source_path: String,
+    tags: Option<Vec<String>>,
+    shred: bool,
+) -> Result<String, String> {
+    let source_path = resolve_path_in_state(&source_path, &state_mux).map_err(|e| e.to_string())?;
+    with_session_detached(&state_mux, &vault_id, |session| {
+        let used_before = Vault::used_bytes(session);
+        let entry_id = Vault::ingest_file(session, Path::new(&source_path), tags.unwrap_or_default(), shred)?;
+        warn_if_near_capacity(&window, session, used_before);
+        Ok(entry_id)
+    })
+}
+
+/// Fills the free space of the volume holding `dir` with zeros, then removes the filler.
+/// Returns how many bytes were overwritten.
+fn zero_free_space(dir: &Path) -> Result<u64, String> {
+    let filler_path = dir.join(format!(".neura-wipe-{}.tmp", uuid::Uuid::new_v4()));
+    let mut filler = File::create(&filler_path)
+        .map_err(|e| format!("Failed to create wipe file: {}", e))?;
+
+    let zeros = vec![0u8; STREAM_CHUNK_SIZE as usize];
+    let mut written: u64 = 0;
+    // Stops at the first failed write, which is normally the volume running out of space
+    while filler.write_all(&zeros).is_ok() {
+        written += zeros.len() as u64;
+    }
+    let _ = filler.sync_all();
+    drop(filler);
+
+    std::fs::remove_file(&filler_path)
+        .map_err(|e| format!("Failed to remove wipe file {}: {}", filler_path.display(), e))?;
+    Ok(written)
+}
+
+/// Ingests every file under `source_dir` (e.g. a USB stick) into an open vault with one
+/// container write, then shreds the sources and, with `wipe_free_space`, the volume's free
+/// space. Nothing is wiped unless every file was imported and decrypts back to its source,
+/// so a partial run leaves the source untouched.
+#[tauri::command(async)]
+pub fn vault_ingest_and_wipe(
+    window: Window,
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    source_dir: String,
+    tags: Option<Vec<String>>,
+    wipe_free_space: Option<bool>,
+) -> Result<IngestWipeSummary, String> {
+    let source_dir = resolve_path_in_state(&source_dir, &state_mux).map_err(|e| e.to_string())?;
+    let source_root = Path::new(&source_dir);
+    if !source_root.is_dir() {
+        return Err("Source is not a directory".to_string());
+    }
+
+    // Everything under the source is wiped, so hidden and system files are ingested too
+    let walk = WalkOptions::default();
+    let mut files: Vec<(PathBuf, u64)> = Vec::new();
+    for entry in walk.walk_dir(source_root).min_depth(1) {
+        let entry = entry.map_err(|e| format!("Failed to read source directory: {}", e))?;
+        if entry.file_type().is_file() {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            files.push((entry.into_path(), size));
+        }
+    }
+
+    let mut summary = IngestWipeSummary::default();
+    with_session_detached(&state_mux, &vault_id, |session| {
+        let used_before = Vault::used_bytes(session);
+        ingest_verified(session, &files, tags.unwrap_or_default(), &mut summary);
+        warn_if_near_capacity(&window, session, used_before);
+        Ok(())
+    })?;
+
+    if summary.import_failures.is_empty() {
+        wipe_sources(source_root, &files, wipe_free_space.unwrap_or(false), &mut summary);
+    }
+
+    Ok(summary)
+}
+
+/// Ingests `files` and records each one as imported only once its entry decrypts back to
+/// the source. If the ingest itself fails, every file is recorded as a failure.
+fn ingest_verified(
+    session: &mut VaultSession,
+    files: &[(PathBuf, u64)],
+    tags: Vec<String>,
+    summary: &mut IngestWipeSummary,
+) {
+    let paths: Vec<PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+    let entry_ids = match Vault::ingest_files(session, &paths, tags) {
+        Ok(entry_ids) => entry_ids,
+        Err(error) => {
+            for path in &paths {
+                summary.import_failures.push(PathFailure {
+                    path: path.to_string_lossy().to_string(),
+                    error: error.clone(),
+                });
+            }
+            return;
+        }
+    };
+
+    // A source is only shredded once its entry is known to decrypt back to it
+    for (path, entry_id) in paths.iter().zip(entry_ids) {
+        let verified = Vault::verify_export(session, &entry_id, path).and_then(|check| {
+            if check.matches {
+                Ok(())
+            } else {
+                Err("Imported entry does not match the source file".to_string())
+            }
+        });
+        match verified {
+            Ok(()) => summary.imported.push(IngestedFile {
+                source: path.to_string_lossy().to_string(),
+                entry_id,
+            }),
+            Err(error) => summary.import_failures.push(PathFailure {
+                path: path.to_string_lossy().to_string(),
+                error,
+            }),
+        }
+    }
+}
+
+/// Shreds the ingested `files`, removes the directories they leave empty under
+/// `source_root` and optionally zeroes the free space behind them
+fn wipe_sources(source_root: &Path, files: &[(PathBuf, u64)], wipe_free_space: bool, summary: &mut IngestWipeSummary) {
+    for (path, size) in files {
+        match Vault::shred_file(path, *size) {
+            Ok(()) => summary.wiped_files += 1,
+            Err(e) => summary.wipe_failures.push(PathFailure {
+                path: path.to_string_lossy().to_string(),
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    // Deepest directories first, so parents are empty by the time they're removed
+    let mut dirs: Vec<PathBuf> = WalkOptions::default()
+        .walk(source_root)
+        .filter(|e| e.depth() > 0 && e.file_type().is_dir())
+        .map(|e| e.into_path())
+        .collect();
+    dirs.sort_by_key(|d| std::cmp::Reverse(d.components().count()));
+    for dir in dirs {
+        let _ = std::fs::remove_dir(dir);
+    }
+
+    summary.wiped = summary.wipe_failures.is_empty();
+    if summary.wiped && wipe_free_space {
+        // The sources are already gone, so a failed free-space pass is reported, not raised
+        match zero_free_space(source_root) {
+            Ok(bytes) => summary.free_space_wiped_bytes = Some(bytes),
+            Err(error) => summary.free_space_error = Some(error),
+        }
+    }
+}
+
+/// List container backups in `backup_dir`, newest first
+#[tauri::command]
+pub fn vault_list_backups(backup_dir: String) -> Result<Vec<VaultBackupInfo>, String> {
+    let entries = std::fs::read_dir(&backup_dir)
+        .map_err(|e| format!("Failed to read backup directory: {}", e))?;
+
+    let mut backups = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("vault") {
+            continue;
+        }
+        // Only the plaintext header is needed; skip files that aren't containers
+        let Ok(sections) = Vault::read_container(&path) else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        backups.push(VaultBackupInfo {
+            path: path.to_string_lossy().to_string(),
+            file_name: entry.file_name().to_string_lossy().to_string(),
+            size: metadata.len(),
+            vault_id: sections.header.vault_id,
+            modified_at: metadata
+                .modified()
+                .map(|t| DateTime::<Utc>::from(t).to_rfc3339())
+                .unwrap_or_default(),
+        });
+    }
+
+    backups.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+    Ok(backups)
+}
+
+/// Restore a backup over `target_path`. The backup must parse as a container, and
+/// must belong to the same vault as the target unless `force` is set.
+#[tauri::command]
+pub fn vault_restore_backup(
+    state_mux: State<'_, StateSafe>,
+    backup_file: String,
+    target_path: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let backup_file = resolve_path_in_state(&backup_file, &state_mux).map_err(|e| e.to_string())?;
+    let target_path = resolve_path_in_state(&target_path, &state_mux).map_err(|e| e.to_string())?;
+    let backup = Vault::read_container(Path::new(&backup_file))
+        .map_err(|e| format!("Backup is not a valid vault: {}", e))?;
+
+    let target = Path::new(&target_path);
+    if target.exists() && !force.unwrap_or(false) {
+        let target_id = Vault::read_container(target)
+            .map_err(|e| format!("Target is not a valid vault ({}); use force to overwrite", e))?
+            .header
+            .vault_id;
+        if target_id != backup.header.vault_id {
+            return Err("Backup belongs to a different vault; use force to restore anyway".to_string());
+        }
+    }
+
+    restore_container(Path::new(&backup_file), target, backup.header.split_storage)?;
+
+    // Any open session now describes a container that no longer exists
+    let mut state = state_mux.lock().map_err(|_| "Failed to acquire state lock".to_string())?;
+    if let Some(mut session) = state.vault_sessions.remove(&backup.header.vault_id) {
+        Vault::lock_session(&mut session)?;
+    }
+
     Ok(())
 }
 
+/// Copies the container at `backup` over `target`. Both files of a split vault are staged
+/// under temporary names first, so a failed copy leaves the target untouched; the blobs are
+/// swapped in before the meta file, and put back if the meta file can't be replaced.
+fn restore_container(backup: &Path, target: &Path, split_storage: bool) -> Result<(), String> {
+    let meta_temp = target.with_extension("vault.restore");
+    let blobs_temp = target.with_extension("blobs.restore");
+    let discard_staged = || {
+        let _ = std::fs::remove_file(&meta_temp);
+        let _ = std::fs::remove_file(&blobs_temp);
+    };
+
+    let staged = std::fs::copy(backup, &meta_temp)
+        .map_err(|e| format!("Failed to copy backup: {}", e))
+        .and_then(|_| {
+            if split_storage {
+                std::fs::copy(Vault::blobs_path(backup), &blobs_temp)
+                    .map_err(|e| format!("Failed to copy backup blobs: {}", e))?;
+            }
+            Ok(())
+        });
+    if let Err(e) = staged {
+        discard_staged();
+        return Err(e);
+    }
+
+    if !split_storage {
+        return std::fs::rename(&meta_temp, target).map_err(|e| {
+            discard_staged();
+            format!("Failed to replace vault file: {}", e)
+        });
+    }
+
+    // Blobs go first so the restored manifest never points past the end of its blob file
+    let blobs = Vault::blobs_path(target);
+    let previous = target.with_extension("blobs.previous");
+    let had_blobs = blobs.exists();
+    if had_blobs {
+        if let Err(e) = std::fs::rename(&blobs, &previous) {
+            discard_staged();
+            return Err(format!("Failed to move aside vault blobs: {}", e));
+        }
+    }
+    let swapped = std::fs::rename(&blobs_temp, &blobs)
+        .map_err(|e| format!("Failed to replace vault blobs: {}", e))
+        .and_then(|_| {
+            std::fs::rename(&meta_temp, target).map_err(|e| format!("Failed to replace vault file: {}", e))
+        });
+
+    match swapped {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&previous);
+            Ok(())
+        }
+        Err(e) => {
+            let rolled_back = if had_blobs {
+                std::fs::rename(&previous, &blobs)
+            } else {
+                let _ = std::fs::remove_file(&blobs);
+                Ok(())
+            };
+            discard_staged();
+            match rolled_back {
+                Ok(()) => Err(e),
+                Err(rollback) => Err(format!(
+                    "{}; the previous blobs are kept at {} ({})",
+                    e,
+                    previous.display(),
+                    rollback
+                )),
+            }
+        }
+    }
+}
+
+/// Ids of existing entries with the same content as `source_path`, without importing it
+#[tauri::command]
+pub fn vault_check_duplicate(
+    state_mux: State<'_, StateSafe>,
+    vault_id: String,
+    source_path: String,
+) -> Result<Vec<String>, String> {
+    let source_path = resolve_path_in_state(&source_path, &state_mux).map_err(|e| e.to_string())?;
+    with_session(&state_mux, &vault_id, |session| Vault::find_duplicates(session, Path::new(&source_path)))
+}
+
+fn password_strength(password: &str) -> PasswordStrength {
+    let entropy = zxcvbn::zxcvbn(password, &[]);
+    let feedback = entropy.feedback();
+    PasswordStrength {
+        score: u8::from(entropy.score()),
+        crack_time: entropy.crack_times().offline_slow_hashing_1e4_per_second().to_string(),
+        warning: feedback.and_then(|f| f.warning()).map(|w| w.to_string()),
+        suggestions: feedback
+            .map(|f| f.suggestions().iter().map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// Scores a password before it is used for a vault, with feedback for the UI
 #[tauri::command]
-pub fn vault_generate_recovery_codes(_vault_id: String) -> Result<Vec<String>, String> {
-    Ok(Vault::generate_recovery_codes())
+pub fn estimate_password_strength(password: String) -> PasswordStrength {
+    password_strength(&password)
+}
+
+/// Build a Diceware-style passphrase from the bundled wordlist using the OS CSPRNG.
+/// Capitalization adds no entropy; `add_number` inserts a 0-99 number at a random position.
+#[tauri::command]
+pub fn generate_passphrase(
+    word_count: usize,
+    separator: Option<String>,
+    capitalize: Option<bool>,
+    add_number: Option<bool>,
+) -> Result<GeneratedPassphrase, String> {
+    if !(3..=32).contains(&word_count) {
+        return Err("Word count must be between 3 and 32".to_string());
+    }
+
+    let words: Vec<&str> = PASSPHRASE_WORDS.lines().filter(|w| !w.is_empty()).collect();
+    let mut rng = rand::rngs::OsRng;
+
+    let mut parts: Vec<String> = (0..word_count)
+        .map(|_| {
+            let word = words[rng.gen_range(0..words.len())];
+            if capitalize.unwrap_or(false) {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            } else {
+                word.to_string()
+            }
+        })
+        .collect();
+
+    let mut entropy_bits = word_count as f64 * (words.len() as f64).log2();
+    if add_number.unwrap_or(false) {
+        let position = rng.gen_range(0..=parts.len());
+        parts.insert(position, rng.gen_range(0..100).to_string());
+        entropy_bits += 100f64.log2() + ((word_count + 1) as f64).log2();
+    }
+
+    Ok(GeneratedPassphrase {
+        passphrase: parts.join(separator.as_deref().unwrap_or("-")),
+        entropy_bits,
+    })
 }
 
 pub fn init_vault() {
     // Register Tauri commands
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap Argon2 params so tests don't spend seconds per derivation
+    const TEST_ARGON2_PARAMS: &str = "m=1024,t=1,p=1";
+    const TEST_PIN: &str = "482915";
+
+    #[test]
+    fn recovery_codes_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("codes.recovery");
+        let codes = vec!["alpha bravo".to_string(), "charlie delta".to_string()];
+
+        Vault::export_recovery_codes(&codes, &path, TEST_PIN, TEST_ARGON2_PARAMS).unwrap();
+        let file: RecoveryFile = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(file.argon2_params, TEST_ARGON2_PARAMS);
+
+        assert_eq!(Vault::import_recovery_codes(&path, TEST_PIN).unwrap(), codes);
+        assert!(Vault::import_recovery_codes(&path, "incorrect horse battery staple").is_err());
+    }
+
+    #[test]
+    fn recovery_export_rejects_short_pins() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("codes.recovery");
+
+        let err = Vault::export_recovery_codes(&["code".to_string()], &path, "48291", TEST_ARGON2_PARAMS).unwrap_err();
+        assert!(err.contains("at least"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn restore_replaces_both_files_of_a_split_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup = dir.path().join("backup.vault");
+        let target = dir.path().join("target.vault");
+        std::fs::write(&backup, "new meta").unwrap();
+        std::fs::write(Vault::blobs_path(&backup), "new blobs").unwrap();
+        std::fs::write(&target, "old meta").unwrap();
+        std::fs::write(Vault::blobs_path(&target), "old blobs").unwrap();
+
+        restore_container(&backup, &target, true).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "new meta");
+        assert_eq!(std::fs::read_to_string(Vault::blobs_path(&target)).unwrap(), "new blobs");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn failed_restore_leaves_the_target_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup = dir.path().join("backup.vault");
+        let target = dir.path().join("target.vault");
+        std::fs::write(&backup, "new meta").unwrap();
+        // No backup blobs, so staging fails after the meta copy
+        std::fs::write(&target, "old meta").unwrap();
+        std::fs::write(Vault::blobs_path(&target), "old blobs").unwrap();
+
+        assert!(restore_container(&backup, &target, true).is_err());
+
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "old meta");
+        assert_eq!(std::fs::read_to_string(Vault::blobs_path(&target)).unwrap(), "old blobs");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn blobs_are_rolled_back_when_the_meta_file_cannot_be_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup = dir.path().join("backup.vault");
+        std::fs::write(&backup, "new meta").unwrap();
+        std::fs::write(Vault::blobs_path(&backup), "new blobs").unwrap();
+        // A non-empty directory can't be renamed over, so the last step fails
+        let target = dir.path().join("target.vault");
+        std::fs::create_dir(&target).unwrap();
+        std::fs::write(target.join("keep"), "").unwrap();
+        std::fs::write(Vault::blobs_path(&target), "old blobs").unwrap();
+
+        assert!(restore_container(&backup, &target, true).is_err());
+
+        assert_eq!(std::fs::read_to_string(Vault::blobs_path(&target)).unwrap(), "old blobs");
+        assert!(!target.with_extension("vault.restore").exists());
+        assert!(!target.with_extension("blobs.previous").exists());
+    }
+
+    fn test_session() -> VaultSession {
+        VaultSession {
+            vault_id: "v".to_string(),
+            vault_path: PathBuf::from("v.vault"),
+            manifest_key: Zeroizing::new(vec![1; 32]),
+            blob_key: Zeroizing::new(vec![2; 32]),
+            manifest: VaultManifest {
+                entries: HashMap::new(),
+                last_accessed: "then".to_string(),
+                access_log: Vec::new(),
+                archive_key: None,
+            },
+            locked: false,
+            last_accessed: Utc::now(),
+            busy: false,
+        }
+    }
+
+    fn open_session(state_mux: &StateSafe) {
+        state_mux.lock().unwrap().vault_sessions.insert("v".to_string(), test_session());
+    }
+
+    #[test]
+    fn detached_changes_replace_the_session() {
+        let state_mux: StateSafe = Default::default();
+        open_session(&state_mux);
+
+        with_session_detached(&state_mux, "v", |session| {
+            assert_eq!(session.manifest.last_accessed, "then");
+            let state = state_mux.lock().unwrap();
+            assert!(state.vault_sessions["v"].busy);
+            drop(state);
+            session.manifest.last_accessed = "now".to_string();
+            Ok(())
+        })
+        .unwrap();
+
+        let state = state_mux.lock().unwrap();
+        let session = &state.vault_sessions["v"];
+        assert!(!session.busy && !session.locked);
+        assert_eq!(session.manifest.last_accessed, "now");
+    }
+
+    #[test]
+    fn a_panic_clears_busy_and_locks_the_session() {
+        let state_mux: StateSafe = Default::default();
+        open_session(&state_mux);
+
+        let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            with_session_detached(&state_mux, "v", |_| -> Result<(), String> { panic!("boom") })
+        }));
+        assert!(outcome.is_err());
+
+        let state = state_mux.lock().unwrap();
+        let session = &state.vault_sessions["v"];
+        assert!(!session.busy);
+        assert!(session.locked);
+    }
+
+    fn write_archive(path: &Path, version: u32, log: &[AuditLog], key: &[u8]) {
+        let archive = LogArchiveFile {
+            version,
+            vault_id: "v".to_string(),
+            created_at: Utc::now().to_rfc3339(),
+            entries: log.len(),
+            data: general_purpose::STANDARD.encode(Vault::encrypt_data(&log, key).unwrap()),
+        };
+        std::fs::write(path, serde_json::to_vec(&archive).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn log_archives_are_read_with_the_archive_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.archive");
+        let mut session = test_session();
+        let key = [7u8; 32];
+        session.manifest.archive_key = Some(hex::encode(key));
+        let log = vec![AuditLog {
+            timestamp: Utc::now().to_rfc3339(),
+            action: "import".to_string(),
+            entry_id: None,
+            status: "ok".to_string(),
+        }];
+
+        write_archive(&path, LOG_ARCHIVE_VERSION, &log, &key);
+        let read = Vault::read_log_archive(&session, &path).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].action, "import");
+
+        // Only the one format is accepted, whatever key an older one was written under
+        write_archive(&path, LOG_ARCHIVE_VERSION + 1, &log, &key);
+        assert!(Vault::read_log_archive(&session, &path).unwrap_err().contains("Unsupported"));
+    }
+
+    #[test]
+    fn upgraded_vault_opens_with_the_same_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.vault");
+        let (_, codes) = Vault::create_vault(&path, "hunter22", None, false, Some(TEST_ARGON2_PARAMS.to_string())).unwrap();
+        let mut session = Vault::open_vault(&path, "hunter22").unwrap();
+
+        let stronger = "m=2048,t=2,p=1";
+        Vault::upgrade_kdf(&mut session, "hunter22", stronger, Some(&codes)).unwrap();
+
+        let header = Vault::read_container(&path).unwrap().header;
+        assert_eq!(header.argon2_params, stronger);
+        assert!(Vault::open_vault(&path, "hunter22").is_ok());
+        assert!(Vault::open_vault(&path, "wrong password").is_err());
+        assert!(Vault::test_recovery(&path, &codes).unwrap());
+    }
+
+    #[test]
+    fn upgrade_without_codes_drops_the_recovery_binding() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.vault");
+        let (_, codes) = Vault::create_vault(&path, "hunter22", None, false, Some(TEST_ARGON2_PARAMS.to_string())).unwrap();
+        let mut session = Vault::open_vault(&path, "hunter22").unwrap();
+
+        Vault::upgrade_kdf(&mut session, "hunter22", TEST_ARGON2_PARAMS, None).unwrap();
+
+        assert!(Vault::open_vault(&path, "hunter22").is_ok());
+        assert!(Vault::test_recovery(&path, &codes).is_err());
+    }
+
+    /// A new vault in `dir` with test KDF params, opened, plus its recovery codes
+    fn new_vault(dir: &Path, split_storage: bool) -> (VaultSession, Vec<String>) {
+        let path = dir.join("test.vault");
+        let (_, codes) =
+            Vault::create_vault(&path, "hunter22", None, split_storage, Some(TEST_ARGON2_PARAMS.to_string())).unwrap();
+        (Vault::open_vault(&path, "hunter22").unwrap(), codes)
+    }
+
+    /// Writes `data` to `dir/name` and returns the path
+    fn source_file(dir: &Path, name: &str, data: &[u8]) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn merkle_root_is_stable_and_tracks_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let a = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"alpha"), vec![]).unwrap();
+        let b = Vault::import_file(&mut session, &source_file(dir.path(), "b.txt", b"bravo"), vec![]).unwrap();
+
+        let before = Vault::merkle_root(&session).unwrap();
+        assert_eq!(before.leaves.len(), 2);
+        assert_eq!(Vault::merkle_root(&session).unwrap().root, before.root);
+
+        Vault::delete_entry(&mut session, &b).unwrap();
+        let after = Vault::merkle_root(&session).unwrap();
+        assert_ne!(after.root, before.root);
+        assert_eq!(after.leaves[&a], before.leaves[&a]);
+    }
+
+    #[test]
+    fn deletes_back_up_the_container_first_and_keep_the_newest_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let (mut session, _) = new_vault(dir.path(), false);
+        let mut ids = Vec::new();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            ids.push(Vault::import_file(&mut session, &source_file(dir.path(), name, b"data"), vec![]).unwrap());
+        }
+        vault_enable_autobackup(session.vault_id.clone(), backups.to_string_lossy().to_string(), 2).unwrap();
+
+        for id in &ids {
+            // Backup names carry millisecond timestamps
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            Vault::delete_entry(&mut session, id).unwrap();
+        }
+        vault_disable_autobackup(session.vault_id.clone()).unwrap();
+
+        let mut copies: Vec<PathBuf> = std::fs::read_dir(&backups).unwrap().map(|e| e.unwrap().path()).collect();
+        copies.sort();
+        assert_eq!(copies.len(), 2);
+        // The newest copy was taken just before the last delete
+        let newest = Vault::open_vault(&copies[1], "hunter22").unwrap();
+        assert_eq!(newest.manifest.entries.len(), 1);
+        assert!(newest.manifest.entries.contains_key(&ids[2]));
+    }
+
+    #[test]
+    fn metadata_export_leaves_out_ciphertext_hashes_and_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        session.manifest.archive_key = Some(hex::encode([7u8; 32]));
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"alpha"), vec!["work".to_string()]).unwrap();
+
+        let metadata: serde_json::Value = serde_json::from_str(&Vault::export_metadata(&session).unwrap()).unwrap();
+        let entry = &metadata["entries"][&id];
+        assert_eq!(entry["filename"], "a.txt");
+        assert_eq!(entry["tags"][0], "work");
+        assert!(entry.get("encrypted_data").is_none() && entry.get("content_hash").is_none());
+        assert!(metadata.get("archive_key").is_none());
+    }
+
+    #[test]
+    fn ingested_files_span_chunks_and_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let data: Vec<u8> = (0..STREAM_CHUNK_SIZE as usize + 1000).map(|i| (i % 251) as u8).collect();
+        let source = source_file(dir.path(), "big.bin", &data);
+
+        let id = Vault::ingest_file(&mut session, &source, vec![], true).unwrap();
+        assert!(!source.exists());
+        assert!(session.manifest.entries[&id].blob.is_some());
+
+        let reopened = Vault::open_vault(&session.vault_path, "hunter22").unwrap();
+        assert_eq!(Vault::decrypt_entry(&reopened, &id).unwrap(), data);
+    }
+
+    /// Manifest-only entry with no ciphertext behind it
+    fn test_entry(id: &str, filename: &str, file_size: u64) -> VaultEntry {
+        VaultEntry {
+            id: id.to_string(),
+            filename: filename.to_string(),
+            original_path: format!("/src/{}", filename),
+            file_size,
+            mime_type: Vault::guess_mime_type(Path::new(filename)),
+            imported_at: "2024-01-01T00:00:00Z".to_string(),
+            nonce: String::new(),
+            tags: Vec::new(),
+            encrypted_data: String::new(),
+            blob: None,
+            content_hash: None,
+            pinned: false,
+            sort_order: None,
+        }
+    }
+
+    #[test]
+    fn capacity_warning_fires_only_when_crossing_the_threshold() {
+        let mut session = test_session();
+        let near_full = MAX_VAULT_SIZE / 100 * 95;
+        session.manifest.entries.insert("e".to_string(), test_entry("e", "big.bin", near_full));
+
+        let warning = capacity_warning(&session, 0).unwrap();
+        assert_eq!(warning.used_bytes, near_full);
+        assert!((warning.percent_used - 95.0).abs() < 0.1);
+        assert!(capacity_warning(&session, near_full - 1).is_none());
+    }
+
+    #[test]
+    fn duplicates_are_found_by_plaintext_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"same bytes"), vec![]).unwrap();
+
+        let copy = source_file(dir.path(), "renamed.txt", b"same bytes");
+        assert_eq!(Vault::find_duplicates(&session, &copy).unwrap(), [id]);
+        let other = source_file(dir.path(), "other.txt", b"diff bytes");
+        assert!(Vault::find_duplicates(&session, &other).unwrap().is_empty());
+    }
+
+    #[test]
+    fn passphrases_use_the_wordlist_and_report_entropy() {
+        let generated = generate_passphrase(5, Some(" ".to_string()), None, None).unwrap();
+        let words: Vec<&str> = generated.passphrase.split(' ').collect();
+        assert_eq!(words.len(), 5);
+        assert!(words.iter().all(|w| PASSPHRASE_WORDS.lines().any(|l| l == *w)));
+        assert!((generated.entropy_bits - 55.0).abs() < 1e-9);
+
+        let numbered = generate_passphrase(3, None, Some(true), Some(true)).unwrap();
+        let parts: Vec<&str> = numbered.passphrase.split('-').collect();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts.iter().filter(|p| p.parse::<u8>().is_ok()).count(), 1);
+        assert!(parts.iter().filter(|p| p.parse::<u8>().is_err()).all(|w| w.starts_with(char::is_uppercase)));
+
+        assert!(generate_passphrase(2, None, None, None).is_err());
+    }
+
+    #[test]
+    fn split_vaults_keep_blobs_in_the_sibling_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), true);
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"split payload"), vec![]).unwrap();
+
+        let blobs = Vault::blobs_path(&session.vault_path);
+        assert!(std::fs::metadata(&blobs).unwrap().len() > 0);
+        assert!(session.manifest.entries[&id].blob.is_some());
+
+        // Further manifest saves leave the blobs file alone
+        let blobs_before = std::fs::read(&blobs).unwrap();
+        Vault::set_pinned(&mut session, &id, true, None).unwrap();
+        assert_eq!(std::fs::read(&blobs).unwrap(), blobs_before);
+
+        let reopened = Vault::open_vault(&session.vault_path, "hunter22").unwrap();
+        assert_eq!(Vault::decrypt_entry(&reopened, &id).unwrap(), b"split payload");
+    }
+
+    #[test]
+    fn manifest_and_blob_keys_are_separate_subkeys() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        assert_eq!(session.manifest_key.len(), 32);
+        assert_ne!(session.manifest_key, session.blob_key);
+
+        // The same master key always yields the same subkeys
+        let reopened = Vault::open_vault(&session.vault_path, "hunter22").unwrap();
+        assert_eq!(reopened.manifest_key, session.manifest_key);
+        assert_eq!(reopened.blob_key, session.blob_key);
+
+        // Blobs are sealed with the blob key only
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"payload"), vec![]).unwrap();
+        assert_eq!(Vault::decrypt_entry(&session, &id).unwrap(), b"payload");
+        session.blob_key = session.manifest_key.clone();
+        assert!(Vault::decrypt_entry(&session, &id).is_err());
+
+        // Older containers used the master key for both
+        let mut header = Vault::read_container(&session.vault_path).unwrap().header;
+        header.version = KEY_SEPARATION_VERSION - 1;
+        let (manifest_key, blob_key) = Vault::derive_subkeys(&[9u8; 32], &header).unwrap();
+        assert_eq!(*manifest_key, vec![9u8; 32]);
+        assert_eq!(manifest_key, blob_key);
+    }
+
+    #[test]
+    fn only_writes_by_other_processes_count_as_external_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = source_file(dir.path(), "watched.vault", b"ours");
+        Vault::mark_own_write(std::slice::from_ref(&path), false);
+        assert_eq!(Vault::external_change(&path), None);
+
+        Vault::mark_own_write(std::slice::from_ref(&path), true);
+        std::fs::write(&path, "ours, in progress").unwrap();
+        assert_eq!(Vault::external_change(&path), None);
+        Vault::mark_own_write(std::slice::from_ref(&path), false);
+
+        std::fs::write(&path, "someone else's longer write").unwrap();
+        assert_eq!(Vault::external_change(&path), Some("modified"));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(Vault::external_change(&path), Some("deleted"));
+    }
+
+    #[test]
+    fn stored_argon2_params_are_parsed_and_honoured() {
+        assert!(Vault::argon2_for("m=1024,t=1,p=1").is_ok());
+        assert!(Vault::argon2_for("m=1024,t=1").unwrap_err().contains("need m, t and p"));
+        assert!(Vault::argon2_for("m=1024,t=1,p=1,x=2").unwrap_err().contains("Unknown"));
+        assert!(Vault::argon2_for("m=lots,t=1,p=1").is_err());
+
+        let salt = hex::encode([3u8; 16]);
+        let cheap = Vault::derive_key("pw", &salt, TEST_ARGON2_PARAMS).unwrap();
+        assert_eq!(cheap, Vault::derive_key("pw", &salt, TEST_ARGON2_PARAMS).unwrap());
+        assert_ne!(cheap, Vault::derive_key("pw", &salt, "m=2048,t=1,p=1").unwrap());
+
+        // Headers older than the params-aware format were derived with the defaults
+        let dir = tempfile::tempdir().unwrap();
+        let (session, _) = new_vault(dir.path(), false);
+        let mut header = Vault::read_container(&session.vault_path).unwrap().header;
+        assert_eq!(Vault::kdf_params(&header), TEST_ARGON2_PARAMS);
+        header.version = KDF_PARAMS_VERSION - 1;
+        assert_eq!(Vault::kdf_params(&header), "");
+    }
+
+    /// Test session holding `entries`
+    fn session_with(entries: Vec<VaultEntry>) -> VaultSession {
+        let mut session = test_session();
+        session.manifest.entries = entries.into_iter().map(|e| (e.id.clone(), e)).collect();
+        session
+    }
+
+    #[test]
+    fn pinned_entries_list_first_in_their_sort_order() {
+        let entry = |id: &str, pinned: bool, sort_order: Option<u32>, imported_at: &str| VaultEntry {
+            pinned,
+            sort_order,
+            imported_at: imported_at.to_string(),
+            ..test_entry(id, "f.txt", 1)
+        };
+        let session = session_with(vec![
+            entry("newer", false, None, "2024-02-01"),
+            entry("second", true, Some(2), "2024-01-01"),
+            entry("first", true, Some(1), "2024-01-01"),
+            entry("older", false, None, "2024-01-01"),
+            entry("unordered", true, None, "2024-01-01"),
+        ]);
+
+        let order: Vec<String> = Vault::sorted_entries(&session.manifest).into_iter().map(|e| e.id).collect();
+        assert_eq!(order, ["first", "second", "unordered", "newer", "older"]);
+    }
+
+    #[test]
+    fn entries_are_grouped_by_mime_category() {
+        let session = session_with(vec![
+            test_entry("photo", "photo.jpg", 1),
+            test_entry("scan", "scan.pdf", 1),
+            test_entry("notes", "notes.txt", 1),
+            test_entry("blob", "blob.bin", 1),
+        ]);
+        let mut archive = test_entry("backup", "backup.tar", 1);
+        archive.mime_type = Some("application/x-tar".to_string());
+        let mut manifest = session.manifest;
+        manifest.entries.insert("backup".to_string(), archive);
+
+        let groups = Vault::entries_by_category(&manifest);
+        let ids = |category: &str| {
+            let mut ids: Vec<&str> = groups[category].iter().map(|e| e.id.as_str()).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids("images"), ["photo"]);
+        assert_eq!(ids("documents"), ["notes", "scan"]);
+        assert_eq!(ids("archives"), ["backup"]);
+        assert_eq!(ids("other"), ["blob"]);
+    }
+
+    #[test]
+    fn manifests_are_matched_by_content_hash() {
+        let hashed = |id: &str, filename: &str, hash: &str| VaultEntry {
+            content_hash: Some(hash.to_string()),
+            ..test_entry(id, filename, 1)
+        };
+        let a = session_with(vec![hashed("a1", "report.txt", "h1"), hashed("a2", "old.txt", "h2")]);
+        let renamed = VaultEntry { tags: vec!["moved".to_string()], ..hashed("b1", "report-final.txt", "h1") };
+        let b = session_with(vec![renamed, hashed("b2", "new.txt", "h3")]);
+
+        let diff = diff_manifests(&a.manifest, &b.manifest);
+        assert_eq!(diff.only_in_a.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["a2"]);
+        assert_eq!(diff.only_in_b.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["b2"]);
+        assert_eq!(diff.in_both.len(), 1);
+        assert_eq!(diff.in_both[0].differences, ["filename", "tags", "original_path"]);
+    }
+
+    #[test]
+    fn diagnostics_split_inline_and_streamed_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        Vault::import_file(&mut session, &source_file(dir.path(), "small.txt", b"inline"), vec![]).unwrap();
+        Vault::ingest_file(&mut session, &source_file(dir.path(), "streamed.bin", &[1u8; 4096]), vec![], false).unwrap();
+
+        let report = Vault::diagnostics(&session).unwrap();
+        assert!(!report.split_storage);
+        assert_eq!((report.inline_entries, report.streamed_entries), (1, 1));
+        assert_eq!(report.logical_bytes, 6 + 4096);
+        assert_eq!(report.streamed_bytes, StreamedBlob::encrypted_len(4096, STREAM_CHUNK_SIZE));
+        assert!(report.inline_payload_bytes > 0);
+        assert_eq!(report.container_bytes, std::fs::metadata(&session.vault_path).unwrap().len());
+        assert!(report.overhead_ratio > 0.0);
+    }
+
+    #[test]
+    fn exports_are_verified_against_their_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"original"), vec![]).unwrap();
+        let exported = dir.path().join("exported.txt");
+        Vault::export_file(&mut session, &id, &exported).unwrap();
+
+        let verification = Vault::verify_export(&session, &id, &exported).unwrap();
+        assert!(verification.matches);
+        assert_eq!(verification.entry_hash, verification.exported_hash);
+
+        std::fs::write(&exported, "tampered").unwrap();
+        let verification = Vault::verify_export(&session, &id, &exported).unwrap();
+        assert!(!verification.matches);
+        assert_eq!(verification.exported_size, 8);
+    }
+
+    #[test]
+    fn audit_pages_are_newest_first_with_whole_log_counts() {
+        let log: Vec<AuditLog> = ["import", "export", "import", "delete", "import"]
+            .iter()
+            .enumerate()
+            .map(|(i, action)| AuditLog {
+                timestamp: format!("2024-01-0{}T00:00:00Z", i + 1),
+                action: action.to_string(),
+                entry_id: None,
+                status: "success".to_string(),
+            })
+            .collect();
+
+        let page = audit_page(&log, Some("import"), 1, 1);
+        assert_eq!(page.total, 3);
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].timestamp, "2024-01-03T00:00:00Z");
+        assert_eq!(page.counts_by_action["import"], 3);
+        assert_eq!(page.counts_by_action["delete"], 1);
+
+        assert_eq!(audit_page(&log, None, 0, 10).entries[0].action, "import");
+        assert_eq!(audit_page(&log, None, 0, 10).total, 5);
+    }
+
+    #[test]
+    fn sources_are_wiped_only_after_a_verified_ingest() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let drive = dir.path().join("drive");
+        std::fs::create_dir_all(drive.join("nested")).unwrap();
+        let files: Vec<(PathBuf, u64)> = [("a.txt", "alpha"), ("nested/b.txt", "bravo")]
+            .iter()
+            .map(|(name, data)| (source_file(&drive, name, data.as_bytes()), data.len() as u64))
+            .collect();
+
+        let mut summary = IngestWipeSummary::default();
+        ingest_verified(&mut session, &files, vec![], &mut summary);
+        assert_eq!(summary.imported.len(), 2);
+        assert!(summary.import_failures.is_empty());
+        wipe_sources(&drive, &files, false, &mut summary);
+
+        assert!(summary.wiped);
+        assert_eq!(summary.wiped_files, 2);
+        assert_eq!(std::fs::read_dir(&drive).unwrap().count(), 0);
+        let entry_id = &summary.imported[1].entry_id;
+        assert_eq!(Vault::decrypt_entry(&session, entry_id).unwrap(), b"bravo");
+    }
+
+    #[test]
+    fn a_failed_ingest_records_every_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let files = vec![
+            (source_file(dir.path(), "a.txt", b"alpha"), 5),
+            (dir.path().join("missing.txt"), 0),
+        ];
+
+        let mut summary = IngestWipeSummary::default();
+        ingest_verified(&mut session, &files, vec![], &mut summary);
+        assert!(summary.imported.is_empty());
+        assert_eq!(summary.import_failures.len(), 2);
+        assert!(session.manifest.entries.is_empty());
+        assert!(files[0].0.exists());
+    }
+
+    #[test]
+    fn weak_passwords_are_refused_below_the_minimum_score() {
+        assert_eq!(password_strength("password").score, 0);
+        assert!(password_strength("password").warning.is_some());
+        assert_eq!(password_strength("vivid-orbit-cactus-lantern-97").score, 4);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("weak.vault");
+        let err = vault_create(path.to_string_lossy().to_string(), "password".to_string(), None, None, None, Some(3))
+            .unwrap_err();
+        assert!(err.starts_with("Password is too weak (0/4)"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn crlf_converted_containers_still_open() {
+        let dir = tempfile::tempdir().unwrap();
+        // Converting back is only lossless when the ciphertext held no CRLF of its own
+        let (session, data) = (0..50)
+            .find_map(|_| {
+                let (session, _) = new_vault(dir.path(), false);
+                let data = std::fs::read(&session.vault_path).unwrap();
+                if data.windows(2).any(|w| w == b"\r\n") {
+                    std::fs::remove_file(&session.vault_path).unwrap();
+                    return None;
+                }
+                Some((session, data))
+            })
+            .unwrap();
+
+        let mut converted = Vec::new();
+        for &byte in &data {
+            if byte == b'\n' {
+                converted.push(b'\r');
+            }
+            converted.push(byte);
+        }
+        assert_eq!(crlf_to_lf(&converted), data);
+        std::fs::write(&session.vault_path, &converted).unwrap();
+        assert!(Vault::open_vault(&session.vault_path, "hunter22").is_ok());
+
+        std::fs::write(&session.vault_path, b"{}\nno boundary here").unwrap();
+        let Err(err) = Vault::open_vault(&session.vault_path, "hunter22") else {
+            panic!("a container without a boundary opened");
+        };
+        assert!(err.starts_with("VaultCorrupted: boundary not found"));
+        assert!(err.contains("restore it from a backup"));
+    }
+
+    #[test]
+    fn log_repair_flags_anomalies_and_resorts_on_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let log = |timestamp: &str, action: &str| AuditLog {
+            timestamp: timestamp.to_string(),
+            action: action.to_string(),
+            entry_id: None,
+            status: "success".to_string(),
+        };
+        session.manifest.access_log = vec![
+            log("2024-01-02T00:00:00Z", "a"),
+            log("2024-01-01T00:00:00Z", "b"),
+            log("yesterday", "c"),
+            log("2999-01-01T00:00:00Z", "d"),
+        ];
+
+        let report = Vault::repair_log(&mut session, false).unwrap();
+        let indexes = |anomalies: &[LogAnomaly]| anomalies.iter().map(|a| a.index).collect::<Vec<_>>();
+        assert_eq!(indexes(&report.out_of_order), [1]);
+        assert_eq!(indexes(&report.unparseable), [2]);
+        assert_eq!(indexes(&report.future_dated), [3]);
+        assert!(!report.reordered);
+
+        assert!(Vault::repair_log(&mut session, true).unwrap().reordered);
+        let reopened = Vault::open_vault(&session.vault_path, "hunter22").unwrap();
+        let actions: Vec<&str> = reopened.manifest.access_log.iter().map(|e| e.action.as_str()).collect();
+        // The unparseable entry keeps its place after the entry logged before it
+        assert_eq!(actions, ["b", "c", "a", "d", "log_repaired"]);
+    }
+
+    #[test]
+    fn locking_erases_both_subkeys() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"alpha"), vec![]).unwrap();
+
+        Vault::lock_session(&mut session).unwrap();
+        assert!(session.manifest_key.is_empty() && session.blob_key.is_empty());
+        assert_eq!(Vault::decrypt_entry(&session, &id).unwrap_err(), "Vault is locked");
+    }
+
+    #[test]
+    fn thumbnails_are_scaled_down_jpegs() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let mut png = Vec::new();
+        image::RgbImage::from_pixel(400, 200, image::Rgb([200, 30, 30]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "wide.png", &png), vec![]).unwrap();
+
+        let thumbnail = Vault::thumbnail(&session, &id, 100).unwrap();
+        assert_eq!((thumbnail.width, thumbnail.height), (100, 50));
+        assert_eq!(thumbnail.mime, "image/jpeg");
+        let jpeg = general_purpose::STANDARD.decode(&thumbnail.data).unwrap();
+        assert_eq!(image::guess_format(&jpeg).unwrap(), image::ImageFormat::Jpeg);
+
+        let text = Vault::import_file(&mut session, &source_file(dir.path(), "notes.txt", b"text"), vec![]).unwrap();
+        assert!(Vault::thumbnail(&session, &text, 100).is_err());
+    }
+
+    #[test]
+    fn recovery_codes_unlock_only_their_own_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let (session, codes) = new_vault(dir.path(), false);
+        let shouted: Vec<String> = codes.iter().map(|c| format!("  {} ", c.to_uppercase())).collect();
+
+        assert!(Vault::test_recovery(&session.vault_path, &codes).unwrap());
+        assert!(Vault::test_recovery(&session.vault_path, &shouted).unwrap());
+        let mut wrong = codes.clone();
+        wrong[0] = "not the code".to_string();
+        assert!(!Vault::test_recovery(&session.vault_path, &wrong).unwrap());
+
+        let other_dir = tempfile::tempdir().unwrap();
+        let (other, _) = new_vault(other_dir.path(), false);
+        assert!(!Vault::test_recovery(&other.vault_path, &codes).unwrap());
+    }
+
+    #[test]
+    fn vaults_are_discovered_from_their_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        let (session, _) = new_vault(&nested, true);
+        source_file(dir.path(), "config.json", br#"{"vault_id": "x"}"#);
+        source_file(dir.path(), "renamed.vault", b"not a vault");
+
+        let found = find_vaults(&dir.path().to_string_lossy(), &WalkOptions::default()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].vault_id, session.vault_id);
+        assert!(found[0].split_storage && found[0].has_recovery);
+
+        assert!(Vault::is_vault_file(&session.vault_path));
+        assert!(Vault::is_vault_file(&Vault::blobs_path(&session.vault_path)));
+        assert!(!Vault::is_vault_file(&dir.path().join("config.json")));
+    }
+
+    #[test]
+    fn one_issued_recovery_code_unlocks_the_vault_without_the_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, set_codes) = new_vault(dir.path(), false);
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"alpha"), vec![]).unwrap();
+        assert!(Vault::issue_recovery_codes(&mut session, "wrong password").is_err());
+        let replaced = Vault::issue_recovery_codes(&mut session, "hunter22").unwrap();
+        let codes = Vault::issue_recovery_codes(&mut session, "hunter22").unwrap();
+        let path = session.vault_path.clone();
+        drop(session);
+
+        // The password is forgotten; any single code, however it was typed, is enough
+        let mut recovered = Vault::open_with_recovery_code(&path, &format!(" {} ", codes[3].to_uppercase())).unwrap();
+        let out = dir.path().join("out.txt");
+        Vault::export_file(&mut recovered, &id, &out).unwrap();
+        assert_eq!(std::fs::read(&out).unwrap(), b"alpha");
+
+        assert!(Vault::open_with_recovery_code(&path, &replaced[0]).is_err());
+        assert!(Vault::open_with_recovery_code(&path, &set_codes[0]).is_err());
+        assert!(Vault::test_recovery(&path, &set_codes).unwrap());
+    }
+
+    #[test]
+    fn password_commands_change_the_open_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"alpha"), vec![]).unwrap();
+        let path = session.vault_path.clone();
+        let vault_id = session.vault_id.clone();
+        let state_mux: StateSafe = Default::default();
+        state_mux.lock().unwrap().vault_sessions.insert(vault_id.clone(), session);
+
+        with_password_session(&state_mux, &path, "hunter22", |session| Vault::delete_entry(session, &id)).unwrap();
+        assert!(with_password_session(&state_mux, &path, "wrong password", |_| Ok(())).is_err());
+
+        // A later save from the open session must not bring the entry back
+        with_session(&state_mux, &vault_id, |session| Vault::save_manifest(session)).unwrap();
+        let reopened = Vault::open_vault(&path, "hunter22").unwrap();
+        assert!(!reopened.manifest.entries.contains_key(&id));
+    }
+
+    #[test]
+    fn exports_outside_the_allowed_roots_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"alpha"), vec![]).unwrap();
+        let path = session.vault_path.clone();
+        let allowed = dir.path().join("allowed");
+        std::fs::create_dir(&allowed).unwrap();
+        let state_mux: StateSafe = Default::default();
+        state_mux.lock().unwrap().allowed_roots = vec![allowed.canonicalize().unwrap()];
+
+        let outside = dir.path().join("out.txt");
+        let err = export_entry(&state_mux, &path, "hunter22", &id, &outside.to_string_lossy()).unwrap_err();
+        assert!(err.contains("outside the allowed directories"), "{}", err);
+        assert!(!outside.exists());
+
+        let inside = allowed.join("out.txt");
+        export_entry(&state_mux, &path, "hunter22", &id, &inside.to_string_lossy()).unwrap();
+        assert_eq!(std::fs::read(&inside).unwrap(), b"alpha");
+    }
+
+    #[test]
+    fn truncating_the_log_archives_it_and_keeps_the_newest_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            Vault::import_file(&mut session, &source_file(dir.path(), name, name.as_bytes()), vec![]).unwrap();
+        }
+        let describe = |log: &AuditLog| format!("{} {}", log.timestamp, log.action);
+        let before: Vec<String> = session.manifest.access_log.iter().map(describe).collect();
+        assert!(before.len() > 3);
+
+        let archive_path = dir.path().join("log.archive");
+        let truncation = Vault::truncate_log(&mut session, 2, &archive_path).unwrap();
+        assert_eq!(truncation.archived, before.len());
+        assert_eq!(truncation.kept, 3);
+        assert!(Vault::truncate_log(&mut session, 2, &archive_path).is_err());
+
+        let archived = Vault::read_log_archive(&session, &archive_path).unwrap();
+        assert_eq!(archived[0].action, "vault_created");
+        assert_eq!(archived.len(), before.len());
+
+        // The manifest still decrypts and holds the two newest entries plus the truncation record
+        let reopened = Vault::open_vault(&session.vault_path, "hunter22").unwrap();
+        let log = &reopened.manifest.access_log;
+        let kept: Vec<String> = log[..2].iter().map(describe).collect();
+        assert_eq!(kept, before[before.len() - 2..]);
+        assert_eq!(log[2].action, "log_truncated");
+        assert_eq!(reopened.manifest.entries.len(), 3);
+    }
+
+    #[test]
+    fn entries_split_off_a_single_file_vault_read_back_from_split_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let mut ids = Vec::new();
+        for (name, data) in [("a.txt", "alpha"), ("b.txt", "bravo"), ("c.txt", "charlie")] {
+            ids.push(Vault::import_file(&mut session, &source_file(dir.path(), name, data.as_bytes()), vec![]).unwrap());
+        }
+
+        let new_path = dir.path().join("split.vault");
+        let moved = ids[..2].to_vec();
+        Vault::split_vault(&mut session, &new_path, "correct horse", &moved, Some(true)).unwrap();
+
+        let split = Vault::open_vault(&new_path, "correct horse").unwrap();
+        assert!(Vault::read_container(&new_path).unwrap().header.split_storage);
+        assert!(std::fs::metadata(Vault::blobs_path(&new_path)).unwrap().len() > 0);
+        assert_eq!(split.manifest.entries.len(), 2);
+        assert_eq!(Vault::decrypt_entry(&split, &ids[0]).unwrap(), b"alpha");
+        assert_eq!(Vault::decrypt_entry(&split, &ids[1]).unwrap(), b"bravo");
+
+        let original = Vault::open_vault(&session.vault_path, "hunter22").unwrap();
+        assert_eq!(original.manifest.entries.len(), 1);
+        assert_eq!(Vault::decrypt_entry(&original, &ids[2]).unwrap(), b"charlie");
+    }
+
+    #[test]
+    fn the_clipboard_is_cleared_only_while_it_holds_the_entry() {
+        assert!(clipboard_still_ours(Some("secret"), "secret"));
+        assert!(!clipboard_still_ours(Some("copied later"), "secret"));
+        assert!(!clipboard_still_ours(Some("secret\n"), "secret"));
+        assert!(!clipboard_still_ours(None, "secret"));
+    }
+
+    #[test]
+    fn only_crlf_damage_gets_the_line_ending_hint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("damaged.vault");
+        let open_err = |contents: &[u8]| {
+            std::fs::write(&path, contents).unwrap();
+            match Vault::open_vault(&path, "hunter22") {
+                Err(err) => err,
+                Ok(_) => panic!("a damaged container opened"),
+            }
+        };
+
+        let crlf = open_err(&[b"{\"version\":".as_slice(), VAULT_BOUNDARY_CRLF, b"manifest"].concat());
+        assert!(crlf.starts_with("VaultCorrupted: failed to parse header"));
+        assert!(crlf.contains("CRLF"));
+
+        for damaged in [
+            [b"{\"version\":".as_slice(), VAULT_BOUNDARY, b"manifest"].concat(),
+            b"NOTAVAULT".to_vec(),
+            b"{\"version\": 1, \"sal".to_vec(),
+        ] {
+            let err = open_err(&damaged);
+            assert!(err.starts_with("VaultCorrupted: "), "{}", err);
+            assert!(!err.contains("line endings") && !err.contains("CRLF"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn calibrated_argon2_params_stay_at_the_minimums_and_derive_keys() {
+        // A 1 ms target can't be met, so calibration bottoms out at the floor
+        let calibration = Vault::calibrate_argon2(1).unwrap();
+
+        assert!(calibration.memory_kib >= CALIBRATION_MIN_MEMORY_KIB);
+        assert!(calibration.iterations >= 1);
+        assert!(calibration.parallelism >= 1);
+        assert_eq!(
+            calibration.params,
+            format!("m={},t={},p={}", calibration.memory_kib, calibration.iterations, calibration.parallelism)
+        );
+        let salt = hex::encode([5u8; 16]);
+        assert_eq!(Vault::derive_key("pw", &salt, &calibration.params).unwrap().len(), 32);
+    }
+
+    #[test]
+    fn pins_survive_the_session_auto_locking() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), false);
+        let pinned = Vault::import_file(&mut session, &source_file(dir.path(), "old.txt", b"a"), vec![]).unwrap();
+        let unpinned = Vault::import_file(&mut session, &source_file(dir.path(), "new.txt", b"b"), vec![]).unwrap();
+        let (vault_id, vault_path) = (session.vault_id.clone(), session.vault_path.clone());
+        let state: StateSafe = Default::default();
+        state.lock().unwrap().vault_sessions.insert(vault_id.clone(), session);
+        with_session(&state, &vault_id, |session| Vault::set_pinned(session, &pinned, true, None)).unwrap();
+
+        // Idle past the timeout, so the next command locks and drops the session
+        let idle_since = Utc::now() - chrono::Duration::seconds(SESSION_INACTIVITY_SECS as i64 + 1);
+        state.lock().unwrap().vault_sessions.get_mut(&vault_id).unwrap().last_accessed = idle_since;
+        assert_eq!(with_session(&state, &vault_id, |_| Ok(())).unwrap_err(), "Vault session expired");
+        assert!(!state.lock().unwrap().vault_sessions.contains_key(&vault_id));
+
+        let reopened = Vault::open_vault(&vault_path, "hunter22").unwrap();
+        let order: Vec<String> = Vault::sorted_entries(&reopened.manifest).into_iter().map(|e| e.id).collect();
+        assert_eq!(order, [pinned.clone(), unpinned.clone()]);
+        assert!(reopened.manifest.entries[&pinned].pinned);
+        assert!(!reopened.manifest.entries[&unpinned].pinned);
+    }
+
+    #[test]
+    fn entries_export_from_a_split_storage_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut session, _) = new_vault(dir.path(), true);
+        let id = Vault::import_file(&mut session, &source_file(dir.path(), "a.txt", b"split export"), vec![]).unwrap();
+        assert!(session.manifest.entries[&id].blob.is_some());
+        let state_mux: StateSafe = Default::default();
+
+        let output = dir.path().join("out.txt");
+        export_entry(&state_mux, &session.vault_path, "hunter22", &id, &output.to_string_lossy()).unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), b"split export");
+    }
+}
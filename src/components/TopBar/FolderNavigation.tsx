@@ -301,47 +301,47 @@ export default function FolderNavigation({ onBackArrowClick, canGoBackward, onFo
         setCountsByType({});
         setCountsByExtension({});
 
-        const unlistenResults = await listen<any>("search_result", (event) => {
-            const payload = event.payload?.child ?? event.payload;
-            setResults((prev) => {
-                const newArr = prev ? [...prev] : [];
-                // insert or push; keep simple push for compact mode
-                newArr.push(payload);
-                setResultCount(newArr.length);
-                setSearchResults?.(newArr);
-                return newArr;
-            });
-        });
-
-        const unlistenFinished = await listen<any>("search_finished", (event) => {
-            const payload = event.payload as any;
-            if (typeof payload === 'number') {
-                setElapsedTime(payload);
-            } else if (payload && typeof payload === 'object') {
-                setElapsedTime(payload.elapsed_ms ?? 0);
-                setScannedCount(payload.scanned ?? 0);
-                setMatchedCount(payload.matched ?? 0);
-                setCountsByType(payload.counts_by_type ?? {});
-                setCountsByExtension(payload.counts_by_extension ?? {});
+        // All updates arrive as one tagged stream; see SearchEvent in search.rs.
+        // Only events tagged with this search's id are handled; earlier ones wait for the id.
+        let searchId: number | null = null;
+        const early: any[] = [];
+
+        const handleEvent = ({ type, data }: any) => {
+            if (type === "result") {
+                setResults((prev) => {
+                    const newArr = prev ? [...prev] : [];
+                    // insert or push; keep simple push for compact mode
+                    newArr.push(data.child);
+                    setResultCount(newArr.length);
+                    setSearchResults?.(newArr);
+                    return newArr;
+                });
+            } else if (type === "progress" || type === "finished") {
+                setScannedCount(data.scanned ?? 0);
+                setMatchedCount(data.matched ?? 0);
+                setCountsByType(data.counts_by_type ?? {});
+                setCountsByExtension(data.counts_by_extension ?? {});
+                if (type === "finished") {
+                    setElapsedTime(data.elapsed_ms ?? 0);
+                    setIsSearching(false);
+                    try { unlistenSearch(); } catch { }
+                }
+            } else if (type === "cancelled") {
+                // Replaced by a newer search; stop listening for this one
+                try { unlistenSearch(); } catch { }
             }
+        };
 
-            setIsSearching(false);
-            try { unlistenResults(); } catch { }
-            try { unlistenFinished(); } catch { }
-            try { unlistenProgress(); } catch { }
-        });
-
-        // listen for progress updates
-        const unlistenProgress = await listen<any>("search_progress", (event) => {
-            const p = event.payload as any;
-            setScannedCount(p.scanned ?? scannedCount);
-            setMatchedCount(p.matched ?? matchedCount);
-            setCountsByType(p.counts_by_type ?? countsByType);
-            setCountsByExtension(p.counts_by_extension ?? countsByExtension);
+        const unlistenSearch = await listen<any>("search_event", (event) => {
+            if (searchId === null) {
+                early.push(event.payload);
+            } else if (event.payload?.data?.search_id === searchId) {
+                handleEvent(event.payload);
+            }
         });
 
         try {
-            await invoke("search_directory", {
+            searchId = await invoke<number>("search_directory", {
                 query: query,
                 searchDirectory: currentDirectoryPath,
                 mountPnt: currentVolume,
@@ -349,12 +349,13 @@ export default function FolderNavigation({ onBackArrowClick, canGoBackward, onFo
                 acceptFiles: true,
                 acceptDirectories: true,
             });
+            early
+                .filter((payload) => payload?.data?.search_id === searchId)
+                .forEach(handleEvent);
         } catch (error) {
             console.error("Search error:", error);
             setIsSearching(false);
-            try { unlistenResults(); } catch { }
-            try { unlistenFinished(); } catch { }
-            try { unlistenProgress(); } catch { }
+            try { unlistenSearch(); } catch { }
         }
     }
 
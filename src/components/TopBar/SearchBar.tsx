@@ -36,10 +36,26 @@ export interface ISearchFilter {
 
 // Interface for backend streamed search results
 interface ScoredChild {
+  search_id: number;
   child: DirectoryContent;
   score: number;
 }
 
+interface SearchStats {
+  search_id: number;
+  scanned: number;
+  matched: number;
+  counts_by_type: Record<string, number>;
+  counts_by_extension: Record<string, number>;
+}
+
+type SearchEvent =
+  | { type: "result"; data: ScoredChild }
+  | { type: "directory_result"; data: { search_id: number; path: string; match_count: number; best_score: number } }
+  | { type: "progress"; data: SearchStats }
+  | { type: "finished"; data: SearchStats & { elapsed_ms: number } }
+  | { type: "cancelled"; data: { search_id: number; scanned: number; matched: number } };
+
 export default function SearchBar({
   currentDirectoryPath,
   currentVolume,
@@ -89,47 +105,57 @@ export default function SearchBar({
     setElapsedTime(0);
     setSearchResults([]);
 
-    const unlistenResults = await listen<ScoredChild>("search_result", (event) => {
-      setResults((prev) => {
-        const newArr = [...prev];
-        let i = 0;
-        while (i < newArr.length && newArr[i].score >= event.payload.score) i++;
-        newArr.splice(i, 0, event.payload);
-        setResultCount(newArr.length);
-        setSearchResults(newArr.map((r) => r.child));
-        return newArr;
-      });
-    });
+    // All updates arrive as one tagged stream; see SearchEvent in search.rs.
+    // Events from other searches share the stream, so only those tagged with this
+    // search's id are handled. Events arriving before the id is known are held back.
+    let searchId: number | null = null;
+    const early: SearchEvent[] = [];
 
-    const unlistenFinished = await listen<any>("search_finished", (event) => {
-      const payload = event.payload as any;
-      // payload is SearchFinished if backend sent stats, or a number for older behavior
-      if (typeof payload === 'number') {
-        setElapsedTime(payload);
-      } else if (payload && typeof payload === 'object') {
-        setElapsedTime(payload.elapsed_ms ?? 0);
-        setScannedCount(payload.scanned ?? 0);
-        setMatchedCount(payload.matched ?? 0);
-        setCountsByType(payload.counts_by_type ?? {});
-        setCountsByExtension(payload.counts_by_extension ?? {});
+    const handleEvent = ({ type, data }: SearchEvent) => {
+      switch (type) {
+        case "result":
+          setResults((prev) => {
+            const newArr = [...prev];
+            let i = 0;
+            while (i < newArr.length && newArr[i].score >= data.score) i++;
+            newArr.splice(i, 0, data);
+            setResultCount(newArr.length);
+            setSearchResults(newArr.map((r) => r.child));
+            return newArr;
+          });
+          break;
+        case "progress":
+          setScannedCount(data.scanned);
+          setMatchedCount(data.matched);
+          setCountsByType(data.counts_by_type);
+          setCountsByExtension(data.counts_by_extension);
+          break;
+        case "finished":
+          setElapsedTime(data.elapsed_ms);
+          setScannedCount(data.scanned);
+          setMatchedCount(data.matched);
+          setCountsByType(data.counts_by_type);
+          setCountsByExtension(data.counts_by_extension);
+          setIsSearching(false);
+          unlistenSearch();
+          break;
+        case "cancelled":
+          // Replaced by a newer search; stop listening for this one
+          unlistenSearch();
+          break;
       }
+    };
 
-      setIsSearching(false);
-      unlistenResults();
-      unlistenFinished();
-    });
-
-    // listen for progress updates
-    const unlistenProgress = await listen<any>("search_progress", (event) => {
-      const p = event.payload as any;
-      setScannedCount(p.scanned ?? scannedCount);
-      setMatchedCount(p.matched ?? matchedCount);
-      setCountsByType(p.counts_by_type ?? countsByType);
-      setCountsByExtension(p.counts_by_extension ?? countsByExtension);
+    const unlistenSearch = await listen<SearchEvent>("search_event", (event) => {
+      if (searchId === null) {
+        early.push(event.payload);
+      } else if (event.payload.data.search_id === searchId) {
+        handleEvent(event.payload);
+      }
     });
 
     try {
-      await invoke("search_directory", {
+      searchId = await invoke<number>("search_directory", {
         query: searchValue,
         searchDirectory: currentDirectoryPath,
         mountPnt: currentVolume,
@@ -137,12 +163,13 @@ export default function SearchBar({
         acceptFiles: searchFilter.acceptFiles,
         acceptDirectories: searchFilter.acceptDirectories,
       });
+      early
+        .filter((payload) => payload.data.search_id === searchId)
+        .forEach(handleEvent);
     } catch (error) {
       console.error("Search error:", error);
       setIsSearching(false);
-      unlistenResults();
-      unlistenFinished();
-      unlistenProgress();
+      unlistenSearch();
     }
   }
 
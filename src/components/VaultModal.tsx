@@ -616,10 +616,13 @@ export default function VaultModal({ isOpen, onClose }: VaultModalProps) {
               onClick={() => {
                 invoke("vault_generate_recovery_codes", {
                   vaultId: vaultId || "main",
-                }).then((codes) => {
-                  setRecoveryCodesShown(codes as string[]);
-                  setScreen("recovery");
-                });
+                  password: password,
+                })
+                  .then((codes) => {
+                    setRecoveryCodesShown(codes as string[]);
+                    setScreen("recovery");
+                  })
+                  .catch((err) => setError(`Failed to generate recovery codes: ${String(err)}`));
               }}
               className="w-full bg-purple-500 hover:bg-purple-600 text-white font-medium py-2 rounded-lg text-sm"
             >
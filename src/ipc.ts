@@ -13,8 +13,14 @@ interface RenameConfig {
   scheme?: string;
 }
 
+export interface DirectoryListing {
+  children: DirectoryContent[];
+  inaccessible: string[];
+}
+
 export async function openDirectory(path: string): Promise<DirectoryContent[]> {
-   return invoke("open_directory", { path });
+   const listing = await invoke<DirectoryListing>("open_directory", { path });
+   return listing.children;
 }
 
 export async function openFile(path: string): Promise<string> {